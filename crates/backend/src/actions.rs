@@ -1,9 +1,22 @@
-use proto::frontend::{Signal as FrontendSignal, SignalAction};
+use std::{io, path::Path, process::Command, thread, time::Duration};
+
+use log::error;
+use proto::{
+    backend::{CommandResponse, CommandStatus, SignalResult},
+    frontend::{
+        AffinityAction, IoClass, IoNiceAction, PowerAction, ReniceAction, ServiceAction,
+        ServiceOperation, Signal as FrontendSignal, SignalAction,
+    },
+};
 use sysinfo::{Pid, Signal};
 
-use crate::client::BackendContext;
+use crate::{client::BackendContext, getters};
+
+const ESRCH: i32 = 3;
+// Gives the response time to reach the frontend before the connection goes down
+const POWER_DELAY: Duration = Duration::from_secs(1);
 
-pub fn process_signal(mut ctx: BackendContext, action: SignalAction) {
+pub fn process_signal(mut ctx: BackendContext, action: SignalAction) -> SignalResult {
     let sys = &mut ctx.system();
 
     let signal = match action.signal {
@@ -11,11 +24,190 @@ pub fn process_signal(mut ctx: BackendContext, action: SignalAction) {
         FrontendSignal::Pause => Signal::Stop,
         FrontendSignal::Term => Signal::Term,
         FrontendSignal::Resume => Signal::Continue,
+        FrontendSignal::Hup => Signal::Hangup,
+        FrontendSignal::Int => Signal::Interrupt,
+        FrontendSignal::Usr1 => Signal::User1,
+        FrontendSignal::Usr2 => Signal::User2,
     };
 
     let Some(proc) = sys.system.process(Pid::from_u32(action.pid)) else {
-        return;
+        return SignalResult::NoSuchProcess;
+    };
+
+    match proc.kill_with(signal) {
+        Some(true) => SignalResult::Sent,
+        None => SignalResult::Unsupported,
+        // The process list can be stale, so the process may have exited since it was refreshed
+        Some(false) => {
+            let err = io::Error::last_os_error();
+            match err.raw_os_error() {
+                Some(ESRCH) => SignalResult::NoSuchProcess,
+                _ if err.kind() == io::ErrorKind::PermissionDenied => {
+                    SignalResult::PermissionDenied
+                }
+                _ => SignalResult::Failed(err.to_string()),
+            }
+        }
+    }
+}
+
+// These shell out to util-linux, so permission errors come back in stderr
+pub fn renice(_ctx: BackendContext, action: ReniceAction) -> CommandResponse {
+    if !(-20..=19).contains(&action.nice) {
+        return CommandResponse::Denied;
+    }
+
+    getters::run_command(Command::new("renice").args([
+        "-n",
+        &action.nice.to_string(),
+        "-p",
+        &action.pid.to_string(),
+    ]))
+}
+
+pub fn ionice(_ctx: BackendContext, action: IoNiceAction) -> CommandResponse {
+    if action.level > 7 {
+        return CommandResponse::Denied;
+    }
+
+    let mut cmd = Command::new("ionice");
+    match action.class {
+        IoClass::Realtime => cmd.args(["-c", "1", "-n", &action.level.to_string()]),
+        IoClass::BestEffort => cmd.args(["-c", "2", "-n", &action.level.to_string()]),
+        IoClass::Idle => cmd.args(["-c", "3"]),
+    };
+    cmd.args(["-p", &action.pid.to_string()]);
+
+    getters::run_command(&mut cmd)
+}
+
+pub fn affinity(_ctx: BackendContext, action: AffinityAction) -> CommandResponse {
+    if action.cpus.is_empty() {
+        return CommandResponse::Denied;
+    }
+
+    let cpus: Vec<String> = action.cpus.iter().map(|x| x.to_string()).collect();
+
+    getters::run_command(Command::new("taskset").args([
+        "--all-tasks",
+        "--cpu-list",
+        "--pid",
+        &cpus.join(","),
+        &action.pid.to_string(),
+    ]))
+}
+
+fn is_valid_unit_name(name: &str) -> bool {
+    // Prevent names from being interpreted as options or containing path separators
+    !name.is_empty()
+        && !name.starts_with('-')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "@._:-".contains(c))
+}
+
+pub fn service_action(_ctx: BackendContext, action: ServiceAction) -> CommandResponse {
+    if !is_valid_unit_name(&action.name) {
+        return CommandResponse::Denied;
+    }
+
+    let operation = match action.operation {
+        ServiceOperation::Start => "start",
+        ServiceOperation::Stop => "stop",
+        ServiceOperation::Restart => "restart",
+        ServiceOperation::Enable => "enable",
+        ServiceOperation::Disable => "disable",
+    };
+
+    getters::run_command(Command::new("systemctl").args([operation, "--", &action.name]))
+}
+
+pub fn power(_ctx: BackendContext, action: PowerAction) -> CommandResponse {
+    let verb = match action {
+        PowerAction::Reboot => "reboot",
+        PowerAction::Poweroff => "poweroff",
     };
 
-    proc.kill_with(signal);
+    // Nobody would be around to hear about a failure afterwards, so check that it would work first
+    let resp = getters::run_command(Command::new("systemctl").args(["--dry-run", verb]));
+
+    if let CommandResponse::Finished(output) = &resp
+        && output.status == CommandStatus::Exited(0)
+    {
+        thread::spawn(move || {
+            thread::sleep(POWER_DELAY);
+
+            if let Err(err) = Command::new("systemctl").arg(verb).status() {
+                error!("Failed to {verb}: {err}");
+            }
+        });
+    }
+
+    resp
+}
+
+fn is_valid_hostname(name: &str) -> bool {
+    // RFC 1123, which also prevents names from being interpreted as options
+    !name.is_empty()
+        && name.len() <= 253
+        && name.split('.').all(|label| {
+            (1..=63).contains(&label.len())
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        })
+}
+
+pub fn set_hostname(_ctx: BackendContext, name: String) -> CommandResponse {
+    if !is_valid_hostname(&name) {
+        return CommandResponse::Denied;
+    }
+
+    // DietPi's script also updates /etc/hosts, so the new name still resolves locally
+    let dietpi_script = Path::new("/boot/dietpi/func/change_hostname");
+    if dietpi_script.exists() {
+        getters::run_command(Command::new(dietpi_script).arg(&name))
+    } else {
+        getters::run_command(Command::new("hostnamectl").args(["set-hostname", "--", &name]))
+    }
+}
+
+pub fn set_timezone(_ctx: BackendContext, name: String) -> CommandResponse {
+    let valid = !name.is_empty()
+        && !name.starts_with(['-', '/'])
+        && !name.split('/').any(|x| x == "..")
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "/_-+".contains(c))
+        && Path::new("/usr/share/zoneinfo").join(&name).is_file();
+    if !valid {
+        return CommandResponse::Denied;
+    }
+
+    getters::run_command(Command::new("timedatectl").args(["set-timezone", "--", &name]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_valid_hostnames() {
+        assert!(is_valid_hostname("DietPi"));
+        assert!(is_valid_hostname("pi-4"));
+        assert!(is_valid_hostname("nas.home.lan"));
+        assert!(is_valid_hostname(&"a".repeat(63)));
+    }
+
+    #[test]
+    fn rejects_invalid_hostnames() {
+        assert!(!is_valid_hostname(""));
+        assert!(!is_valid_hostname("-n"));
+        assert!(!is_valid_hostname("pi-"));
+        assert!(!is_valid_hostname("my pi"));
+        assert!(!is_valid_hostname("pi..lan"));
+        assert!(!is_valid_hostname("pi_4"));
+        assert!(!is_valid_hostname(&"a".repeat(64)));
+        assert!(!is_valid_hostname(&["a"; 128].join(".")));
+    }
 }
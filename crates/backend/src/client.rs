@@ -1,16 +1,30 @@
-use std::sync::{Arc, Mutex};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Instant, SystemTime},
+};
 
 use anyhow::{Context, Result};
 use config::PROTOCOL_VERSION;
 use proto::{
     DashboardSocket,
-    backend::{ActionBackendMessage, BackendMessage, Handshake, ResponseBackendMessage},
-    frontend::{ActionFrontendMessage, FrontendMessage, RequestFrontendMessage},
+    backend::{
+        ActionBackendMessage, BackendMessage, Handshake, ResponseBackendMessage, UpdatesResponse,
+    },
+    frontend::{
+        ActionFrontendMessage, FrontendMessage, Job, LogFollowAction, RequestFrontendMessage,
+    },
 };
 use sysinfo::{Components, Disks, Networks, System};
 use tokio::{net::TcpStream, sync::mpsc};
 
-use crate::{SharedConfig, actions, getters};
+use crate::{
+    SharedConfig, actions, containers,
+    diskstats::{self, DiskCounters},
+    files, firewall, getters, journal, network, process, schedule,
+    terminal::TerminalInput,
+    updates, users,
+};
 
 macro_rules! getters {
     ($req:expr, $ctx:expr, {
@@ -32,6 +46,14 @@ pub struct SystemComponents {
     pub components: Components,
     pub disks: Disks,
     pub networks: Networks,
+    // Used to turn the traffic since the last refresh into a rate
+    pub networks_refreshed: Instant,
+    pub disk_counters: HashMap<(u32, u32), DiskCounters>,
+    pub disks_refreshed: Instant,
+    // Context switches and interrupts since boot
+    pub cpu_counters: (u64, u64),
+    pub cpu_refreshed: Instant,
+    pub updates_cache: Option<(Vec<Option<SystemTime>>, UpdatesResponse)>,
 }
 
 impl SystemComponents {
@@ -41,6 +63,12 @@ impl SystemComponents {
             components: Components::new_with_refreshed_list(),
             disks: Disks::new_with_refreshed_list(),
             networks: Networks::new_with_refreshed_list(),
+            networks_refreshed: Instant::now(),
+            disk_counters: diskstats::read(),
+            disks_refreshed: Instant::now(),
+            cpu_counters: getters::cpu_counters().unwrap_or_default(),
+            cpu_refreshed: Instant::now(),
+            updates_cache: None,
         }
     }
 }
@@ -50,7 +78,9 @@ pub struct BackendContext {
     pub config: SharedConfig,
     pub system: SharedSystem,
     pub socket_tx: mpsc::UnboundedSender<BackendMessage>,
-    pub term_tx: mpsc::UnboundedSender<Vec<u8>>,
+    pub term_tx: mpsc::UnboundedSender<TerminalInput>,
+    pub log_tx: mpsc::UnboundedSender<LogFollowAction>,
+    pub job_tx: mpsc::UnboundedSender<Job>,
 }
 
 impl BackendContext {
@@ -106,9 +136,11 @@ impl BackendClient {
 
     async fn send_handshake(&mut self) -> Result<()> {
         let nickname = self.context.config.nickname.clone();
+        let tags = self.context.config.tags.clone();
 
         let handshake = Handshake {
             nickname,
+            tags,
             version: PROTOCOL_VERSION,
         };
 
@@ -143,11 +175,47 @@ impl RequestHandler {
                     Mem => getters::memory,
                     Disk => getters::disks,
                     NetIO => getters::network_io,
-                    Processes => getters::processes,
+                    Processes(query) => getters::processes,
+                    ProcessDetail(pid) => process::process_detail,
+                    Signal(action) => actions::process_signal,
+                    Renice(action) => actions::renice,
+                    IoNice(action) => actions::ionice,
+                    Affinity(action) => actions::affinity,
                     Host => getters::host,
                     Software => getters::software,
                     Command(action) => getters::command,
                     Services => getters::services,
+                    ServiceAction(action) => actions::service_action,
+                    Logs(query) => journal::query,
+                    ListDir(action) => files::list_dir,
+                    ReadFile(action) => files::read_file,
+                    WriteFile(action) => files::write_file,
+                    CreateDir(path) => files::create_dir,
+                    Rename(action) => files::rename,
+                    Delete(path) => files::delete,
+                    Updates => updates::updates,
+                    Power(action) => actions::power,
+                    SetHostname(name) => actions::set_hostname,
+                    SetTimezone(name) => actions::set_timezone,
+                    Timezones => getters::timezones,
+                    Users => users::users,
+                    AuthorizedKeys(name) => users::authorized_keys,
+                    UserAction(action) => users::user_action,
+                    AuditLog => users::audit_log,
+                    Crontabs => schedule::crontabs,
+                    CronAction(action) => schedule::cron_action,
+                    Timers => schedule::timers,
+                    NetworkConfig => network::config,
+                    ApplyNetwork(change) => network::apply,
+                    ConfirmNetwork => network::confirm,
+                    WifiScan => network::wifi_scan,
+                    WifiConnect(action) => network::wifi_connect,
+                    Firewall => firewall::firewall,
+                    FirewallAction(action) => firewall::action,
+                    ConfirmFirewall => firewall::confirm,
+                    Containers => containers::containers,
+                    ContainerAction(action) => containers::container_action,
+                    ContainerLogs(id) => containers::logs,
                 });
 
                 let resp = BackendMessage::Response(id, resp);
@@ -155,12 +223,32 @@ impl RequestHandler {
             }
             FrontendMessage::Action(msg) => match msg {
                 ActionFrontendMessage::Terminal(data) => {
-                    let _ = self.context.term_tx.send(data);
+                    let _ = self.context.term_tx.send(TerminalInput::Data(data));
+                }
+                ActionFrontendMessage::ContainerExec(id) => {
+                    match containers::exec_command(&self.context.config, &id) {
+                        Ok((program, args)) => {
+                            let _ = self
+                                .context
+                                .term_tx
+                                .send(TerminalInput::Spawn(program, args));
+                        }
+                        // Show the error where the shell would have been
+                        Err(err) => {
+                            let msg =
+                                ActionBackendMessage::Terminal(format!("\r\n{err}\r\n").into());
+                            let _ = self.context.socket_tx.send(BackendMessage::Action(msg));
+                        }
+                    }
+                }
+                ActionFrontendMessage::LogFollow(action) => {
+                    let _ = self.context.log_tx.send(action);
+                }
+                ActionFrontendMessage::StartJob(job) => {
+                    let _ = self.context.job_tx.send(job);
                 }
-                ActionFrontendMessage::Signal(action) => {
-                    tokio::task::spawn_blocking(|| actions::process_signal(ctx, action))
-                        .await
-                        .unwrap()
+                ActionFrontendMessage::CancelUpload(action) => {
+                    files::cancel_upload(&self.context, action);
                 }
             },
         }
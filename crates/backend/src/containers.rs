@@ -0,0 +1,504 @@
+use std::{
+    collections::BTreeSet,
+    env,
+    io::{Read, Write},
+    os::unix::net::UnixStream,
+    path::PathBuf,
+    thread,
+    time::Duration,
+};
+
+use proto::{
+    backend::{ContainerInfo, ContainerStats, ContainersResponse, ImageInfo},
+    frontend::{ContainerAction, ContainerOperation},
+};
+use serde_json::Value;
+
+use crate::{SharedConfig, client::BackendContext};
+
+const DEFAULT_SOCKETS: &[&str] = &[
+    "/run/docker.sock",
+    "/var/run/docker.sock",
+    "/run/podman/podman.sock",
+];
+
+// Stopping a container waits up to 10 seconds for it to exit before killing it
+const API_TIMEOUT: Duration = Duration::from_secs(30);
+
+// Keep the response well within a single frame
+const MAX_ITEMS: usize = 200;
+const LOG_LINES: u32 = 500;
+const MAX_LOG_BYTES: usize = 48 * 1024;
+
+struct ApiResponse {
+    status: u16,
+    body: Vec<u8>,
+}
+
+fn socket_path(config: &SharedConfig) -> Result<PathBuf, String> {
+    if !config.container_socket.is_empty() {
+        return Ok(PathBuf::from(&config.container_socket));
+    }
+
+    DEFAULT_SOCKETS
+        .iter()
+        .map(PathBuf::from)
+        .find(|x| x.exists())
+        .ok_or_else(|| "no Docker or Podman socket found".into())
+}
+
+fn decode_chunked(mut body: &[u8]) -> Vec<u8> {
+    let mut decoded = Vec::with_capacity(body.len());
+
+    while let Some(line_end) = body.windows(2).position(|x| x == b"\r\n") {
+        let size_line = String::from_utf8_lossy(&body[..line_end]);
+        let size_hex = size_line.split(';').next().unwrap_or_default().trim();
+        let Ok(size) = usize::from_str_radix(size_hex, 16) else {
+            break;
+        };
+
+        body = &body[line_end + 2..];
+        if size == 0 || size > body.len() {
+            break;
+        }
+
+        decoded.extend_from_slice(&body[..size]);
+        body = body.get(size + 2..).unwrap_or_default();
+    }
+
+    decoded
+}
+
+// A minimal HTTP/1.1 client, which is all the Engine API needs
+fn request(config: &SharedConfig, method: &str, path: &str) -> Result<ApiResponse, String> {
+    let socket = socket_path(config)?;
+    let mut stream = UnixStream::connect(&socket)
+        .map_err(|err| format!("failed to connect to {}: {err}", socket.display()))?;
+    stream
+        .set_read_timeout(Some(API_TIMEOUT))
+        .map_err(|err| err.to_string())?;
+
+    write!(
+        stream,
+        "{method} {path} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\nContent-Length: 0\r\n\r\n"
+    )
+    .map_err(|err| err.to_string())?;
+
+    let mut raw = Vec::new();
+    stream
+        .read_to_end(&mut raw)
+        .map_err(|err| format!("failed to read response: {err}"))?;
+
+    let header_end = raw
+        .windows(4)
+        .position(|x| x == b"\r\n\r\n")
+        .ok_or("invalid response from the container engine")?;
+    let head = String::from_utf8_lossy(&raw[..header_end]);
+
+    let status = head
+        .lines()
+        .next()
+        .and_then(|line| line.split_whitespace().nth(1))
+        .and_then(|x| x.parse().ok())
+        .ok_or("invalid response from the container engine")?;
+    let chunked = head.lines().any(|line| {
+        let line = line.to_ascii_lowercase();
+        line.starts_with("transfer-encoding:") && line.contains("chunked")
+    });
+
+    let body = &raw[header_end + 4..];
+    let body = if chunked {
+        decode_chunked(body)
+    } else {
+        body.to_vec()
+    };
+
+    Ok(ApiResponse { status, body })
+}
+
+fn check_status(resp: ApiResponse) -> Result<ApiResponse, String> {
+    // 304 means the container was already in the requested state
+    if (200..300).contains(&resp.status) || resp.status == 304 {
+        return Ok(resp);
+    }
+
+    let message = serde_json::from_slice::<Value>(&resp.body)
+        .ok()
+        .and_then(|x| x["message"].as_str().map(String::from));
+
+    Err(message.unwrap_or_else(|| format!("request failed with status {}", resp.status)))
+}
+
+fn get_json(config: &SharedConfig, path: &str) -> Result<Value, String> {
+    let resp = check_status(request(config, "GET", path)?)?;
+
+    serde_json::from_slice(&resp.body).map_err(|err| format!("invalid response: {err}"))
+}
+
+fn is_valid_id(id: &str) -> bool {
+    // Covers both IDs and names, and keeps them safe to put in a path
+    (1..=128).contains(&id.len())
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "_.-".contains(c))
+        && !id.starts_with(['-', '.'])
+}
+
+fn short_id(id: &str) -> String {
+    id.trim_start_matches("sha256:").chars().take(12).collect()
+}
+
+fn format_ports(ports: &Value) -> Vec<String> {
+    // Ports published on both IPv4 and IPv6 show up twice, so collect them into a set
+    let ports: BTreeSet<_> = ports
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|port| {
+            let private = port["PrivatePort"].as_u64()?;
+            let protocol = port["Type"].as_str().unwrap_or("tcp");
+
+            Some(match port["PublicPort"].as_u64() {
+                Some(public) => format!("{public}->{private}/{protocol}"),
+                None => format!("{private}/{protocol}"),
+            })
+        })
+        .collect();
+
+    ports.into_iter().collect()
+}
+
+fn stats(config: &SharedConfig, id: &str) -> Option<ContainerStats> {
+    let stats = get_json(config, &format!("/containers/{id}/stats?stream=false")).ok()?;
+
+    let cpu = &stats["cpu_stats"];
+    let precpu = &stats["precpu_stats"];
+    let cpu_delta = cpu["cpu_usage"]["total_usage"].as_f64()?
+        - precpu["cpu_usage"]["total_usage"].as_f64().unwrap_or(0.);
+    let system_delta = cpu["system_cpu_usage"].as_f64().unwrap_or(0.)
+        - precpu["system_cpu_usage"].as_f64().unwrap_or(0.);
+    let online_cpus = cpu["online_cpus"].as_f64().unwrap_or(1.);
+
+    let cpu = if system_delta > 0. {
+        (cpu_delta / system_delta * online_cpus * 100.) as f32
+    } else {
+        0.
+    };
+
+    // Page cache is counted in the usage, but can be reclaimed, so it's left out like `docker stats` does
+    let memory = &stats["memory_stats"];
+    let cache = memory["stats"]["inactive_file"]
+        .as_u64()
+        .or_else(|| memory["stats"]["cache"].as_u64())
+        .unwrap_or(0);
+    let mem_used = memory["usage"].as_u64().unwrap_or(0).saturating_sub(cache);
+
+    Some(ContainerStats {
+        cpu,
+        mem_used,
+        mem_limit: memory["limit"].as_u64().unwrap_or(0),
+    })
+}
+
+fn engine_name(config: &SharedConfig) -> String {
+    let Ok(version) = get_json(config, "/version") else {
+        return "Unknown".into();
+    };
+
+    let name = version["Platform"]["Name"]
+        .as_str()
+        .filter(|x| !x.is_empty())
+        .unwrap_or("Container engine");
+    let number = version["Version"].as_str().unwrap_or_default();
+
+    format!("{name} {number}").trim().to_string()
+}
+
+pub fn containers(ctx: BackendContext) -> Result<ContainersResponse, String> {
+    let config = &ctx.config;
+
+    let list = get_json(config, "/containers/json?all=true")?;
+    let mut containers: Vec<ContainerInfo> = list
+        .as_array()
+        .into_iter()
+        .flatten()
+        .take(MAX_ITEMS)
+        .map(|container| {
+            let name = container["Names"][0]
+                .as_str()
+                .unwrap_or_default()
+                .trim_start_matches('/');
+
+            ContainerInfo {
+                id: short_id(container["Id"].as_str().unwrap_or_default()),
+                name: name.to_string(),
+                image: container["Image"].as_str().unwrap_or_default().to_string(),
+                state: container["State"].as_str().unwrap_or_default().to_string(),
+                status: container["Status"].as_str().unwrap_or_default().to_string(),
+                ports: format_ports(&container["Ports"]),
+                stats: None,
+            }
+        })
+        .collect();
+
+    // Getting stats takes a second per container, since the engine waits for a second sample
+    let results: Vec<_> = thread::scope(|scope| {
+        let handles: Vec<_> = containers
+            .iter()
+            .enumerate()
+            .filter(|(_, container)| container.state == "running")
+            .map(|(idx, container)| (idx, scope.spawn(|| stats(config, &container.id))))
+            .collect();
+
+        handles
+            .into_iter()
+            .map(|(idx, handle)| (idx, handle.join().ok().flatten()))
+            .collect()
+    });
+    for (idx, stats) in results {
+        containers[idx].stats = stats;
+    }
+
+    let images = get_json(config, "/images/json")?;
+    let images = images
+        .as_array()
+        .into_iter()
+        .flatten()
+        .take(MAX_ITEMS)
+        .map(|image| ImageInfo {
+            id: short_id(image["Id"].as_str().unwrap_or_default()),
+            tags: image["RepoTags"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(|x| x.as_str())
+                .filter(|x| *x != "<none>:<none>")
+                .map(String::from)
+                .collect(),
+            size: image["Size"].as_u64().unwrap_or(0),
+            created: image["Created"].as_u64().unwrap_or(0),
+        })
+        .collect();
+
+    Ok(ContainersResponse {
+        engine: engine_name(config),
+        containers,
+        images,
+    })
+}
+
+pub fn container_action(ctx: BackendContext, action: ContainerAction) -> Result<(), String> {
+    if !is_valid_id(&action.id) {
+        return Err(format!("invalid container: {}", action.id));
+    }
+
+    let id = &action.id;
+    let (method, path) = match action.operation {
+        ContainerOperation::Start => ("POST", format!("/containers/{id}/start")),
+        ContainerOperation::Stop => ("POST", format!("/containers/{id}/stop")),
+        ContainerOperation::Restart => ("POST", format!("/containers/{id}/restart")),
+        ContainerOperation::Remove => ("DELETE", format!("/containers/{id}")),
+    };
+
+    check_status(request(&ctx.config, method, &path)?).map(|_| ())
+}
+
+// Without a TTY, output is split into frames with an 8 byte header saying which stream it's from
+fn demux_logs(body: &[u8]) -> Vec<u8> {
+    let is_header = |x: &[u8]| x.len() >= 8 && x[0] <= 2 && x[1..4] == [0, 0, 0];
+    if !is_header(body) {
+        return body.to_vec();
+    }
+
+    let mut output = Vec::with_capacity(body.len());
+    let mut rest = body;
+    while is_header(rest) {
+        let size = u32::from_be_bytes([rest[4], rest[5], rest[6], rest[7]]) as usize;
+        let end = (8 + size).min(rest.len());
+
+        output.extend_from_slice(&rest[8..end]);
+        rest = &rest[end..];
+    }
+
+    output
+}
+
+pub fn logs(ctx: BackendContext, id: String) -> Result<String, String> {
+    if !is_valid_id(&id) {
+        return Err(format!("invalid container: {id}"));
+    }
+
+    let path = format!("/containers/{id}/logs?stdout=true&stderr=true&tail={LOG_LINES}");
+    let resp = check_status(request(&ctx.config, "GET", &path)?)?;
+
+    let logs = String::from_utf8_lossy(&demux_logs(&resp.body)).into_owned();
+    if logs.len() <= MAX_LOG_BYTES {
+        return Ok(logs);
+    }
+
+    // Keep the end of the logs, starting from a whole line
+    let mut start = logs.len() - MAX_LOG_BYTES;
+    while !logs.is_char_boundary(start) {
+        start += 1;
+    }
+    let trimmed = &logs[start..];
+    let trimmed = trimmed.split_once('\n').map_or(trimmed, |(_, rest)| rest);
+
+    Ok(trimmed.to_string())
+}
+
+fn find_program(name: &str) -> Option<PathBuf> {
+    let path = env::var_os("PATH")?;
+
+    env::split_paths(&path)
+        .map(|dir| dir.join(name))
+        .find(|x| x.is_file())
+}
+
+// Returns the program and arguments used to open a shell in the container
+pub fn exec_command(config: &SharedConfig, id: &str) -> Result<(PathBuf, Vec<String>), String> {
+    if !is_valid_id(id) {
+        return Err(format!("invalid container: {id}"));
+    }
+
+    let socket = socket_path(config)?;
+    let shell_args = [
+        "exec".into(),
+        "-it".into(),
+        id.into(),
+        "sh".into(),
+        "-c".into(),
+        "if command -v bash > /dev/null; then exec bash; else exec sh; fi".into(),
+    ];
+
+    let is_podman = socket.to_string_lossy().contains("podman");
+    let docker = find_program("docker").filter(|_| !is_podman);
+
+    if let Some(docker) = docker {
+        // Make sure the CLI talks to the same engine as the page
+        let mut args = vec!["-H".into(), format!("unix://{}", socket.display())];
+        args.extend(shell_args);
+        Ok((docker, args))
+    } else if let Some(podman) = find_program("podman") {
+        Ok((podman, shell_args.to_vec()))
+    } else {
+        Err("neither the docker nor the podman command is installed".into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io::BufRead, io::BufReader, os::unix::net::UnixListener, sync::Arc};
+
+    use config::backend::BackendConfig;
+
+    use super::*;
+
+    // Serves a single canned response, and returns the request line it received
+    fn serve_once(response: &'static [u8]) -> (SharedConfig, thread::JoinHandle<String>) {
+        let path = env::temp_dir().join(format!(
+            "dietpi-dashboard-test-{}-{:?}.sock",
+            std::process::id(),
+            thread::current().id()
+        ));
+        let _ = std::fs::remove_file(&path);
+        let listener = UnixListener::bind(&path).unwrap();
+
+        let config = Arc::new(BackendConfig {
+            container_socket: path.to_string_lossy().into_owned(),
+            ..Default::default()
+        });
+
+        let handle = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+            let mut line = String::new();
+            while reader.read_line(&mut line).unwrap() > 0 && line != "\r\n" {
+                line.clear();
+            }
+
+            stream.write_all(response).unwrap();
+            let _ = std::fs::remove_file(&path);
+            request_line.trim_end().to_string()
+        });
+
+        (config, handle)
+    }
+
+    #[test]
+    fn decodes_chunked_body() {
+        let body = b"5\r\nhello\r\n7;ext=1\r\n, world\r\n0\r\n\r\n";
+        assert_eq!(decode_chunked(body), b"hello, world");
+    }
+
+    #[test]
+    fn stops_at_truncated_chunk() {
+        let body = b"5\r\nhello\r\nff\r\nshort";
+        assert_eq!(decode_chunked(body), b"hello");
+        assert_eq!(decode_chunked(b"zz\r\nhello\r\n"), b"");
+    }
+
+    #[test]
+    fn reads_plain_response() {
+        let (config, server) = serve_once(
+            b"HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: 2\r\n\r\n[]",
+        );
+
+        let resp = request(&config, "GET", "/containers/json?all=true").unwrap();
+
+        assert_eq!(resp.status, 200);
+        assert_eq!(resp.body, b"[]");
+        assert_eq!(
+            server.join().unwrap(),
+            "GET /containers/json?all=true HTTP/1.1"
+        );
+    }
+
+    #[test]
+    fn reads_chunked_response() {
+        let (config, server) = serve_once(
+            b"HTTP/1.1 200 OK\r\nTransfer-Encoding: Chunked\r\n\r\n2\r\n[{\r\n2\r\n}]\r\n0\r\n\r\n",
+        );
+
+        let resp = request(&config, "POST", "/containers/abc/start").unwrap();
+
+        assert_eq!(resp.body, b"[{}]");
+        assert_eq!(
+            server.join().unwrap(),
+            "POST /containers/abc/start HTTP/1.1"
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_response() {
+        let (config, server) = serve_once(b"not http");
+
+        assert!(request(&config, "GET", "/version").is_err());
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn checks_status() {
+        let ok = |status| ApiResponse {
+            status,
+            body: Vec::new(),
+        };
+        assert!(check_status(ok(204)).is_ok());
+        assert!(check_status(ok(304)).is_ok());
+
+        let err = check_status(ApiResponse {
+            status: 404,
+            body: br#"{"message":"No such container: abc"}"#.to_vec(),
+        });
+        assert_eq!(err.err().unwrap(), "No such container: abc");
+
+        let err = check_status(ApiResponse {
+            status: 500,
+            body: b"oops".to_vec(),
+        });
+        assert_eq!(err.err().unwrap(), "request failed with status 500");
+    }
+}
@@ -0,0 +1,49 @@
+use std::{collections::HashMap, fs};
+
+// /proc/diskstats always counts in 512 byte sectors, regardless of the device's sector size
+const SECTOR_SIZE: u64 = 512;
+
+#[derive(Clone, Copy, Default)]
+pub struct DiskCounters {
+    pub reads: u64,
+    pub writes: u64,
+    pub read_bytes: u64,
+    pub written_bytes: u64,
+}
+
+impl DiskCounters {
+    // Counters can go backwards if a device is removed and another takes its place
+    pub fn since(self, prev: Self) -> Self {
+        Self {
+            reads: self.reads.saturating_sub(prev.reads),
+            writes: self.writes.saturating_sub(prev.writes),
+            read_bytes: self.read_bytes.saturating_sub(prev.read_bytes),
+            written_bytes: self.written_bytes.saturating_sub(prev.written_bytes),
+        }
+    }
+}
+
+// Reads I/O counters for every block device and partition, keyed by (major, minor)
+pub fn read() -> HashMap<(u32, u32), DiskCounters> {
+    let stats = fs::read_to_string("/proc/diskstats").unwrap_or_default();
+
+    stats
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let num = |i: usize| fields.get(i)?.parse::<u64>().ok();
+
+            let major = fields.first()?.parse().ok()?;
+            let minor = fields.get(1)?.parse().ok()?;
+
+            let counters = DiskCounters {
+                reads: num(3)?,
+                read_bytes: num(5)? * SECTOR_SIZE,
+                writes: num(7)?,
+                written_bytes: num(9)? * SECTOR_SIZE,
+            };
+
+            Some(((major, minor), counters))
+        })
+        .collect()
+}
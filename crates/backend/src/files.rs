@@ -0,0 +1,356 @@
+use std::{
+    collections::HashMap,
+    fs::{self, File, OpenOptions},
+    io::{self, Read, Seek, SeekFrom, Write},
+    os::unix::fs::{MetadataExt, OpenOptionsExt},
+    path::{Path, PathBuf},
+};
+
+use proto::{
+    MAX_CHUNK_SIZE,
+    backend::{DirEntryInfo, DirResponse, FileChunk, FileKind},
+    frontend::{CancelUploadAction, ListDirAction, ReadFileAction, RenameAction, WriteFileAction},
+};
+use rustix::fs::OFlags;
+
+use crate::client::BackendContext;
+
+// Directory listings are paged so they fit in a single frame
+const LIST_PAGE_BYTES: usize = 40 * 1024;
+const UPLOAD_SUFFIX: &str = ".dashboard-upload";
+
+fn canonical_roots(roots: &[String]) -> Vec<PathBuf> {
+    roots
+        .iter()
+        .filter_map(|root| fs::canonicalize(root).ok())
+        .collect()
+}
+
+// Writable roots can be read as well, so they don't need to be listed twice
+fn roots(ctx: &BackendContext) -> Vec<PathBuf> {
+    let mut roots = canonical_roots(&ctx.config.file_roots);
+    roots.extend(canonical_roots(&ctx.config.writable_file_roots));
+    roots
+}
+
+fn is_writable(ctx: &BackendContext, path: &Path) -> bool {
+    canonical_roots(&ctx.config.writable_file_roots)
+        .iter()
+        .any(|root| path.starts_with(root))
+}
+
+// Resolves a path and makes sure it stays within the configured roots.
+// If `follow` is false, only the parent is canonicalized, so operations act on symlinks themselves.
+fn resolve(ctx: &BackendContext, path: &str, follow: bool) -> Result<PathBuf, String> {
+    let path = Path::new(path);
+
+    if !path.is_absolute() {
+        return Err("path must be absolute".into());
+    }
+
+    let resolved = if follow {
+        fs::canonicalize(path).map_err(|err| err.to_string())?
+    } else {
+        let parent = path.parent().ok_or("invalid path")?;
+        let name = path.file_name().ok_or("invalid path")?;
+        fs::canonicalize(parent)
+            .map_err(|err| err.to_string())?
+            .join(name)
+    };
+
+    if roots(ctx).iter().any(|root| resolved.starts_with(root)) {
+        Ok(resolved)
+    } else {
+        Err("path is outside of the allowed directories".into())
+    }
+}
+
+// The backend runs as root, so a symlink planted in a writable root must never be written through
+pub fn open_nofollow(path: &Path, options: &mut OpenOptions) -> io::Result<File> {
+    let file = options
+        .custom_flags(OFlags::NOFOLLOW.bits() as i32)
+        .open(path)?;
+
+    if !file.metadata()?.is_file() {
+        return Err(io::Error::other("not a regular file"));
+    }
+
+    Ok(file)
+}
+
+fn upload_path(path: &Path, upload_id: u64) -> PathBuf {
+    let mut upload_path = path.as_os_str().to_owned();
+    upload_path.push(format!(".{upload_id:016x}{UPLOAD_SUFFIX}"));
+    PathBuf::from(upload_path)
+}
+
+// Like `resolve`, but only allows paths inside writable roots, and not the roots themselves
+fn resolve_entry(ctx: &BackendContext, path: &str) -> Result<PathBuf, String> {
+    let resolved = resolve(ctx, path, false)?;
+
+    if roots(ctx).contains(&resolved) {
+        return Err("cannot modify a root directory".into());
+    }
+    if !is_writable(ctx, &resolved) {
+        return Err("path is in a read-only directory".into());
+    }
+
+    Ok(resolved)
+}
+
+fn mtime_nanos(meta: &fs::Metadata) -> u64 {
+    meta.mtime() as u64 * 1_000_000_000 + meta.mtime_nsec() as u64
+}
+
+// Parses /etc/passwd or /etc/group into a map of IDs to names
+pub fn id_names(path: &str) -> HashMap<u32, String> {
+    let file = fs::read_to_string(path).unwrap_or_default();
+
+    file.lines()
+        .filter_map(|line| {
+            let mut fields = line.split(':');
+            let name = fields.next()?;
+            let id = fields.nth(1)?.parse().ok()?;
+            Some((id, name.to_string()))
+        })
+        .collect()
+}
+
+fn list_roots(ctx: &BackendContext) -> DirResponse {
+    let config = &ctx.config;
+    let entries = config
+        .file_roots
+        .iter()
+        .chain(&config.writable_file_roots)
+        .filter_map(|root| {
+            let meta = fs::metadata(root).ok()?;
+
+            Some(DirEntryInfo {
+                name: root.clone(),
+                kind: FileKind::Dir,
+                size: meta.size(),
+                mode: meta.mode(),
+                owner: String::new(),
+                group: String::new(),
+                mtime: meta.mtime(),
+            })
+        })
+        .collect();
+
+    DirResponse {
+        path: String::new(),
+        entries,
+        next_offset: None,
+        writable: false,
+    }
+}
+
+pub fn list_dir(ctx: BackendContext, action: ListDirAction) -> Result<DirResponse, String> {
+    // An empty path lists the roots themselves
+    if action.path.is_empty() {
+        return Ok(list_roots(&ctx));
+    }
+
+    let path = resolve(&ctx, &action.path, true)?;
+
+    let users = id_names("/etc/passwd");
+    let groups = id_names("/etc/group");
+
+    let mut entries: Vec<_> = fs::read_dir(&path)
+        .map_err(|err| err.to_string())?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let meta = entry.metadata().ok()?;
+            let file_type = meta.file_type();
+
+            let kind = if file_type.is_dir() {
+                FileKind::Dir
+            } else if file_type.is_file() {
+                FileKind::File
+            } else if file_type.is_symlink() {
+                FileKind::Symlink
+            } else {
+                FileKind::Other
+            };
+
+            let unknown_id = |id: u32| id.to_string();
+
+            Some(DirEntryInfo {
+                name: entry.file_name().to_string_lossy().into(),
+                kind,
+                size: meta.size(),
+                mode: meta.mode(),
+                owner: users
+                    .get(&meta.uid())
+                    .cloned()
+                    .unwrap_or_else(|| unknown_id(meta.uid())),
+                group: groups
+                    .get(&meta.gid())
+                    .cloned()
+                    .unwrap_or_else(|| unknown_id(meta.gid())),
+                mtime: meta.mtime(),
+            })
+        })
+        .collect();
+
+    // Sort so that paging is stable, with directories first
+    entries.sort_by(|a, b| a.kind.cmp(&b.kind).then_with(|| a.name.cmp(&b.name)));
+
+    let mut page = Vec::new();
+    let mut page_bytes = 0;
+
+    for entry in entries.into_iter().skip(action.offset as usize) {
+        if page_bytes >= LIST_PAGE_BYTES {
+            break;
+        }
+
+        page_bytes += entry.name.len() + entry.owner.len() + entry.group.len() + 32;
+        page.push(entry);
+    }
+
+    let next_offset = (page_bytes >= LIST_PAGE_BYTES).then(|| action.offset + page.len() as u32);
+
+    Ok(DirResponse {
+        writable: is_writable(&ctx, &path),
+        path: path.to_string_lossy().into(),
+        entries: page,
+        next_offset,
+    })
+}
+
+pub fn read_file(ctx: BackendContext, action: ReadFileAction) -> Result<FileChunk, String> {
+    let path = resolve(&ctx, &action.path, true)?;
+
+    let mut file = File::open(path).map_err(|err| err.to_string())?;
+    let meta = file.metadata().map_err(|err| err.to_string())?;
+
+    file.seek(SeekFrom::Start(action.offset))
+        .map_err(|err| err.to_string())?;
+
+    let len = action.len.min(MAX_CHUNK_SIZE);
+    let mut data = Vec::with_capacity(len as usize);
+
+    file.take(len.into())
+        .read_to_end(&mut data)
+        .map_err(|err| err.to_string())?;
+
+    Ok(FileChunk {
+        data,
+        total_size: meta.len(),
+        mtime: mtime_nanos(&meta),
+    })
+}
+
+pub fn write_file(ctx: BackendContext, action: WriteFileAction) -> Result<(), String> {
+    let path = resolve_entry(&ctx, &action.path)?;
+
+    // Chunks are written next to the target and moved into place at the end,
+    // so a failed upload never leaves a half-written file behind
+    let upload_path = upload_path(&path, action.upload_id);
+
+    let result = write_upload(&path, &upload_path, &action);
+    if result.is_err() {
+        let _ = fs::remove_file(&upload_path);
+    }
+
+    result
+}
+
+// Copies to a new file and renames it over the backup, so neither path is followed if it's a symlink
+fn write_backup(path: &Path, upload_id: u64) -> io::Result<()> {
+    let mut backup_path = path.as_os_str().to_owned();
+    backup_path.push(".bak");
+    let backup_path = PathBuf::from(backup_path);
+    let tmp_path = upload_path(&backup_path, upload_id);
+
+    let mut src = open_nofollow(path, OpenOptions::new().read(true))?;
+    let mut dst = open_nofollow(&tmp_path, OpenOptions::new().write(true).create_new(true))?;
+
+    let result = io::copy(&mut src, &mut dst)
+        .and_then(|_| dst.set_permissions(src.metadata()?.permissions()))
+        .and_then(|_| fs::rename(&tmp_path, &backup_path));
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp_path);
+    }
+
+    result
+}
+
+fn write_upload(path: &Path, upload_path: &Path, action: &WriteFileAction) -> Result<(), String> {
+    // The first chunk creates the file, so whatever is already there is never written to
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(action.offset == 0);
+    let mut file = open_nofollow(upload_path, &mut options).map_err(|err| err.to_string())?;
+
+    // A hard link would point the rest of the upload at another file just like a symlink
+    let upload_meta = file.metadata().map_err(|err| err.to_string())?;
+    if upload_meta.nlink() != 1 {
+        return Err("upload was replaced while it was being written".into());
+    }
+
+    file.seek(SeekFrom::Start(action.offset))
+        .and_then(|_| file.write_all(&action.data))
+        .map_err(|err| err.to_string())?;
+
+    if action.last {
+        // Only regular files are replaced, a symlink could point anywhere
+        let current_meta = match fs::symlink_metadata(path) {
+            Ok(meta) if meta.is_file() => Some(meta),
+            Ok(_) => return Err("can only replace regular files".into()),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => None,
+            Err(err) => return Err(err.to_string()),
+        };
+
+        if let Some(expected_mtime) = action.expected_mtime
+            && current_meta.as_ref().map(mtime_nanos) != Some(expected_mtime)
+        {
+            return Err("file was modified after it was opened".into());
+        }
+
+        if let Some(meta) = current_meta {
+            if action.backup {
+                write_backup(path, action.upload_id)
+                    .map_err(|err| format!("failed to create backup: {err}"))?;
+            }
+
+            // Keep the permissions of the file being replaced
+            let _ = file.set_permissions(meta.permissions());
+        }
+
+        fs::rename(upload_path, path).map_err(|err| err.to_string())?;
+    }
+
+    Ok(())
+}
+
+pub fn cancel_upload(ctx: &BackendContext, action: CancelUploadAction) {
+    if let Ok(path) = resolve_entry(ctx, &action.path) {
+        let _ = fs::remove_file(upload_path(&path, action.upload_id));
+    }
+}
+
+pub fn create_dir(ctx: BackendContext, path: String) -> Result<(), String> {
+    let path = resolve_entry(&ctx, &path)?;
+
+    fs::create_dir(path).map_err(|err| err.to_string())
+}
+
+pub fn rename(ctx: BackendContext, action: RenameAction) -> Result<(), String> {
+    let from = resolve_entry(&ctx, &action.from)?;
+    let to = resolve_entry(&ctx, &action.to)?;
+
+    fs::rename(from, to).map_err(|err| err.to_string())
+}
+
+pub fn delete(ctx: BackendContext, path: String) -> Result<(), String> {
+    let path = resolve_entry(&ctx, &path)?;
+
+    let meta = fs::symlink_metadata(&path).map_err(|err| err.to_string())?;
+
+    if meta.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    }
+    .map_err(|err| err.to_string())
+}
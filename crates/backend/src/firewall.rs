@@ -0,0 +1,413 @@
+use std::{
+    collections::HashMap,
+    fmt::Write,
+    fs,
+    net::{IpAddr, SocketAddr},
+    path::Path,
+    process::Command,
+};
+
+use log::{info, warn};
+use proto::{
+    backend::{ExposedSocket, FirewallBackend, FirewallResponse, SocketProtocol},
+    frontend::{FirewallAction, FirewallRule, RuleAction},
+};
+
+use crate::{client::BackendContext, getters, network, process};
+
+const RULES_FILE: &str = "/var/lib/dietpi-dashboard/firewall-rules";
+const ROLLBACK_DIR: &str = "/var/lib/dietpi-dashboard/firewall-rollback";
+const ROLLBACK_SCRIPT: &str = "/var/lib/dietpi-dashboard/firewall-rollback/restore.sh";
+const ROLLBACK_UNIT: &str = "dietpi-dashboard-firewall-rollback";
+const ROLLBACK_SECS: u32 = 60;
+
+const NFT_TABLE: &str = "dietpi_dashboard";
+const IPTABLES_CHAIN: &str = "DIETPI-DASHBOARD";
+
+// Leave room in the frame for the sockets and rules
+const MAX_RULESET_BYTES: usize = 32 * 1024;
+
+fn detect_backend() -> Option<FirewallBackend> {
+    let works = |args: &[&str]| {
+        Command::new(args[0])
+            .args(&args[1..])
+            .output()
+            .is_ok_and(|output| output.status.success())
+    };
+
+    // iptables is often just a wrapper around nftables anyway, so prefer nft when it's there
+    if works(&["nft", "list", "tables"]) {
+        Some(FirewallBackend::Nftables)
+    } else if works(&["iptables", "--version"]) {
+        Some(FirewallBackend::Iptables)
+    } else {
+        None
+    }
+}
+
+fn protocol_name(protocol: SocketProtocol) -> &'static str {
+    match protocol {
+        SocketProtocol::Tcp => "tcp",
+        SocketProtocol::Udp => "udp",
+    }
+}
+
+fn parse_rule(line: &str) -> Option<FirewallRule> {
+    let mut words = line.split_whitespace();
+
+    let action = match words.next()? {
+        "allow" => RuleAction::Allow,
+        "deny" => RuleAction::Deny,
+        _ => return None,
+    };
+    let protocol = match words.next()? {
+        "tcp" => SocketProtocol::Tcp,
+        "udp" => SocketProtocol::Udp,
+        _ => return None,
+    };
+    let port = words.next()?.parse().ok()?;
+    let source = words.next().unwrap_or_default().to_string();
+
+    Some(FirewallRule {
+        action,
+        protocol,
+        port,
+        source,
+    })
+}
+
+fn format_rule(rule: &FirewallRule) -> String {
+    let action = match rule.action {
+        RuleAction::Allow => "allow",
+        RuleAction::Deny => "deny",
+    };
+
+    format!(
+        "{action} {} {} {}",
+        protocol_name(rule.protocol),
+        rule.port,
+        rule.source
+    )
+    .trim_end()
+    .to_string()
+}
+
+fn read_rules() -> Vec<FirewallRule> {
+    fs::read_to_string(RULES_FILE)
+        .unwrap_or_default()
+        .lines()
+        .filter_map(parse_rule)
+        .collect()
+}
+
+// Returns the source address and prefix length, normalizing the address
+fn parse_source(source: &str) -> Option<(IpAddr, Option<u8>)> {
+    let (addr, prefix) = match source.split_once('/') {
+        Some((addr, prefix)) => (addr, Some(prefix.parse().ok()?)),
+        None => (source, None),
+    };
+    let addr: IpAddr = addr.parse().ok()?;
+
+    let max_prefix = if addr.is_ipv4() { 32 } else { 128 };
+    if prefix.is_some_and(|x| x > max_prefix) {
+        return None;
+    }
+
+    Some((addr, prefix))
+}
+
+fn check_rule(rule: &FirewallRule) -> Result<FirewallRule, String> {
+    if rule.port == 0 {
+        return Err("port must be between 1 and 65535".into());
+    }
+
+    let source = rule.source.trim();
+    let source = if source.is_empty() {
+        String::new()
+    } else {
+        // Also keeps the source safe to use in the rule scripts
+        match parse_source(source).ok_or(format!("invalid source address: {source}"))? {
+            (addr, Some(prefix)) => format!("{addr}/{prefix}"),
+            (addr, None) => addr.to_string(),
+        }
+    };
+
+    Ok(FirewallRule {
+        source,
+        ..rule.clone()
+    })
+}
+
+// Allow rules go first, so they can be used to make exceptions to deny rules
+fn ordered(rules: &[FirewallRule]) -> impl Iterator<Item = &FirewallRule> {
+    let allow = rules.iter().filter(|x| x.action == RuleAction::Allow);
+    let deny = rules.iter().filter(|x| x.action == RuleAction::Deny);
+
+    allow.chain(deny)
+}
+
+fn is_ipv6_source(rule: &FirewallRule) -> Option<bool> {
+    parse_source(&rule.source).map(|(addr, _)| addr.is_ipv6())
+}
+
+// Builds a shell script that replaces the dashboard's rules with the given ones. Everything in
+// the rules has been validated, so it doesn't need quoting.
+fn rules_script(backend: FirewallBackend, rules: &[FirewallRule]) -> String {
+    let mut script = String::from("set -e\n");
+
+    match backend {
+        FirewallBackend::Nftables => {
+            // Creating the table first means deleting it can't fail
+            let _ = writeln!(script, "nft -f - << 'EOF'");
+            let _ = writeln!(script, "table inet {NFT_TABLE}");
+            let _ = writeln!(script, "delete table inet {NFT_TABLE}");
+            let _ = writeln!(script, "table inet {NFT_TABLE} {{");
+            let _ = writeln!(script, "  chain input {{");
+            let _ = writeln!(
+                script,
+                "    type filter hook input priority filter - 1; policy accept;"
+            );
+            for rule in ordered(rules) {
+                let source = match is_ipv6_source(rule) {
+                    Some(true) => format!("ip6 saddr {} ", rule.source),
+                    Some(false) => format!("ip saddr {} ", rule.source),
+                    None => String::new(),
+                };
+                let verdict = match rule.action {
+                    RuleAction::Allow => "accept",
+                    RuleAction::Deny => "drop",
+                };
+                let _ = writeln!(
+                    script,
+                    "    {source}{} dport {} {verdict}",
+                    protocol_name(rule.protocol),
+                    rule.port
+                );
+            }
+            let _ = writeln!(script, "  }}");
+            let _ = writeln!(script, "}}");
+            let _ = writeln!(script, "EOF");
+        }
+        FirewallBackend::Iptables => {
+            for (cmd, ipv6) in [("iptables", false), ("ip6tables", true)] {
+                let _ = writeln!(script, "if command -v {cmd} > /dev/null; then");
+                let _ = writeln!(script, "  {cmd} -N {IPTABLES_CHAIN} 2> /dev/null || true");
+                let _ = writeln!(script, "  {cmd} -F {IPTABLES_CHAIN}");
+                let _ = writeln!(
+                    script,
+                    "  {cmd} -C INPUT -j {IPTABLES_CHAIN} 2> /dev/null || {cmd} -I INPUT -j {IPTABLES_CHAIN}"
+                );
+                for rule in ordered(rules) {
+                    let source = match is_ipv6_source(rule) {
+                        Some(x) if x != ipv6 => continue,
+                        Some(_) => format!(" -s {}", rule.source),
+                        None => String::new(),
+                    };
+                    let target = match rule.action {
+                        RuleAction::Allow => "ACCEPT",
+                        RuleAction::Deny => "DROP",
+                    };
+                    let _ = writeln!(
+                        script,
+                        "  {cmd} -A {IPTABLES_CHAIN} -p {0} --dport {1}{source} -j {target}",
+                        protocol_name(rule.protocol),
+                        rule.port
+                    );
+                }
+                let _ = writeln!(script, "fi");
+            }
+        }
+    }
+
+    script
+}
+
+fn run_script(script: &str) -> Result<(), String> {
+    getters::run_checked(Command::new("/bin/sh").args(["-c", script]))
+}
+
+fn ruleset(backend: FirewallBackend) -> Result<String, String> {
+    let commands: &[&[&str]] = match backend {
+        FirewallBackend::Nftables => &[&["nft", "list", "ruleset"]],
+        FirewallBackend::Iptables => &[&["iptables-save"], &["ip6tables-save"]],
+    };
+
+    let mut ruleset = String::new();
+    for args in commands {
+        let output = Command::new(args[0])
+            .args(&args[1..])
+            .output()
+            .map_err(|err| format!("failed to run {}: {err}", args[0]))?;
+        ruleset.push_str(&String::from_utf8_lossy(&output.stdout));
+    }
+
+    if ruleset.len() > MAX_RULESET_BYTES {
+        let mut end = MAX_RULESET_BYTES;
+        while !ruleset.is_char_boundary(end) {
+            end -= 1;
+        }
+        ruleset.truncate(end);
+        ruleset.push_str("\n...");
+    }
+
+    Ok(ruleset)
+}
+
+fn exposed_sockets() -> Vec<ExposedSocket> {
+    let listening = process::listening_sockets();
+
+    // Finding the owner means looking through every process's open files
+    let mut owners = HashMap::new();
+    for entry in fs::read_dir("/proc").into_iter().flatten().flatten() {
+        let Some(pid) = entry.file_name().to_str().and_then(|x| x.parse().ok()) else {
+            continue;
+        };
+        for inode in process::open_files(pid).1 {
+            if listening.contains_key(&inode) {
+                owners.entry(inode).or_insert(pid);
+            }
+        }
+    }
+
+    let mut sockets: Vec<_> = listening
+        .into_iter()
+        .map(|(inode, socket)| {
+            let pid = owners.get(&inode).copied();
+            let process = pid
+                .and_then(|pid| fs::read_to_string(format!("/proc/{pid}/comm")).ok())
+                .map(|x| x.trim().to_string())
+                .unwrap_or_default();
+
+            ExposedSocket {
+                socket,
+                pid,
+                process,
+            }
+        })
+        .collect();
+
+    sockets.sort_by_key(|x| {
+        let port = x
+            .socket
+            .address
+            .parse::<SocketAddr>()
+            .map_or(0, |x| x.port());
+        (port, x.socket.address.clone())
+    });
+
+    sockets
+}
+
+pub fn firewall(_ctx: BackendContext) -> FirewallResponse {
+    let backend = detect_backend();
+
+    FirewallResponse {
+        sockets: exposed_sockets(),
+        backend,
+        ruleset: backend.map_or(Err("no supported firewall found".into()), ruleset),
+        rules: read_rules(),
+        rollback_pending: network::rollback_pending(ROLLBACK_UNIT),
+    }
+}
+
+// The rollback is kept as a standalone script, so it can also be run if the system restarts
+// before the timer fires
+fn write_rollback(backend: FirewallBackend, rules: &[FirewallRule]) -> Result<(), String> {
+    fs::create_dir_all(ROLLBACK_DIR).map_err(|err| err.to_string())?;
+
+    let mut script = if Path::new(RULES_FILE).exists() {
+        network::backup_file(RULES_FILE, &format!("{ROLLBACK_DIR}/firewall-rules"))
+            .map_err(|err| format!("failed to back up {RULES_FILE}: {err}"))?;
+        format!("cp -p {ROLLBACK_DIR}/firewall-rules {RULES_FILE}\n")
+    } else {
+        format!("rm -f {RULES_FILE}\n")
+    };
+    script.push_str(&rules_script(backend, rules));
+    let _ = writeln!(script, "rm -rf {ROLLBACK_DIR}");
+
+    network::replace_file(ROLLBACK_SCRIPT, &script).map_err(|err| err.to_string())
+}
+
+pub fn action(_ctx: BackendContext, action: FirewallAction) -> Result<u32, String> {
+    let backend = detect_backend().ok_or("no supported firewall found")?;
+    if network::rollback_pending(ROLLBACK_UNIT) {
+        return Err("a previous change is still waiting to be confirmed".into());
+    }
+
+    let old_rules = read_rules();
+    let mut rules = old_rules.clone();
+
+    match action {
+        FirewallAction::Add(rule) => {
+            let rule = check_rule(&rule)?;
+            if rules.contains(&rule) {
+                return Err("the rule already exists".into());
+            }
+            rules.push(rule);
+        }
+        FirewallAction::Remove(rule) => {
+            let idx = rules
+                .iter()
+                .position(|x| *x == rule)
+                .ok_or("the rule doesn't exist, reload and try again")?;
+            rules.remove(idx);
+        }
+    }
+
+    write_rollback(backend, &old_rules)?;
+    network::arm_rollback(
+        ROLLBACK_UNIT,
+        "Roll back firewall changes made from DietPi-Dashboard",
+        ROLLBACK_SECS,
+        &format!("sh {ROLLBACK_SCRIPT}"),
+    )?;
+
+    let mut contents: String = rules.iter().map(|x| format_rule(x) + "\n").collect();
+    if contents.is_empty() {
+        contents.push('\n');
+    }
+
+    let applied = network::replace_file(RULES_FILE, &contents)
+        .map_err(|err| err.to_string())
+        .and_then(|()| run_script(&rules_script(backend, &rules)));
+
+    // No need to wait for the timer if the rules didn't even apply
+    if let Err(err) = applied {
+        let _ = network::cancel_rollback(ROLLBACK_UNIT);
+        let _ = run_script(&format!("sh {ROLLBACK_SCRIPT}"));
+        return Err(format!("failed to apply rules: {err}"));
+    }
+
+    Ok(ROLLBACK_SECS)
+}
+
+pub fn confirm(_ctx: BackendContext) -> Result<(), String> {
+    network::cancel_rollback(ROLLBACK_UNIT)?;
+
+    fs::remove_dir_all(ROLLBACK_DIR).map_err(|err| err.to_string())
+}
+
+// Rules added at runtime don't survive a reboot, so they're loaded again whenever the backend starts
+pub fn restore() {
+    let unconfirmed =
+        Path::new(ROLLBACK_SCRIPT).exists() && !network::rollback_pending(ROLLBACK_UNIT);
+    if !unconfirmed && !Path::new(RULES_FILE).exists() {
+        return;
+    }
+    let Some(backend) = detect_backend() else {
+        warn!("Firewall rules were saved, but no supported firewall was found");
+        return;
+    };
+
+    // A change that was never confirmed gets rolled back, even if the timer didn't get to run
+    let result = if unconfirmed {
+        info!("Rolling back unconfirmed firewall changes");
+        run_script(&format!("sh {ROLLBACK_SCRIPT}"))
+    } else {
+        run_script(&rules_script(backend, &read_rules()))
+    };
+
+    if let Err(err) = result {
+        warn!("Failed to restore firewall rules: {err}");
+    }
+}
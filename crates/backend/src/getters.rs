@@ -1,25 +1,68 @@
-use std::{fs, path::PathBuf, process::Command};
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    os::unix::process::ExitStatusExt,
+    path::{Path, PathBuf},
+    process::Command,
+    time::Instant,
+};
 
+use log::{debug, warn};
 use proto::{
     backend::{
-        CommandResponse, CpuResponse, DiskInfo, DiskResponse, HostResponse, MemResponse,
-        NetworkResponse, ProcessInfo, ProcessResponse, ProcessStatus, ServiceInfo, ServiceResponse,
-        ServiceStatus, SoftwareInfo, SoftwareResponse, TempResponse, UsageData,
+        CommandOutput, CommandResponse, CommandStatus, CpuFrequency, CpuResponse, DiskInfo, DiskIo,
+        DiskResponse, HostResponse, InterfaceInfo, MemResponse, NetworkResponse, ProcessInfo,
+        ProcessResponse, ProcessStatus, SensorInfo, ServiceInfo, ServiceResponse, ServiceStatus,
+        SoftwareInfo, SoftwareResponse, TempResponse, UsageData,
     },
-    frontend::CommandAction,
+    frontend::{CommandAction, ProcessListQuery, ProcessSort},
+};
+use sysinfo::{
+    CpuRefreshKind, ProcessRefreshKind, ProcessesToUpdate, System, ThreadKind, UpdateKind,
 };
-use sysinfo::{ProcessRefreshKind, ProcessesToUpdate, System, UpdateKind};
 
-use crate::client::BackendContext;
+use crate::{client::BackendContext, diskstats, files, memory, sensors, systemd};
+
+// The full command line is in the detail view, this just keeps each process small
+const MAX_CMDLINE_CHARS: usize = 96;
+// Leaves plenty of room in a frame for everything besides the strings of each process
+const MAX_PROCESS_LIST_BYTES: usize = 48 * 1024;
+const PROCESS_OVERHEAD_BYTES: usize = 32;
 
-fn round_to_2(num: f32) -> f32 {
+pub fn round_to_2(num: f32) -> f32 {
     (num * 100.).round() / 100.
 }
 
+// Reads the total number of context switches and interrupts since boot
+pub fn cpu_counters() -> Option<(u64, u64)> {
+    let stat = fs::read_to_string("/proc/stat").ok()?;
+
+    let counter = |name: &str| {
+        stat.lines()
+            .find_map(|line| line.strip_prefix(name))?
+            .split_whitespace()
+            .next()?
+            .parse()
+            .ok()
+    };
+
+    Some((counter("ctxt ")?, counter("intr ")?))
+}
+
+// Reads a cpufreq value, which is in kHz
+fn cpufreq_mhz(cpu: usize, file: &str) -> Option<u64> {
+    let path = format!("/sys/devices/system/cpu/cpu{cpu}/cpufreq/{file}");
+    let khz: u64 = fs::read_to_string(path).ok()?.trim().parse().ok()?;
+
+    Some(khz / 1000)
+}
+
 pub fn cpu(mut ctx: BackendContext) -> CpuResponse {
-    let sys = &mut ctx.system().system;
+    let mut system = ctx.system();
+    let system = &mut *system;
+    let sys = &mut system.system;
 
-    sys.refresh_cpu_usage();
+    sys.refresh_cpu_specifics(CpuRefreshKind::everything());
 
     let global_cpu = round_to_2(sys.global_cpu_usage());
     let cpus: Vec<f32> = sys
@@ -28,7 +71,51 @@ pub fn cpu(mut ctx: BackendContext) -> CpuResponse {
         .map(|x| round_to_2(x.cpu_usage()))
         .collect();
 
-    CpuResponse { global_cpu, cpus }
+    let frequencies = sys
+        .cpus()
+        .iter()
+        .enumerate()
+        .map(|(i, x)| CpuFrequency {
+            current: x.frequency(),
+            min: cpufreq_mhz(i, "cpuinfo_min_freq"),
+            max: cpufreq_mhz(i, "cpuinfo_max_freq"),
+        })
+        .collect();
+
+    let brand = sys
+        .cpus()
+        .first()
+        .map(|x| x.brand().trim())
+        .filter(|x| !x.is_empty())
+        .unwrap_or("unknown")
+        .to_string();
+
+    let load_avg = System::load_average();
+    let load_avg = [load_avg.one, load_avg.five, load_avg.fifteen].map(|x| round_to_2(x as f32));
+
+    let counters = cpu_counters().unwrap_or(system.cpu_counters);
+    let elapsed = system.cpu_refreshed.elapsed().as_secs_f64();
+    let per_second = |now: u64, prev: u64| {
+        if elapsed > 0. {
+            (now.saturating_sub(prev) as f64 / elapsed) as u64
+        } else {
+            0
+        }
+    };
+    let context_switches = per_second(counters.0, system.cpu_counters.0);
+    let interrupts = per_second(counters.1, system.cpu_counters.1);
+    system.cpu_counters = counters;
+    system.cpu_refreshed = Instant::now();
+
+    CpuResponse {
+        global_cpu,
+        cpus,
+        frequencies,
+        load_avg,
+        context_switches,
+        interrupts,
+        brand,
+    }
 }
 
 pub fn temp(mut ctx: BackendContext) -> TempResponse {
@@ -36,7 +123,7 @@ pub fn temp(mut ctx: BackendContext) -> TempResponse {
     components.refresh();
     let components = components.list();
 
-    let known_sensor_names = ["coretemp Package", "tdie"];
+    let known_sensor_names = ["coretemp Package", "tdie", "cpu_thermal"];
 
     let temp = components
         .iter()
@@ -44,7 +131,22 @@ pub fn temp(mut ctx: BackendContext) -> TempResponse {
         .or_else(|| components.first())
         .map(|x| round_to_2(x.temperature()));
 
-    TempResponse { temp }
+    let sensors = components
+        .iter()
+        .map(|x| SensorInfo {
+            label: x.label().into(),
+            temp: round_to_2(x.temperature()),
+            max: round_to_2(x.max()),
+            critical: x.critical().map(round_to_2),
+        })
+        .collect();
+
+    TempResponse {
+        temp,
+        sensors,
+        fans: sensors::fans(),
+        throttle: sensors::throttle_state(),
+    }
 }
 
 pub fn memory(mut ctx: BackendContext) -> MemResponse {
@@ -63,49 +165,240 @@ pub fn memory(mut ctx: BackendContext) -> MemResponse {
         total: sys.total_swap(),
     };
 
-    MemResponse { ram, swap }
+    MemResponse {
+        ram,
+        swap,
+        breakdown: memory::breakdown(),
+        zram: memory::zram(),
+        pressure: memory::pressure(),
+    }
+}
+
+// Filesystems that don't correspond to real storage, skipped when discovering disks
+const VIRTUAL_FILESYSTEMS: &[&str] = &["tmpfs", "devtmpfs", "ramfs", "overlay", "squashfs"];
+
+// Checks whether /etc/fstab mounts a filesystem read-only
+fn fstab_read_only(mnt_point: &Path) -> Option<bool> {
+    let fstab = fs::read_to_string("/etc/fstab").ok()?;
+
+    fstab
+        .lines()
+        .filter(|line| !line.trim_start().starts_with('#'))
+        .find_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            (Path::new(fields.get(1)?) == mnt_point).then(|| {
+                fields
+                    .get(3)
+                    .is_some_and(|x| x.split(',').any(|x| x == "ro"))
+            })
+        })
 }
 
 pub fn disks(mut ctx: BackendContext) -> DiskResponse {
-    let mnt_points = &ctx.config.disks;
-    let mnt_points: Vec<_> = mnt_points.iter().map(PathBuf::from).collect();
+    let config = ctx.config.clone();
+    let mnt_points: Vec<_> = config.disks.iter().map(PathBuf::from).collect();
+
+    let mut system = ctx.system();
+    let system = &mut *system;
+
+    if config.auto_disks {
+        system.disks.refresh_list();
+    } else {
+        system.disks.refresh();
+    }
+
+    let counters = diskstats::read();
+    let prev_counters = std::mem::replace(&mut system.disk_counters, counters.clone());
+    let elapsed = system.disks_refreshed.elapsed().as_secs_f64();
+    system.disks_refreshed = Instant::now();
 
-    let disks = &mut ctx.system().disks;
-    disks.refresh();
-    let disks = disks.list();
+    let per_second = |num: u64| {
+        if elapsed > 0. {
+            num as f64 / elapsed
+        } else {
+            0.
+        }
+    };
 
-    let disks: Vec<_> = disks
+    let mut devices_seen = HashSet::new();
+    let (mut read, mut write) = (0, 0);
+
+    let disks: Vec<_> = system
+        .disks
         .iter()
-        .filter(|disk| mnt_points.iter().any(|path| path == disk.mount_point()))
-        .map(|disk| DiskInfo {
-            name: disk.name().to_str().unwrap_or("unknown").into(),
-            mnt_point: disk.mount_point().to_str().unwrap_or("unknown").into(),
-            usage: UsageData {
-                used: disk.total_space() - disk.available_space(),
-                total: disk.total_space(),
-            },
+        .filter(|disk| {
+            if config.auto_disks {
+                let fs_type = disk.file_system().to_string_lossy();
+                !VIRTUAL_FILESYSTEMS.contains(&fs_type.as_ref())
+            } else {
+                mnt_points.iter().any(|path| path == disk.mount_point())
+            }
+        })
+        .map(|disk| {
+            let mnt_point = disk.mount_point();
+
+            // Using the device number works even when the device name is something like /dev/root
+            let device = rustix::fs::stat(mnt_point).ok().map(|stat| {
+                (
+                    rustix::fs::major(stat.st_dev),
+                    rustix::fs::minor(stat.st_dev),
+                )
+            });
+            let io = device.and_then(|device| {
+                let delta = counters.get(&device)?.since(*prev_counters.get(&device)?);
+
+                let io = DiskIo {
+                    read_rate: per_second(delta.read_bytes) as u64,
+                    write_rate: per_second(delta.written_bytes) as u64,
+                    read_iops: round_to_2(per_second(delta.reads) as f32),
+                    write_iops: round_to_2(per_second(delta.writes) as f32),
+                };
+
+                // Don't count the same device twice if it's mounted in multiple places
+                if devices_seen.insert(device) {
+                    read += io.read_rate;
+                    write += io.write_rate;
+                }
+
+                Some(io)
+            });
+
+            // Some filesystems, like FAT, don't have inodes and report 0
+            let inodes = rustix::fs::statvfs(mnt_point)
+                .ok()
+                .filter(|stat| stat.f_files > 0)
+                .map(|stat| UsageData {
+                    used: stat.f_files - stat.f_ffree,
+                    total: stat.f_files,
+                });
+
+            DiskInfo {
+                name: disk.name().to_str().unwrap_or("unknown").into(),
+                mnt_point: mnt_point.to_str().unwrap_or("unknown").into(),
+                fs_type: disk.file_system().to_string_lossy().into(),
+                usage: UsageData {
+                    used: disk.total_space() - disk.available_space(),
+                    total: disk.total_space(),
+                },
+                inodes,
+                read_only: disk.is_read_only(),
+                // The kernel remounts filesystems read-only after errors, which usually means a failing disk
+                remounted_ro: disk.is_read_only() && fstab_read_only(mnt_point) == Some(false),
+                io,
+            }
         })
         .collect();
 
-    DiskResponse { disks }
+    DiskResponse { disks, read, write }
+}
+
+// Patterns can end with "*" to match any interface starting with the rest of the pattern
+pub fn interface_hidden(patterns: &[String], name: &str) -> bool {
+    patterns
+        .iter()
+        .any(|pattern| match pattern.strip_suffix('*') {
+            Some(prefix) => name.starts_with(prefix),
+            None => name == pattern,
+        })
+}
+
+pub fn read_sys_net(name: &str, file: &str) -> Option<String> {
+    fs::read_to_string(format!("/sys/class/net/{name}/{file}"))
+        .ok()
+        .map(|x| x.trim().to_string())
 }
 
 pub fn network_io(mut ctx: BackendContext) -> NetworkResponse {
-    let networks = &mut ctx.system().networks;
-    networks.refresh();
-    let networks = networks.list();
+    let config = ctx.config.clone();
+    let mut system = ctx.system();
 
-    let mut resp = NetworkResponse { sent: 0, recv: 0 };
+    system.networks.refresh();
+    let elapsed = system.networks_refreshed.elapsed().as_secs_f64();
+    system.networks_refreshed = Instant::now();
 
-    for net in networks.values() {
-        resp.recv += net.received();
-        resp.sent += net.transmitted();
+    let per_second = |bytes: u64| {
+        if elapsed > 0. {
+            (bytes as f64 / elapsed) as u64
+        } else {
+            0
+        }
+    };
+
+    let mut interfaces: Vec<InterfaceInfo> = system
+        .networks
+        .iter()
+        .filter(|(name, _)| !interface_hidden(&config.hidden_interfaces, name))
+        .map(|(name, net)| {
+            let stat = |file: &str| {
+                read_sys_net(name, &format!("statistics/{file}"))
+                    .and_then(|x| x.parse().ok())
+                    .unwrap_or(0)
+            };
+
+            InterfaceInfo {
+                name: name.clone(),
+                mac: net.mac_address().to_string(),
+                addresses: net
+                    .ip_networks()
+                    .iter()
+                    .map(|x| format!("{}/{}", x.addr, x.prefix))
+                    .collect(),
+                up: read_sys_net(name, "operstate").is_some_and(|x| x == "up"),
+                // Reading the speed fails or gives -1 if the link is down or it isn't applicable
+                speed: read_sys_net(name, "speed").and_then(|x| x.parse().ok()),
+                sent: per_second(net.transmitted()),
+                recv: per_second(net.received()),
+                total_sent: net.total_transmitted(),
+                total_recv: net.total_received(),
+                packets_sent: net.total_packets_transmitted(),
+                packets_recv: net.total_packets_received(),
+                errors_sent: net.total_errors_on_transmitted(),
+                errors_recv: net.total_errors_on_received(),
+                dropped_sent: stat("tx_dropped"),
+                dropped_recv: stat("rx_dropped"),
+            }
+        })
+        .collect();
+    interfaces.sort_by(|a, b| a.name.cmp(&b.name));
+
+    NetworkResponse {
+        sent: interfaces.iter().map(|x| x.sent).sum(),
+        recv: interfaces.iter().map(|x| x.recv).sum(),
+        interfaces,
     }
+}
 
-    resp
+// Finds the interface used by the default route
+fn default_route_interface() -> Option<String> {
+    let routes = fs::read_to_string("/proc/net/route").ok()?;
+
+    routes.lines().skip(1).find_map(|line| {
+        let mut fields = line.split_whitespace();
+        let iface = fields.next()?;
+        let destination = fields.next()?;
+
+        (destination == "00000000").then(|| iface.to_string())
+    })
+}
+
+pub fn process_status(status: sysinfo::ProcessStatus) -> ProcessStatus {
+    match status {
+        sysinfo::ProcessStatus::Run => ProcessStatus::Running,
+        sysinfo::ProcessStatus::Sleep => ProcessStatus::Sleeping,
+        sysinfo::ProcessStatus::Stop => ProcessStatus::Paused,
+        _ => ProcessStatus::Other,
+    }
+}
+
+fn matches_filter(proc: &ProcessInfo, filter: &str) -> bool {
+    let filter = filter.to_lowercase();
+
+    [&proc.name, &proc.cmdline, &proc.user]
+        .iter()
+        .any(|x| x.to_lowercase().contains(&filter))
 }
 
-pub fn processes(mut ctx: BackendContext) -> ProcessResponse {
+pub fn processes(mut ctx: BackendContext, query: ProcessListQuery) -> ProcessResponse {
     let sys = &mut ctx.system().system;
 
     sys.refresh_processes_specifics(
@@ -114,40 +407,86 @@ pub fn processes(mut ctx: BackendContext) -> ProcessResponse {
         ProcessRefreshKind::new()
             .with_cpu()
             .with_memory()
+            .with_user(UpdateKind::OnlyIfNotSet)
             .with_cmd(UpdateKind::OnlyIfNotSet),
     );
 
-    let processes = sys
+    let users = files::id_names("/etc/passwd");
+
+    let mut processes: Vec<_> = sys
         .processes()
         .iter()
-        .filter(|(_, proc)| !proc.cmd().is_empty())
-        .map(|(pid, proc)| ProcessInfo {
-            pid: pid.as_u32(),
-            name: proc.name().to_string_lossy().into(),
-            cpu: round_to_2(proc.cpu_usage()),
-            mem: proc.memory(),
-            status: match proc.status() {
-                sysinfo::ProcessStatus::Run => ProcessStatus::Running,
-                sysinfo::ProcessStatus::Sleep => ProcessStatus::Sleeping,
-                sysinfo::ProcessStatus::Stop => ProcessStatus::Paused,
-                _ => ProcessStatus::Other,
-            },
+        // Userland threads show up as processes too, but they're counted in the detail view instead
+        .filter(|(_, proc)| proc.thread_kind() != Some(ThreadKind::Userland))
+        .map(|(pid, proc)| {
+            let cmdline = proc
+                .cmd()
+                .iter()
+                .map(|x| x.to_string_lossy())
+                .collect::<Vec<_>>()
+                .join(" ");
+
+            ProcessInfo {
+                pid: pid.as_u32(),
+                parent: proc.parent().map(|x| x.as_u32()),
+                name: proc.name().to_string_lossy().into(),
+                cmdline: cmdline.chars().take(MAX_CMDLINE_CHARS).collect(),
+                user: proc
+                    .user_id()
+                    .map(|uid| users.get(uid).cloned().unwrap_or(uid.to_string()))
+                    .unwrap_or_default(),
+                kernel_thread: proc.thread_kind() == Some(ThreadKind::Kernel)
+                    || proc.cmd().is_empty(),
+                cpu: round_to_2(proc.cpu_usage()),
+                mem: proc.memory(),
+                status: process_status(proc.status()),
+            }
+        })
+        .filter(|proc| {
+            (query.kernel || !proc.kernel_thread) && matches_filter(proc, query.filter.trim())
         })
         .collect();
 
-    ProcessResponse { processes }
+    match query.sort {
+        ProcessSort::Pid => processes.sort_by_key(|a| a.pid),
+        ProcessSort::Name => processes.sort_by(|a, b| a.name.cmp(&b.name)),
+        ProcessSort::Status => processes.sort_by_key(|a| a.status),
+        ProcessSort::Cpu => processes.sort_by(|a, b| a.cpu.total_cmp(&b.cpu)),
+        ProcessSort::Ram => processes.sort_by_key(|a| a.mem),
+    }
+    if query.reverse {
+        processes.reverse();
+    }
+
+    // Everything has to fit in one frame, so drop whatever comes last in the chosen order
+    let total = processes.len() as u32;
+    let mut size = 0;
+    let fits = processes
+        .iter()
+        .take_while(|proc| {
+            size += PROCESS_OVERHEAD_BYTES + proc.name.len() + proc.cmdline.len() + proc.user.len();
+            size <= MAX_PROCESS_LIST_BYTES
+        })
+        .count();
+    processes.truncate(fits);
+
+    ProcessResponse { processes, total }
 }
 
 pub fn host(mut ctx: BackendContext) -> HostResponse {
+    let config = ctx.config.clone();
     let net = &ctx.system().networks;
 
     let unknown = || "unknown".to_string();
 
-    let nic = net
-        .iter()
-        .max_by_key(|(_, net)| net.total_transmitted())
-        .map(|(name, _)| name)
-        .cloned()
+    // Fall back to guessing based on traffic if there's no default route
+    let nic = default_route_interface()
+        .or_else(|| {
+            net.iter()
+                .filter(|(name, _)| !interface_hidden(&config.hidden_interfaces, name))
+                .max_by_key(|(_, net)| net.total_transmitted())
+                .map(|(name, _)| name.clone())
+        })
         .unwrap_or_else(unknown);
 
     let uptime = System::uptime();
@@ -176,6 +515,21 @@ pub fn host(mut ctx: BackendContext) -> HostResponse {
         .map(|output| output.stdout.into_iter().filter(|&x| x == b'\n').count())
         .unwrap_or(0);
 
+    // /etc/localtime is normally a symlink into the zoneinfo database
+    let timezone = fs::read_link("/etc/localtime")
+        .ok()
+        .and_then(|path| {
+            let path = path.to_str()?;
+            let (_, name) = path.split_once("zoneinfo/")?;
+            Some(name.to_string())
+        })
+        .or_else(|| {
+            fs::read_to_string("/etc/timezone")
+                .ok()
+                .map(|x| x.trim().to_string())
+        })
+        .unwrap_or_else(unknown);
+
     HostResponse {
         nic,
         uptime,
@@ -185,9 +539,22 @@ pub fn host(mut ctx: BackendContext) -> HostResponse {
         hostname,
         dp_version,
         num_pkgs,
+        timezone,
     }
 }
 
+pub fn timezones(_ctx: BackendContext) -> Vec<String> {
+    let output = Command::new("timedatectl")
+        .arg("list-timezones")
+        .output()
+        .ok();
+
+    output
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|out| out.lines().map(String::from).collect())
+        .unwrap_or_default()
+}
+
 fn parse_software_line(line: &str) -> Option<(SoftwareInfo, bool)> {
     let mut fields = line.split('|');
 
@@ -209,6 +576,9 @@ fn parse_software_line(line: &str) -> Option<(SoftwareInfo, bool)> {
 
     let docs = fields.next()?.into();
 
+    // Older versions of dietpi-software don't print a category
+    let category = fields.next().unwrap_or_default().trim().into();
+
     Some((
         SoftwareInfo {
             id,
@@ -216,11 +586,38 @@ fn parse_software_line(line: &str) -> Option<(SoftwareInfo, bool)> {
             desc,
             deps,
             docs,
+            category,
+            dep_ids: Vec::new(),
         },
         installed,
     ))
 }
 
+// Dependencies are listed either by ID or by name, e.g. "+ Python 3, + Redis"
+fn resolve_deps(deps: &str, names: &HashMap<String, u16>) -> Vec<u16> {
+    let mut ids = Vec::new();
+
+    for part in deps.split(',') {
+        let part = part.trim().trim_start_matches('+').trim();
+        if part.is_empty() {
+            continue;
+        }
+
+        if let Some(&id) = names.get(&part.to_lowercase()) {
+            ids.push(id);
+        } else {
+            ids.extend(
+                part.split_whitespace()
+                    .filter_map(|x| x.parse::<u16>().ok()),
+            );
+        }
+    }
+
+    ids.sort_unstable();
+    ids.dedup();
+    ids
+}
+
 pub fn software(_ctx: BackendContext) -> SoftwareResponse {
     let cmd_out = Command::new("/boot/dietpi/dietpi-software")
         .args(["list", "--machine-readable"])
@@ -228,17 +625,26 @@ pub fn software(_ctx: BackendContext) -> SoftwareResponse {
         .ok();
     let cmd_out = cmd_out.and_then(|output| String::from_utf8(output.stdout).ok());
 
-    let software_iter = cmd_out
+    let mut software: Vec<_> = cmd_out
         .iter()
         .flat_map(|out| out.lines())
-        .filter_map(parse_software_line);
+        .filter_map(parse_software_line)
+        .collect();
+
+    let names: HashMap<_, _> = software
+        .iter()
+        .map(|(info, _)| (info.name.to_lowercase(), info.id))
+        .collect();
+    for (info, _) in &mut software {
+        info.dep_ids = resolve_deps(&info.deps, &names);
+    }
 
     let mut resp = SoftwareResponse {
         installed: Vec::new(),
         uninstalled: Vec::new(),
     };
 
-    for (info, installed) in software_iter {
+    for (info, installed) in software {
         if installed {
             resp.installed.push(info);
         } else {
@@ -249,7 +655,7 @@ pub fn software(_ctx: BackendContext) -> SoftwareResponse {
     resp
 }
 
-fn remove_escape_codes(s: impl Iterator<Item = u8>) -> Vec<u8> {
+pub fn remove_escape_codes(s: impl Iterator<Item = u8>) -> Vec<u8> {
     s.scan(false, |in_escape, c| {
         if *in_escape {
             if c.is_ascii_alphabetic() {
@@ -267,15 +673,91 @@ fn remove_escape_codes(s: impl Iterator<Item = u8>) -> Vec<u8> {
     .collect()
 }
 
-pub fn command(_ctx: BackendContext, action: CommandAction) -> CommandResponse {
-    let output = Command::new(action.cmd)
-        .args(&action.args)
-        .output()
-        .map(|out| out.stdout.into_iter())
-        .map(remove_escape_codes)
-        .unwrap_or_else(|err| format!("command execution failed: {err}").into());
+pub fn run_command(cmd: &mut Command) -> CommandResponse {
+    let output = match cmd.output() {
+        Ok(output) => output,
+        Err(err) => return CommandResponse::SpawnFailed(err.to_string()),
+    };
+
+    // If there's no exit code, the process must have been killed by a signal
+    let status = match output.status.code() {
+        Some(code) => CommandStatus::Exited(code),
+        None => CommandStatus::Signaled(output.status.signal().unwrap_or_default()),
+    };
+
+    CommandResponse::Finished(CommandOutput {
+        stdout: remove_escape_codes(output.stdout.into_iter()),
+        stderr: remove_escape_codes(output.stderr.into_iter()),
+        status,
+    })
+}
 
-    CommandResponse { output }
+// For callers that only care whether the command succeeded
+pub fn run_checked(cmd: &mut Command) -> Result<(), String> {
+    match run_command(cmd) {
+        CommandResponse::Finished(output) if output.status == CommandStatus::Exited(0) => Ok(()),
+        CommandResponse::Finished(output) => {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            Err(match output.status {
+                _ if !stderr.trim().is_empty() => stderr.trim().to_string(),
+                CommandStatus::Exited(code) => format!("exited with code {code}"),
+                CommandStatus::Signaled(signal) => format!("killed by signal {signal}"),
+            })
+        }
+        CommandResponse::SpawnFailed(err) => Err(err),
+        CommandResponse::Denied => Err("denied".into()),
+    }
+}
+
+fn arg_matches(pattern: &str, arg: &str) -> bool {
+    match pattern {
+        "<id>" => !arg.is_empty() && arg.bytes().all(|c| c.is_ascii_digit()),
+        _ => pattern == arg,
+    }
+}
+
+fn command_matches(pattern: &str, action: &CommandAction) -> bool {
+    let mut patterns = pattern.split_whitespace();
+
+    if patterns.next() != Some(action.cmd.as_str()) {
+        return false;
+    }
+
+    let mut args = action.args.iter();
+
+    for pattern in patterns {
+        if let Some(pattern) = pattern.strip_suffix("...") {
+            // A repeated pattern has to match at least once, and takes all remaining arguments
+            let rest: Vec<_> = args.collect();
+            return !rest.is_empty() && rest.iter().all(|arg| arg_matches(pattern, arg));
+        }
+
+        match args.next() {
+            Some(arg) if arg_matches(pattern, arg) => {}
+            _ => return false,
+        }
+    }
+
+    args.next().is_none()
+}
+
+pub fn command(ctx: BackendContext, action: CommandAction) -> CommandResponse {
+    let allowed = ctx
+        .config
+        .allowed_commands
+        .iter()
+        .any(|pattern| command_matches(pattern, &action));
+
+    if !allowed {
+        warn!(
+            "Denied command not in allow-list: {} {}",
+            action.cmd,
+            action.args.join(" ")
+        );
+        return CommandResponse::Denied;
+    }
+
+    run_command(Command::new(action.cmd).args(&action.args))
 }
 
 fn services_helper() -> Option<ServiceResponse> {
@@ -304,6 +786,11 @@ fn services_helper() -> Option<ServiceResponse> {
             (name, status.unwrap_or(statusdate), date.unwrap_or_default())
         })
         .map(|(name, status, date)| {
+            let sub_state = status
+                .split_once(" (")
+                .map(|(_, sub)| sub.trim_end_matches(')'))
+                .unwrap_or_default();
+
             let status = match status {
                 "active (running)" | "active (exited)" => ServiceStatus::Active,
                 "inactive (dead)" => ServiceStatus::Inactive,
@@ -311,9 +798,15 @@ fn services_helper() -> Option<ServiceResponse> {
             };
 
             ServiceInfo {
-                name: name.into(),
+                name: name.trim().into(),
+                description: String::new(),
                 status,
+                load_state: "loaded".into(),
+                sub_state: sub_state.into(),
                 start: date.into(),
+                main_pid: None,
+                memory: None,
+                cpu_time: None,
                 err_log: String::new(),
             }
         });
@@ -323,9 +816,15 @@ fn services_helper() -> Option<ServiceResponse> {
         .filter_map(|desc| desc.split_once(".service"))
         .filter_map(|(name, rest)| rest.split_once("\n\n").map(|(_, err_log)| (name, err_log)))
         .map(|(name, err_log)| ServiceInfo {
-            name: name.into(),
+            name: name.trim().into(),
+            description: String::new(),
             status: ServiceStatus::Failed,
+            load_state: "loaded".into(),
+            sub_state: "failed".into(),
             start: String::new(),
+            main_pid: None,
+            memory: None,
+            cpu_time: None,
             err_log: err_log.into(),
         });
 
@@ -337,6 +836,14 @@ fn services_helper() -> Option<ServiceResponse> {
 }
 
 pub fn services(_ctx: BackendContext) -> ServiceResponse {
+    // Getters run on a blocking thread, so it's fine to block on the D-Bus queries here
+    match tokio::runtime::Handle::current().block_on(systemd::list_services()) {
+        Ok(services) => return ServiceResponse { services },
+        Err(err) => {
+            debug!("Failed to query systemd over D-Bus, falling back to dietpi-services: {err}")
+        }
+    }
+
     services_helper().unwrap_or_else(|| ServiceResponse {
         services: Vec::new(),
     })
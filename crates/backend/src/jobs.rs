@@ -0,0 +1,256 @@
+use std::{
+    fs,
+    io::{self, Read, Seek, SeekFrom},
+    time::Duration,
+};
+
+use proto::{
+    backend::{ActionBackendMessage, BackendMessage, CommandStatus, JobEvent},
+    frontend::Job,
+};
+use tokio::{process::Command, sync::mpsc, task::JoinHandle};
+
+use crate::getters::remove_escape_codes;
+
+const JOB_UNIT: &str = "dietpi-dashboard-job";
+const JOB_DIR: &str = "/var/lib/dietpi-dashboard/job";
+const JOB_NAME_FILE: &str = "/var/lib/dietpi-dashboard/job/name";
+const JOB_OUTPUT_FILE: &str = "/var/lib/dietpi-dashboard/job/output";
+const JOB_STATUS_FILE: &str = "/var/lib/dietpi-dashboard/job/status";
+
+// Keeps a single line well within a frame, even if a program prints a progress bar without newlines
+const MAX_LINE_LEN: usize = 4096;
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+const JOBS: [Job; 3] = [Job::AptUpdate, Job::AptUpgrade, Job::DietPiUpdate];
+
+fn job_name(job: Job) -> &'static str {
+    match job {
+        Job::AptUpdate => "apt_update",
+        Job::AptUpgrade => "apt_upgrade",
+        Job::DietPiUpdate => "dietpi_update",
+    }
+}
+
+fn job_args(job: Job) -> Vec<&'static str> {
+    // Keep apt and dpkg from asking questions, nobody would be able to answer them
+    let apt_get = |arg| {
+        vec![
+            "apt-get",
+            "-y",
+            "-o",
+            "Dpkg::Options::=--force-confdef",
+            "-o",
+            "Dpkg::Options::=--force-confold",
+            arg,
+        ]
+    };
+
+    match job {
+        Job::AptUpdate => apt_get("update"),
+        Job::AptUpgrade => apt_get("upgrade"),
+        // Non-interactive mode
+        Job::DietPiUpdate => vec!["/boot/dietpi/dietpi-update", "1"],
+    }
+}
+
+fn send(socket_tx: &mpsc::UnboundedSender<BackendMessage>, event: JobEvent) {
+    let msg = BackendMessage::Action(ActionBackendMessage::Job(event));
+    let _ = socket_tx.send(msg);
+}
+
+fn output_line(line: &[u8]) -> JobEvent {
+    let mut line = remove_escape_codes(line.iter().copied());
+    line.truncate(MAX_LINE_LEN);
+
+    JobEvent::Output(String::from_utf8_lossy(&line).into_owned())
+}
+
+async fn unit_active() -> bool {
+    Command::new("systemctl")
+        .args(["is-active", "--quiet", &format!("{JOB_UNIT}.service")])
+        .status()
+        .await
+        .is_ok_and(|status| status.success())
+}
+
+// Holds the unit's ExecMainCode and ExecMainStatus, as `systemctl show` prints them
+fn read_status() -> Option<CommandStatus> {
+    let content = fs::read_to_string(JOB_STATUS_FILE).ok()?;
+    let property = |name| {
+        content
+            .lines()
+            .find_map(|line| line.strip_prefix(name)?.strip_prefix('='))?
+            .parse::<i32>()
+            .ok()
+    };
+    let (code, status) = (property("ExecMainCode")?, property("ExecMainStatus")?);
+
+    // The code is a CLD_* value from waitid: exited, or killed or dumped by a signal
+    match code {
+        1 => Some(CommandStatus::Exited(status)),
+        2 | 3 => Some(CommandStatus::Signaled(status)),
+        _ => None,
+    }
+}
+
+fn read_new_output(offset: &mut u64, buf: &mut Vec<u8>) -> io::Result<()> {
+    let mut file = fs::File::open(JOB_OUTPUT_FILE)?;
+    file.seek(SeekFrom::Start(*offset))?;
+    *offset += file.read_to_end(buf)? as u64;
+
+    Ok(())
+}
+
+// Sends every complete line in the buffer, and anything that has grown too long to be a line
+fn send_lines(socket_tx: &mpsc::UnboundedSender<BackendMessage>, buf: &mut Vec<u8>) {
+    while let Some(end) = buf.iter().position(|&c| c == b'\n') {
+        send(socket_tx, output_line(&buf[..end]));
+        buf.drain(..=end);
+    }
+
+    if buf.len() > MAX_LINE_LEN {
+        send(socket_tx, output_line(buf));
+        buf.clear();
+    }
+}
+
+async fn start_unit(job: Job) -> Result<(), String> {
+    fs::create_dir_all(JOB_DIR).map_err(|err| err.to_string())?;
+    fs::write(JOB_NAME_FILE, job_name(job)).map_err(|err| err.to_string())?;
+    // Clear out the last job, so none of it gets mixed up with this one
+    let _ = fs::remove_file(JOB_OUTPUT_FILE);
+    let _ = fs::remove_file(JOB_STATUS_FILE);
+
+    // The job replaces the shell, so the unit's status is the job's own
+    let script = format!("exec \"$@\" > {JOB_OUTPUT_FILE} 2>&1 < /dev/null");
+    // The unit only records that status until it's collected, so save it alongside the output.
+    // It's renamed into place, so it's never read half written.
+    let save_status = format!(
+        "ExecStopPost=/bin/sh -c 'systemctl show --property=ExecMainCode,ExecMainStatus \
+         {JOB_UNIT}.service > {JOB_STATUS_FILE}.tmp && mv {JOB_STATUS_FILE}.tmp {JOB_STATUS_FILE}'"
+    );
+
+    let output = Command::new("systemd-run")
+        .args([
+            &format!("--unit={JOB_UNIT}"),
+            &format!("--description=DietPi-Dashboard job: {}", job_name(job)),
+            "--collect",
+            "--quiet",
+            "--setenv=DEBIAN_FRONTEND=noninteractive",
+            &format!("--property={save_status}"),
+            "/bin/sh",
+            "-c",
+            &script,
+            "sh",
+        ])
+        .args(job_args(job))
+        .output()
+        .await
+        .map_err(|err| err.to_string())?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("failed to start the job: {}", stderr.trim()));
+    }
+
+    Ok(())
+}
+
+// Follows the output file until the job writes its exit status
+async fn tail_job(socket_tx: mpsc::UnboundedSender<BackendMessage>) {
+    let (mut offset, mut buf) = (0, Vec::new());
+
+    loop {
+        tokio::time::sleep(POLL_INTERVAL).await;
+
+        // Check before reading, so nothing written just before exiting is missed
+        let status = read_status();
+        let stopped = status.is_none() && !unit_active().await;
+
+        match read_new_output(&mut offset, &mut buf) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => {
+                send(&socket_tx, JobEvent::Failed(err.to_string()));
+                return;
+            }
+            _ => {}
+        }
+        send_lines(&socket_tx, &mut buf);
+
+        if status.is_none() && !stopped {
+            continue;
+        }
+
+        if !buf.is_empty() {
+            send(&socket_tx, output_line(&buf));
+        }
+
+        // The status may have been written between the two checks
+        let event = match status.or_else(read_status) {
+            Some(status) => JobEvent::Finished(status),
+            None => JobEvent::Failed("the job stopped without reporting its status".into()),
+        };
+        send(&socket_tx, event);
+        return;
+    }
+}
+
+async fn run_job(job: Job, socket_tx: mpsc::UnboundedSender<BackendMessage>) {
+    if let Err(err) = start_unit(job).await {
+        send(&socket_tx, JobEvent::Rejected(err));
+        return;
+    }
+
+    send(&socket_tx, JobEvent::Started(job));
+    tail_job(socket_tx).await;
+}
+
+// Runs one job at a time. Jobs run in their own systemd unit, so they keep going if the backend exits,
+// since interrupting something like a package upgrade could break the system.
+pub struct JobRunner {
+    socket_tx: mpsc::UnboundedSender<BackendMessage>,
+    rx: mpsc::UnboundedReceiver<Job>,
+    current: Option<JoinHandle<()>>,
+}
+
+impl JobRunner {
+    pub fn new(
+        socket_tx: mpsc::UnboundedSender<BackendMessage>,
+        rx: mpsc::UnboundedReceiver<Job>,
+    ) -> Self {
+        Self {
+            socket_tx,
+            rx,
+            current: None,
+        }
+    }
+
+    // Picks up a job that was started before the backend last exited, replaying its output so far
+    async fn resume(&mut self) {
+        if !unit_active().await {
+            return;
+        }
+
+        let name = fs::read_to_string(JOB_NAME_FILE).unwrap_or_default();
+        let Some(job) = JOBS.into_iter().find(|&job| job_name(job) == name.trim()) else {
+            return;
+        };
+
+        send(&self.socket_tx, JobEvent::Started(job));
+        self.current = Some(tokio::spawn(tail_job(self.socket_tx.clone())));
+    }
+
+    pub async fn run(mut self) {
+        self.resume().await;
+
+        while let Some(job) = self.rx.recv().await {
+            if self.current.as_ref().is_some_and(|x| !x.is_finished()) || unit_active().await {
+                let err = "another job is already running".to_string();
+                send(&self.socket_tx, JobEvent::Rejected(err));
+                continue;
+            }
+
+            self.current = Some(tokio::spawn(run_job(job, self.socket_tx.clone())));
+        }
+    }
+}
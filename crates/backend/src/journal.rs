@@ -0,0 +1,255 @@
+use std::{
+    collections::HashMap,
+    io::{BufRead, BufReader},
+    process::{Command, Stdio},
+};
+
+use proto::{
+    backend::{ActionBackendMessage, BackendMessage, LogEntry, LogResponse},
+    frontend::{LogFollowAction, LogQuery},
+};
+use serde_json::Value;
+use tokio::{
+    io::{AsyncBufReadExt, BufReader as AsyncBufReader},
+    process::Command as AsyncCommand,
+    sync::mpsc,
+    task::JoinHandle,
+};
+
+use crate::client::BackendContext;
+
+const PAGE_SIZE: usize = 100;
+// Frames are limited to 64KiB, so leave plenty of room for the rest of the response
+const PAGE_BYTES: usize = 40 * 1024;
+const MAX_MESSAGE_LEN: usize = 2048;
+
+fn journalctl_args(query: &LogQuery) -> Vec<String> {
+    let mut args = vec!["--output=json".into(), "--no-pager".into()];
+
+    if let Some(unit) = &query.unit {
+        args.push(format!("--unit={unit}"));
+    }
+    if let Some(priority) = query.priority {
+        args.push(format!("--priority={}", priority.min(7)));
+    }
+    if let Some(since) = &query.since {
+        args.push(format!("--since={since}"));
+    }
+    if let Some(until) = &query.until {
+        args.push(format!("--until={until}"));
+    }
+
+    args
+}
+
+fn field_str(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        // Non-UTF-8 fields are encoded as arrays of bytes
+        Value::Array(bytes) => {
+            let bytes: Vec<u8> = bytes
+                .iter()
+                .filter_map(|b| b.as_u64())
+                .map(|b| b as u8)
+                .collect();
+            Some(String::from_utf8_lossy(&bytes).into_owned())
+        }
+        _ => None,
+    }
+}
+
+fn truncate_message(mut message: String) -> String {
+    if message.len() > MAX_MESSAGE_LEN {
+        let mut end = MAX_MESSAGE_LEN;
+        while !message.is_char_boundary(end) {
+            end -= 1;
+        }
+        message.truncate(end);
+        message.push('…');
+    }
+
+    message
+}
+
+// Returns the entry along with its cursor
+fn parse_entry(line: &str) -> Option<(LogEntry, String)> {
+    let fields: HashMap<String, Value> = serde_json::from_str(line).ok()?;
+
+    let get = |key: &str| fields.get(key).and_then(field_str);
+
+    let cursor = get("__CURSOR")?;
+    let timestamp = get("__REALTIME_TIMESTAMP")?.parse().ok()?;
+    // Entries without a priority are treated as "info"
+    let priority = get("PRIORITY").and_then(|x| x.parse().ok()).unwrap_or(6);
+    let unit = get("_SYSTEMD_UNIT")
+        .or_else(|| get("SYSLOG_IDENTIFIER"))
+        .or_else(|| get("_COMM"))
+        .unwrap_or_default();
+    let message = truncate_message(get("MESSAGE").unwrap_or_default());
+
+    let entry = LogEntry {
+        timestamp,
+        priority,
+        unit,
+        message,
+    };
+
+    Some((entry, cursor))
+}
+
+fn matches_filter(entry: &LogEntry, filter: Option<&str>) -> bool {
+    filter.is_none_or(|filter| {
+        entry.message.to_lowercase().contains(filter) || entry.unit.to_lowercase().contains(filter)
+    })
+}
+
+pub fn query(_ctx: BackendContext, query: LogQuery) -> LogResponse {
+    let mut args = journalctl_args(&query);
+    // Show newest entries first, continuing backwards from the cursor if there is one
+    args.push("--reverse".into());
+    if let Some(cursor) = &query.cursor {
+        args.push(format!("--after-cursor={cursor}"));
+    }
+
+    let child = Command::new("journalctl")
+        .args(&args)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn();
+    let mut child = match child {
+        Ok(child) => child,
+        Err(err) => {
+            return LogResponse {
+                entries: Vec::new(),
+                next_cursor: None,
+                error: Some(format!("failed to run journalctl: {err}")),
+            };
+        }
+    };
+
+    let filter = query.filter.map(|x| x.to_lowercase());
+
+    let mut entries = Vec::new();
+    let mut next_cursor = None;
+    let mut page_bytes = 0;
+
+    let stdout = BufReader::new(child.stdout.take().unwrap());
+
+    for line in stdout.lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        let Some((entry, cursor)) = parse_entry(&line) else {
+            continue;
+        };
+
+        // Keep track of the cursor even for filtered entries, so the next page doesn't scan them again
+        next_cursor = Some(cursor);
+
+        if !matches_filter(&entry, filter.as_deref()) {
+            continue;
+        }
+
+        page_bytes += entry.message.len() + entry.unit.len();
+        entries.push(entry);
+
+        if entries.len() >= PAGE_SIZE || page_bytes >= PAGE_BYTES {
+            break;
+        }
+    }
+
+    let _ = child.kill();
+    let output = child.wait_with_output().ok();
+
+    let error = output
+        .filter(|output| !output.status.success() && entries.is_empty())
+        .map(|output| String::from_utf8_lossy(&output.stderr).trim().to_string())
+        .filter(|stderr| !stderr.is_empty());
+
+    // An incomplete page means we've reached the start of the journal
+    if entries.len() < PAGE_SIZE && page_bytes < PAGE_BYTES {
+        next_cursor = None;
+    }
+
+    LogResponse {
+        entries,
+        next_cursor,
+        error,
+    }
+}
+
+async fn follow(id: u16, query: LogQuery, socket_tx: mpsc::UnboundedSender<BackendMessage>) {
+    let mut args = journalctl_args(&query);
+    args.extend(["--follow".into(), "--lines=0".into()]);
+
+    let child = AsyncCommand::new("journalctl")
+        .args(&args)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn();
+    let Ok(mut child) = child else {
+        return;
+    };
+
+    let filter = query.filter.map(|x| x.to_lowercase());
+
+    let mut lines = AsyncBufReader::new(child.stdout.take().unwrap()).lines();
+
+    while let Ok(Some(line)) = lines.next_line().await {
+        let Some((entry, _)) = parse_entry(&line) else {
+            continue;
+        };
+
+        if !matches_filter(&entry, filter.as_deref()) {
+            continue;
+        }
+
+        let msg = ActionBackendMessage::LogFollow(id, entry);
+        let msg = BackendMessage::Action(msg);
+
+        if socket_tx.send(msg).is_err() {
+            break;
+        }
+    }
+}
+
+pub struct LogFollower {
+    socket_tx: mpsc::UnboundedSender<BackendMessage>,
+    rx: mpsc::UnboundedReceiver<LogFollowAction>,
+    tasks: HashMap<u16, JoinHandle<()>>,
+}
+
+impl LogFollower {
+    pub fn new(
+        socket_tx: mpsc::UnboundedSender<BackendMessage>,
+        rx: mpsc::UnboundedReceiver<LogFollowAction>,
+    ) -> Self {
+        Self {
+            socket_tx,
+            rx,
+            tasks: HashMap::new(),
+        }
+    }
+
+    pub async fn run(mut self) {
+        while let Some(action) = self.rx.recv().await {
+            match action {
+                LogFollowAction::Start(id, query) => {
+                    let task = tokio::spawn(follow(id, query, self.socket_tx.clone()));
+
+                    // Frontend reused an id, so the old follower is no longer wanted
+                    if let Some(old_task) = self.tasks.insert(id, task) {
+                        old_task.abort();
+                    }
+                }
+                LogFollowAction::Stop(id) => {
+                    // Aborting drops the child process, which kills journalctl
+                    if let Some(task) = self.tasks.remove(&id) {
+                        task.abort();
+                    }
+                }
+            }
+        }
+    }
+}
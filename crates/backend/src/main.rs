@@ -6,6 +6,8 @@ use config::{
     APP_VERSION,
     backend::{BackendConfig, get_config},
 };
+use jobs::JobRunner;
+use journal::LogFollower;
 use log::{error, info};
 use simple_logger::SimpleLogger;
 use terminal::Terminal;
@@ -13,8 +15,22 @@ use tokio::sync::mpsc;
 
 mod actions;
 mod client;
+mod containers;
+mod diskstats;
+mod files;
+mod firewall;
 mod getters;
+mod jobs;
+mod journal;
+mod memory;
+mod network;
+mod process;
+mod schedule;
+mod sensors;
+mod systemd;
 mod terminal;
+mod updates;
+mod users;
 
 pub type SharedConfig = Arc<BackendConfig>;
 
@@ -29,6 +45,8 @@ async fn main() -> Result<()> {
 
     info!("Starting DietPi-Dashboard backend v{APP_VERSION}...");
 
+    firewall::restore();
+
     info!("Connecting to {}", config.frontend_addr);
 
     let (term_tx, term_rx) = mpsc::unbounded_channel();
@@ -37,11 +55,23 @@ async fn main() -> Result<()> {
     let terminal = Terminal::new(socket_tx.clone(), term_rx).context("terminal build error")?;
     tokio::spawn(terminal.run());
 
+    let (log_tx, log_rx) = mpsc::unbounded_channel();
+
+    let log_follower = LogFollower::new(socket_tx.clone(), log_rx);
+    tokio::spawn(log_follower.run());
+
+    let (job_tx, job_rx) = mpsc::unbounded_channel();
+
+    let job_runner = JobRunner::new(socket_tx.clone(), job_rx);
+    tokio::spawn(job_runner.run());
+
     let system = Arc::new(Mutex::new(SystemComponents::new()));
     let context = BackendContext {
         config,
         system,
         term_tx,
+        log_tx,
+        job_tx,
         socket_tx,
     };
 
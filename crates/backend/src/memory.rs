@@ -0,0 +1,97 @@
+use std::{collections::HashMap, fs};
+
+use proto::backend::{MemBreakdown, PressureInfo, ZramInfo};
+
+use crate::getters::round_to_2;
+
+// Parses /proc/meminfo into bytes, keyed by field name
+fn meminfo() -> HashMap<String, u64> {
+    let meminfo = fs::read_to_string("/proc/meminfo").unwrap_or_default();
+
+    meminfo
+        .lines()
+        .filter_map(|line| {
+            let (name, value) = line.split_once(':')?;
+            // Values are in KiB, despite the "kB" suffix
+            let kib: u64 = value.trim().trim_end_matches(" kB").parse().ok()?;
+
+            Some((name.to_string(), kib * 1024))
+        })
+        .collect()
+}
+
+pub fn breakdown() -> Option<MemBreakdown> {
+    let info = meminfo();
+    let field = |name: &str| info.get(name).copied();
+
+    Some(MemBreakdown {
+        available: field("MemAvailable")?,
+        buffers: field("Buffers")?,
+        // Reclaimable slab memory is cache in all but name
+        cached: field("Cached")? + field("SReclaimable").unwrap_or(0),
+        shared: field("Shmem")?,
+        dirty: field("Dirty")?,
+        // Only present if the kernel was built with zswap support
+        zswap: field("Zswap"),
+        zswapped: field("Zswapped"),
+    })
+}
+
+pub fn zram() -> Vec<ZramInfo> {
+    let Ok(devices) = fs::read_dir("/sys/block") else {
+        return Vec::new();
+    };
+
+    let mut zram: Vec<ZramInfo> = devices
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_name().to_string_lossy().starts_with("zram"))
+        .filter_map(|entry| {
+            // Fields are orig_data_size, compr_data_size and mem_used_total, followed by others
+            let mm_stat = fs::read_to_string(entry.path().join("mm_stat")).ok()?;
+            let mut fields = mm_stat.split_whitespace().map(|x| x.parse().ok());
+
+            Some(ZramInfo {
+                name: entry.file_name().to_string_lossy().into(),
+                orig_data: fields.next()??,
+                compr_data: fields.next()??,
+                mem_used: fields.next()??,
+            })
+        })
+        .filter(|x| x.orig_data > 0)
+        .collect();
+    zram.sort_by(|a, b| a.name.cmp(&b.name));
+
+    zram
+}
+
+// Parses a line like "some avg10=0.00 avg60=0.00 avg300=0.00 total=0"
+fn parse_pressure_line(line: &str) -> Option<[f32; 3]> {
+    let mut avgs = line
+        .split_whitespace()
+        .filter_map(|x| x.split_once('='))
+        .filter(|(name, _)| name.starts_with("avg"))
+        .map(|(_, value)| value.parse().ok().map(round_to_2));
+
+    Some([avgs.next()??, avgs.next()??, avgs.next()??])
+}
+
+// Pressure stall information isn't available on kernels without CONFIG_PSI or with psi=0
+pub fn pressure() -> Vec<PressureInfo> {
+    ["cpu", "memory", "io"]
+        .into_iter()
+        .filter_map(|resource| {
+            let file = fs::read_to_string(format!("/proc/pressure/{resource}")).ok()?;
+            let line = |prefix: &str| {
+                file.lines()
+                    .find(|line| line.starts_with(prefix))
+                    .and_then(parse_pressure_line)
+            };
+
+            Some(PressureInfo {
+                resource: resource.into(),
+                some: line("some ")?,
+                full: line("full "),
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,653 @@
+use std::{
+    collections::HashMap,
+    fs::{self, OpenOptions, Permissions},
+    io::{self, Write},
+    net::{IpAddr, Ipv4Addr},
+    path::Path,
+    process::Command,
+    thread,
+    time::Duration,
+};
+
+use proto::{
+    backend::{InterfaceConfig, NetworkConfigResponse, WifiBackend, WifiNetwork},
+    frontend::{AddressMethod, NetworkChange, WifiConnectAction},
+};
+use sysinfo::Networks;
+
+use crate::{client::BackendContext, files, getters};
+
+const INTERFACES_FILE: &str = "/etc/network/interfaces";
+const RESOLV_CONF: &str = "/etc/resolv.conf";
+const ROLLBACK_DIR: &str = "/var/lib/dietpi-dashboard/network-rollback";
+const ROLLBACK_UNIT: &str = "dietpi-dashboard-network-rollback";
+const ROLLBACK_SECS: u32 = 90;
+
+const SCAN_WAIT: Duration = Duration::from_secs(4);
+const MAX_SCAN_RESULTS: usize = 50;
+
+fn first_word(line: &str) -> &str {
+    line.split_whitespace().next().unwrap_or_default()
+}
+
+fn is_stanza_start(line: &str) -> bool {
+    let word = first_word(line);
+
+    ["iface", "mapping", "auto", "source", "source-directory"].contains(&word)
+        || word.starts_with("allow-")
+}
+
+// Returns the range of lines making up an interface's IPv4 stanza, including the iface line
+fn find_stanza(lines: &[&str], name: &str) -> Option<(usize, usize)> {
+    let start = lines.iter().position(|line| {
+        let mut words = line.split_whitespace();
+        words.next() == Some("iface") && words.next() == Some(name) && words.next() == Some("inet")
+    })?;
+
+    let end = lines[start + 1..]
+        .iter()
+        .position(|line| is_stanza_start(line))
+        .map_or(lines.len(), |x| start + 1 + x);
+
+    Some((start, end))
+}
+
+fn stanza_option<'a>(lines: &[&'a str], option: &str) -> Option<&'a str> {
+    lines.iter().find_map(|line| {
+        let mut words = line.split_whitespace();
+        (words.next() == Some(option))
+            .then(|| words.next())
+            .flatten()
+    })
+}
+
+fn netmask_prefix(netmask: &str) -> Option<u32> {
+    let mask: Ipv4Addr = netmask.parse().ok()?;
+    Some(u32::from(mask).count_ones())
+}
+
+fn default_gateways() -> HashMap<String, String> {
+    let routes = fs::read_to_string("/proc/net/route").unwrap_or_default();
+
+    routes
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let iface = fields.next()?;
+            let destination = fields.next()?;
+            let gateway = u32::from_str_radix(fields.next()?, 16).ok()?;
+
+            // Addresses are stored in host byte order
+            (destination == "00000000").then(|| {
+                let gateway = Ipv4Addr::from(gateway.to_le_bytes());
+                (iface.to_string(), gateway.to_string())
+            })
+        })
+        .collect()
+}
+
+fn nameservers() -> Vec<String> {
+    let file = fs::read_to_string(RESOLV_CONF).unwrap_or_default();
+
+    file.lines()
+        .filter(|line| first_word(line) == "nameserver")
+        .filter_map(|line| line.split_whitespace().nth(1))
+        .map(String::from)
+        .collect()
+}
+
+fn is_wireless(name: &str) -> bool {
+    Path::new("/sys/class/net")
+        .join(name)
+        .join("wireless")
+        .exists()
+}
+
+fn wireless_interface() -> Option<String> {
+    let mut names: Vec<_> = fs::read_dir("/sys/class/net")
+        .ok()?
+        .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+        .filter(|name| is_wireless(name))
+        .collect();
+    names.sort();
+
+    names.into_iter().next()
+}
+
+fn is_editable() -> bool {
+    Path::new(INTERFACES_FILE).exists()
+        && ["/sbin/ifup", "/usr/sbin/ifup"]
+            .iter()
+            .any(|x| Path::new(x).exists())
+}
+
+fn wifi_backend() -> Option<WifiBackend> {
+    // NetworkManager drives wpa_supplicant itself when it's running, so it has to be used instead
+    let nm_running = Command::new("nmcli")
+        .args(["-t", "-f", "RUNNING", "general"])
+        .output()
+        .is_ok_and(|output| output.status.success() && output.stdout.starts_with(b"running"));
+
+    if nm_running {
+        Some(WifiBackend::NetworkManager)
+    } else if Command::new("wpa_cli").arg("-v").output().is_ok() {
+        Some(WifiBackend::WpaSupplicant)
+    } else {
+        None
+    }
+}
+
+pub fn rollback_pending(unit: &str) -> bool {
+    Command::new("systemctl")
+        .args(["is-active", "--quiet", &format!("{unit}.timer")])
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+// The backend exits when it loses its connection, so rollbacks have to be handled by systemd.
+// Collecting the unit even if it fails keeps the name free for the next change.
+pub fn arm_rollback(unit: &str, description: &str, secs: u32, script: &str) -> Result<(), String> {
+    getters::run_checked(Command::new("systemd-run").args([
+        &format!("--unit={unit}"),
+        "--collect",
+        &format!("--description={description}"),
+        &format!("--on-active={secs}s"),
+        "--timer-property=AccuracySec=1s",
+        "/bin/sh",
+        "-c",
+        script,
+    ]))
+    .map_err(|err| format!("failed to schedule rollback: {err}"))
+}
+
+pub fn cancel_rollback(unit: &str) -> Result<(), String> {
+    if !rollback_pending(unit) {
+        return Err(
+            "no change is waiting to be confirmed, it may have already been rolled back".into(),
+        );
+    }
+
+    getters::run_checked(Command::new("systemctl").args(["stop", &format!("{unit}.timer")]))
+}
+
+pub fn config(ctx: BackendContext) -> NetworkConfigResponse {
+    let interfaces_file = fs::read_to_string(INTERFACES_FILE).unwrap_or_default();
+    let lines: Vec<&str> = interfaces_file.lines().collect();
+    let gateways = default_gateways();
+
+    // Use a separate list, since refreshing the shared one would throw off the network rates
+    let networks = Networks::new_with_refreshed_list();
+
+    let mut interfaces: Vec<_> = networks
+        .iter()
+        .filter(|(name, _)| !getters::interface_hidden(&ctx.config.hidden_interfaces, name))
+        .map(|(name, net)| {
+            let stanza = find_stanza(&lines, name).map(|(start, end)| &lines[start..end]);
+
+            let method = stanza.and_then(|stanza| match stanza[0].split_whitespace().nth(3) {
+                Some("dhcp") => Some(AddressMethod::Dhcp),
+                Some("static") => Some(AddressMethod::Static),
+                _ => None,
+            });
+            let static_address = stanza
+                .and_then(|stanza| {
+                    let address = stanza_option(stanza, "address")?;
+                    if address.contains('/') {
+                        return Some(address.to_string());
+                    }
+                    let prefix = stanza_option(stanza, "netmask").and_then(netmask_prefix)?;
+                    Some(format!("{address}/{prefix}"))
+                })
+                .unwrap_or_default();
+            let static_gateway = stanza
+                .and_then(|stanza| stanza_option(stanza, "gateway"))
+                .unwrap_or_default()
+                .to_string();
+
+            InterfaceConfig {
+                name: name.clone(),
+                mac: net.mac_address().to_string(),
+                up: getters::read_sys_net(name, "operstate").is_some_and(|x| x == "up"),
+                addresses: net
+                    .ip_networks()
+                    .iter()
+                    .map(|x| format!("{}/{}", x.addr, x.prefix))
+                    .collect(),
+                gateway: gateways.get(name).cloned(),
+                wireless: is_wireless(name),
+                method,
+                static_address,
+                static_gateway,
+            }
+        })
+        .collect();
+    interfaces.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let wifi = interfaces
+        .iter()
+        .any(|x| x.wireless)
+        .then(wifi_backend)
+        .flatten();
+
+    NetworkConfigResponse {
+        interfaces,
+        dns: nameservers(),
+        editable: is_editable(),
+        rollback_pending: rollback_pending(ROLLBACK_UNIT),
+        wifi,
+    }
+}
+
+fn is_valid_interface(name: &str) -> bool {
+    // Also keeps the name safe to use in the rollback script
+    (1..=15).contains(&name.len())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "._-".contains(c))
+        && !name.starts_with(['-', '.'])
+        && Path::new("/sys/class/net").join(name).exists()
+}
+
+fn parse_cidr(address: &str) -> Option<(Ipv4Addr, u32)> {
+    let (addr, prefix) = address.split_once('/')?;
+    let prefix: u32 = prefix.parse().ok()?;
+
+    (1..=32)
+        .contains(&prefix)
+        .then_some((addr.parse().ok()?, prefix))
+}
+
+fn update_interfaces(contents: &str, change: &NetworkChange) -> Result<String, String> {
+    let current: Vec<&str> = contents.lines().collect();
+    let mut lines: Vec<String> = current.iter().map(|x| x.to_string()).collect();
+
+    let (start, end) = match find_stanza(&current, &change.interface) {
+        Some(range) => range,
+        None => {
+            lines.push(String::new());
+            lines.push(format!("allow-hotplug {}", change.interface));
+            lines.push(String::new());
+            (lines.len() - 1, lines.len())
+        }
+    };
+
+    let method = match change.method {
+        AddressMethod::Dhcp => "dhcp",
+        AddressMethod::Static => "static",
+    };
+    lines[start] = format!("iface {} inet {method}", change.interface);
+
+    // Replace the old addressing, but keep everything else, like wpa-conf
+    let mut body: Vec<String> = lines[start + 1..end]
+        .iter()
+        .filter(|line| !["address", "netmask", "gateway"].contains(&first_word(line)))
+        .cloned()
+        .collect();
+
+    if change.method == AddressMethod::Static {
+        let (address, prefix) = parse_cidr(change.address.trim())
+            .ok_or("static address must be like 192.168.1.2/24")?;
+        let netmask = Ipv4Addr::from(u32::MAX << (32 - prefix));
+
+        let mut options = vec![format!("address {address}"), format!("netmask {netmask}")];
+
+        let gateway = change.gateway.trim();
+        if !gateway.is_empty() {
+            let gateway: Ipv4Addr = gateway.parse().map_err(|_| "invalid gateway address")?;
+            options.push(format!("gateway {gateway}"));
+        }
+
+        body.splice(0..0, options);
+    }
+
+    lines.splice(start + 1..end, body);
+
+    let mut contents = lines.join("\n");
+    contents.push('\n');
+    Ok(contents)
+}
+
+fn update_resolv_conf(dns: &[String]) -> Result<String, String> {
+    if fs::symlink_metadata(RESOLV_CONF).is_ok_and(|meta| meta.file_type().is_symlink()) {
+        return Err(format!("{RESOLV_CONF} is managed by another service"));
+    }
+
+    let mut lines = Vec::new();
+    for server in dns {
+        let server: IpAddr = server
+            .trim()
+            .parse()
+            .map_err(|_| format!("invalid DNS server: {server}"))?;
+        lines.push(format!("nameserver {server}"));
+    }
+
+    // Keep search domains and options
+    let current = fs::read_to_string(RESOLV_CONF).unwrap_or_default();
+    lines.extend(
+        current
+            .lines()
+            .filter(|line| first_word(line) != "nameserver")
+            .map(String::from),
+    );
+
+    let mut contents = lines.join("\n");
+    contents.push('\n');
+    Ok(contents)
+}
+
+// Writes a new file and renames it into place, so a symlink at either path is replaced rather than
+// written through, and nothing ever sees half of the contents
+fn write_new_file(path: &str, data: &[u8], permissions: Option<Permissions>) -> io::Result<()> {
+    let tmp_path = format!("{path}.dashboard-tmp");
+
+    // Only removes a leftover link itself, creating the file then fails if anything takes its place
+    let _ = fs::remove_file(&tmp_path);
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    let mut file = files::open_nofollow(Path::new(&tmp_path), &mut options)?;
+
+    let result = file
+        .write_all(data)
+        .and_then(|_| match permissions {
+            Some(permissions) => file.set_permissions(permissions),
+            None => Ok(()),
+        })
+        .and_then(|_| fs::rename(&tmp_path, path));
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp_path);
+    }
+
+    result
+}
+
+pub fn replace_file(path: &str, contents: &str) -> io::Result<()> {
+    let permissions = fs::symlink_metadata(path)
+        .ok()
+        .filter(|meta| meta.is_file())
+        .map(|meta| meta.permissions());
+
+    write_new_file(path, contents.as_bytes(), permissions)
+}
+
+// Keeps the permissions, so restoring with `cp -p` puts back the original file as it was
+pub fn backup_file(src: &str, dst: &str) -> io::Result<()> {
+    let data = fs::read(src)?;
+    let permissions = fs::metadata(src)?.permissions();
+
+    write_new_file(dst, &data, Some(permissions))
+}
+
+pub fn apply(_ctx: BackendContext, change: NetworkChange) -> Result<u32, String> {
+    if !is_editable() {
+        return Err("only ifupdown configurations can be edited".into());
+    }
+    if rollback_pending(ROLLBACK_UNIT) {
+        return Err("a previous change is still waiting to be confirmed".into());
+    }
+    if !is_valid_interface(&change.interface) {
+        return Err(format!("invalid interface: {}", change.interface));
+    }
+
+    let interfaces = fs::read_to_string(INTERFACES_FILE).map_err(|err| err.to_string())?;
+    let new_interfaces = update_interfaces(&interfaces, &change)?;
+    let new_resolv = if change.dns.is_empty() {
+        None
+    } else {
+        Some(update_resolv_conf(&change.dns)?)
+    };
+
+    fs::create_dir_all(ROLLBACK_DIR).map_err(|err| err.to_string())?;
+    backup_file(INTERFACES_FILE, &format!("{ROLLBACK_DIR}/interfaces"))
+        .map_err(|err| format!("failed to back up {INTERFACES_FILE}: {err}"))?;
+    let mut restore = format!("cp -p {ROLLBACK_DIR}/interfaces {INTERFACES_FILE}");
+    if new_resolv.is_some() {
+        backup_file(RESOLV_CONF, &format!("{ROLLBACK_DIR}/resolv.conf"))
+            .map_err(|err| format!("failed to back up {RESOLV_CONF}: {err}"))?;
+        restore.push_str(&format!("; cp -p {ROLLBACK_DIR}/resolv.conf {RESOLV_CONF}"));
+    }
+
+    let restart = format!("ifdown --force {0}; ifup {0}", change.interface);
+
+    arm_rollback(
+        ROLLBACK_UNIT,
+        "Roll back network changes made from DietPi-Dashboard",
+        ROLLBACK_SECS,
+        &format!("{restore}; {restart}"),
+    )?;
+
+    // If writing fails part way through, the rollback puts back whatever was already written
+    replace_file(INTERFACES_FILE, &new_interfaces).map_err(|err| err.to_string())?;
+    if let Some(resolv) = new_resolv {
+        replace_file(RESOLV_CONF, &resolv).map_err(|err| err.to_string())?;
+    }
+
+    // Also restarted outside of the backend, which might be stopped along with the connection.
+    // The delay lets the response reach the frontend first.
+    getters::run_checked(Command::new("systemd-run").args([
+        "--collect",
+        "--description=Apply network changes made from DietPi-Dashboard",
+        "/bin/sh",
+        "-c",
+        &format!("sleep 1; {restart}"),
+    ]))?;
+
+    Ok(ROLLBACK_SECS)
+}
+
+pub fn confirm(_ctx: BackendContext) -> Result<(), String> {
+    cancel_rollback(ROLLBACK_UNIT)
+}
+
+// nmcli escapes colons in values with a backslash in terse mode
+fn split_terse(line: &str) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut chars = line.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => fields.last_mut().unwrap().extend(chars.next()),
+            ':' => fields.push(String::new()),
+            c => fields.last_mut().unwrap().push(c),
+        }
+    }
+
+    fields
+}
+
+// wpa_cli escapes unprintable bytes in SSIDs like printf
+fn unescape_ssid(ssid: &str) -> String {
+    let mut bytes = Vec::with_capacity(ssid.len());
+    let mut iter = ssid.bytes();
+
+    while let Some(b) = iter.next() {
+        if b != b'\\' {
+            bytes.push(b);
+            continue;
+        }
+        match iter.next() {
+            Some(b'x') => {
+                let hex: Vec<u8> = iter.by_ref().take(2).collect();
+                let byte = std::str::from_utf8(&hex)
+                    .ok()
+                    .and_then(|x| u8::from_str_radix(x, 16).ok());
+                bytes.extend(byte);
+            }
+            Some(b'n') => bytes.push(b'\n'),
+            Some(b't') => bytes.push(b'\t'),
+            Some(other) => bytes.push(other),
+            None => {}
+        }
+    }
+
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+fn wpa_cli(iface: &str, args: &[&str]) -> Result<String, String> {
+    let output = Command::new("wpa_cli")
+        .args(["-i", iface])
+        .args(args)
+        .output()
+        .map_err(|err| err.to_string())?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if !output.status.success() || stdout.starts_with("FAIL") {
+        return Err(format!("wpa_cli {} failed: {stdout}", args[0]));
+    }
+
+    Ok(stdout)
+}
+
+fn scan_network_manager() -> Result<Vec<WifiNetwork>, String> {
+    let output = Command::new("nmcli")
+        .args([
+            "-t",
+            "-f",
+            "IN-USE,SSID,SIGNAL,SECURITY",
+            "device",
+            "wifi",
+            "list",
+            "--rescan",
+            "yes",
+        ])
+        .output()
+        .map_err(|err| err.to_string())?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    Ok(stdout
+        .lines()
+        .filter_map(|line| {
+            let fields = split_terse(line);
+            let [in_use, ssid, signal, security] = fields.as_slice() else {
+                return None;
+            };
+
+            Some(WifiNetwork {
+                ssid: ssid.clone(),
+                signal: signal.parse().unwrap_or(0),
+                secured: !security.is_empty() && security != "--",
+                connected: in_use == "*",
+            })
+        })
+        .collect())
+}
+
+fn scan_wpa_supplicant() -> Result<Vec<WifiNetwork>, String> {
+    let iface = wireless_interface().ok_or("no wireless interface found")?;
+
+    wpa_cli(&iface, &["scan"])?;
+    thread::sleep(SCAN_WAIT);
+    let results = wpa_cli(&iface, &["scan_results"])?;
+
+    let status = wpa_cli(&iface, &["status"]).unwrap_or_default();
+    let current_ssid = status
+        .lines()
+        .find_map(|line| line.strip_prefix("ssid="))
+        .map(unescape_ssid);
+
+    // Skip the header
+    Ok(results
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            // BSSID, frequency, signal level in dBm, flags, SSID
+            let mut fields = line.splitn(5, '\t');
+            let signal: i32 = fields.nth(2)?.parse().ok()?;
+            let flags = fields.next()?;
+            let ssid = unescape_ssid(fields.next()?);
+
+            Some(WifiNetwork {
+                connected: current_ssid.as_ref() == Some(&ssid),
+                ssid,
+                // Roughly how NetworkManager converts dBm into a percentage
+                signal: (2 * (signal + 100)).clamp(0, 100) as u8,
+                secured: ["WPA", "WEP", "SAE"].iter().any(|x| flags.contains(x)),
+            })
+        })
+        .collect())
+}
+
+pub fn wifi_scan(_ctx: BackendContext) -> Result<Vec<WifiNetwork>, String> {
+    let mut networks = match wifi_backend() {
+        Some(WifiBackend::NetworkManager) => scan_network_manager()?,
+        Some(WifiBackend::WpaSupplicant) => scan_wpa_supplicant()?,
+        None => return Err("neither NetworkManager nor wpa_supplicant is available".into()),
+    };
+
+    // Access points for the same network show up separately, so only keep the strongest
+    networks.retain(|x| !x.ssid.is_empty());
+    networks.sort_by(|a, b| b.connected.cmp(&a.connected).then(b.signal.cmp(&a.signal)));
+    let mut seen = Vec::new();
+    networks.retain(|x| {
+        let new = !seen.contains(&x.ssid);
+        seen.push(x.ssid.clone());
+        new
+    });
+    networks.truncate(MAX_SCAN_RESULTS);
+
+    Ok(networks)
+}
+
+fn connect_wpa_supplicant(action: &WifiConnectAction) -> Result<(), String> {
+    let iface = wireless_interface().ok_or("no wireless interface found")?;
+
+    let id = wpa_cli(&iface, &["add_network"])?;
+    if id.parse::<u32>().is_err() {
+        return Err(format!("unexpected response from wpa_cli: {id}"));
+    }
+
+    // Passing the SSID as hex avoids having to quote it
+    let hex_ssid: String = action.ssid.bytes().map(|b| format!("{b:02x}")).collect();
+    let psk = format!("\"{}\"", action.password);
+
+    let result = wpa_cli(&iface, &["set_network", &id, "ssid", &hex_ssid])
+        .and_then(|_| {
+            if action.password.is_empty() {
+                wpa_cli(&iface, &["set_network", &id, "key_mgmt", "NONE"])
+            } else {
+                wpa_cli(&iface, &["set_network", &id, "psk", &psk])
+            }
+        })
+        .and_then(|_| wpa_cli(&iface, &["enable_network", &id]))
+        .and_then(|_| wpa_cli(&iface, &["save_config"]));
+
+    if result.is_err() {
+        let _ = wpa_cli(&iface, &["remove_network", &id]);
+    }
+
+    result.map(|_| ())
+}
+
+pub fn wifi_connect(_ctx: BackendContext, action: WifiConnectAction) -> Result<(), String> {
+    if !(1..=32).contains(&action.ssid.len()) || action.ssid.chars().any(char::is_control) {
+        return Err("SSID must be between 1 and 32 bytes".into());
+    }
+    let valid_password = action.password.is_empty()
+        || ((8..=63).contains(&action.password.len())
+            && action
+                .password
+                .chars()
+                .all(|c| c.is_ascii_graphic() || c == ' ')
+            && !action.password.contains('"'));
+    if !valid_password {
+        return Err(
+            "password must be between 8 and 63 printable ASCII characters, without quotes".into(),
+        );
+    }
+
+    match wifi_backend() {
+        Some(WifiBackend::NetworkManager) => {
+            let mut cmd = Command::new("nmcli");
+            cmd.args(["device", "wifi", "connect", &action.ssid]);
+            if !action.password.is_empty() {
+                cmd.args(["password", &action.password]);
+            }
+            getters::run_checked(&mut cmd)
+        }
+        Some(WifiBackend::WpaSupplicant) => connect_wpa_supplicant(&action),
+        None => Err("neither NetworkManager nor wpa_supplicant is available".into()),
+    }
+}
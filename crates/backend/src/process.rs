@@ -0,0 +1,232 @@
+use std::{
+    collections::HashMap,
+    ffi::OsString,
+    fs,
+    net::{Ipv4Addr, Ipv6Addr, SocketAddr},
+};
+
+use proto::backend::{ListeningSocket, OpenFile, ProcessDetails, SocketProtocol};
+use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, UpdateKind};
+
+use crate::{
+    client::BackendContext,
+    files::id_names,
+    getters::{process_status, round_to_2},
+};
+
+// Keep the response well within a single frame, even for processes with huge environments
+const MAX_CMDLINE_BYTES: usize = 8 * 1024;
+const MAX_ENVIRON_BYTES: usize = 16 * 1024;
+const MAX_OPEN_FILES_BYTES: usize = 16 * 1024;
+
+// TCP_LISTEN in the kernel's socket state enum
+const TCP_LISTEN: &str = "0A";
+// TCP_CLOSE, which is what unconnected UDP sockets report
+const UDP_UNCONNECTED: &str = "07";
+
+// Takes strings until they would exceed the byte budget
+fn take_within(strings: &[OsString], budget: usize) -> Vec<String> {
+    let mut used = 0;
+
+    strings
+        .iter()
+        .map(|x| x.to_string_lossy().into_owned())
+        .take_while(|x| {
+            used += x.len();
+            used <= budget
+        })
+        .collect()
+}
+
+// Returns the nice value and thread count from /proc/<pid>/stat
+fn stat_fields(pid: u32) -> Option<(i32, u32)> {
+    let stat = fs::read_to_string(format!("/proc/{pid}/stat")).ok()?;
+
+    // The name can contain spaces and parentheses, so skip past the last one.
+    // The remaining fields start at field 3 (state).
+    let (_, rest) = stat.rsplit_once(')')?;
+    let fields: Vec<&str> = rest.split_whitespace().collect();
+
+    let nice = fields.get(16)?.parse().ok()?;
+    let threads = fields.get(17)?.parse().ok()?;
+
+    Some((nice, threads))
+}
+
+fn cpu_affinity(pid: u32) -> Option<String> {
+    let status = fs::read_to_string(format!("/proc/{pid}/status")).ok()?;
+
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix("Cpus_allowed_list:"))
+        .map(|x| x.trim().to_string())
+}
+
+// Returns open file descriptors, along with the inodes of any sockets among them
+pub fn open_files(pid: u32) -> (Vec<OpenFile>, Vec<u64>) {
+    let Ok(dir) = fs::read_dir(format!("/proc/{pid}/fd")) else {
+        return (Vec::new(), Vec::new());
+    };
+
+    let mut files: Vec<OpenFile> = dir
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            let fd = entry.file_name().to_str()?.parse().ok()?;
+            let target = fs::read_link(entry.path()).ok()?;
+
+            Some(OpenFile {
+                fd,
+                target: target.to_string_lossy().into(),
+            })
+        })
+        .collect();
+    files.sort_by_key(|x| x.fd);
+
+    let socket_inodes = files
+        .iter()
+        .filter_map(|x| {
+            x.target
+                .strip_prefix("socket:[")?
+                .strip_suffix(']')?
+                .parse()
+                .ok()
+        })
+        .collect();
+
+    (files, socket_inodes)
+}
+
+// Parses an address from /proc/net, which is in network byte order but printed as native u32s
+fn parse_addr(addr: &str) -> Option<SocketAddr> {
+    let (ip, port) = addr.split_once(':')?;
+    let port = u16::from_str_radix(port, 16).ok()?;
+
+    let words = (0..ip.len())
+        .step_by(8)
+        .map(|i| u32::from_str_radix(ip.get(i..i + 8)?, 16).ok())
+        .collect::<Option<Vec<u32>>>()?;
+    let bytes: Vec<u8> = words.iter().flat_map(|x| x.to_ne_bytes()).collect();
+
+    let ip = match bytes.len() {
+        4 => Ipv4Addr::from(<[u8; 4]>::try_from(bytes).ok()?).into(),
+        16 => Ipv6Addr::from(<[u8; 16]>::try_from(bytes).ok()?).into(),
+        _ => return None,
+    };
+
+    Some(SocketAddr::new(ip, port))
+}
+
+// Maps socket inodes to listening addresses for every listening socket on the system
+pub fn listening_sockets() -> HashMap<u64, ListeningSocket> {
+    let tables = [
+        ("/proc/net/tcp", SocketProtocol::Tcp, TCP_LISTEN),
+        ("/proc/net/tcp6", SocketProtocol::Tcp, TCP_LISTEN),
+        ("/proc/net/udp", SocketProtocol::Udp, UDP_UNCONNECTED),
+        ("/proc/net/udp6", SocketProtocol::Udp, UDP_UNCONNECTED),
+    ];
+
+    let mut sockets = HashMap::new();
+
+    for (path, protocol, listen_state) in tables {
+        let Ok(table) = fs::read_to_string(path) else {
+            continue;
+        };
+
+        for line in table.lines().skip(1) {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.get(3) != Some(&listen_state) {
+                continue;
+            }
+
+            let Some(address) = fields.get(1).and_then(|x| parse_addr(x)) else {
+                continue;
+            };
+            let Some(inode) = fields.get(9).and_then(|x| x.parse().ok()) else {
+                continue;
+            };
+
+            sockets.insert(
+                inode,
+                ListeningSocket {
+                    protocol,
+                    address: address.to_string(),
+                },
+            );
+        }
+    }
+
+    sockets
+}
+
+pub fn process_detail(mut ctx: BackendContext, pid: u32) -> Option<Box<ProcessDetails>> {
+    let sys = &mut ctx.system().system;
+
+    sys.refresh_processes_specifics(
+        ProcessesToUpdate::Some(&[Pid::from_u32(pid)]),
+        true,
+        ProcessRefreshKind::new()
+            .with_cpu()
+            .with_memory()
+            .with_disk_usage()
+            .with_user(UpdateKind::OnlyIfNotSet)
+            .with_cmd(UpdateKind::OnlyIfNotSet)
+            .with_exe(UpdateKind::OnlyIfNotSet)
+            .with_environ(UpdateKind::Always),
+    );
+
+    let proc = sys.process(Pid::from_u32(pid))?;
+
+    let (nice, threads) = stat_fields(pid).unwrap_or_default();
+    let cpu_affinity = cpu_affinity(pid).unwrap_or_default();
+
+    let (mut files, socket_inodes) = open_files(pid);
+    let mut used = 0;
+    let fits = files
+        .iter()
+        .take_while(|x| {
+            used += x.target.len();
+            used <= MAX_OPEN_FILES_BYTES
+        })
+        .count();
+    let open_files_truncated = fits < files.len();
+    files.truncate(fits);
+
+    let mut sockets = listening_sockets();
+    let listening = socket_inodes
+        .iter()
+        .filter_map(|inode| sockets.remove(inode))
+        .collect();
+
+    let disk_usage = proc.disk_usage();
+
+    Some(Box::new(ProcessDetails {
+        pid,
+        name: proc.name().to_string_lossy().into(),
+        cmdline: take_within(proc.cmd(), MAX_CMDLINE_BYTES),
+        exe: proc.exe().map(|x| x.to_string_lossy().into()),
+        user: proc.user_id().map(|uid| {
+            id_names("/etc/passwd")
+                .remove(uid)
+                .unwrap_or(uid.to_string())
+        }),
+        group: proc.group_id().map(|gid| {
+            id_names("/etc/group")
+                .remove(&gid)
+                .unwrap_or(gid.to_string())
+        }),
+        parent: proc.parent().map(|x| x.as_u32()),
+        start_time: proc.start_time(),
+        threads,
+        nice,
+        cpu_affinity,
+        status: process_status(proc.status()),
+        cpu: round_to_2(proc.cpu_usage()),
+        mem: proc.memory(),
+        disk_read: disk_usage.total_read_bytes,
+        disk_written: disk_usage.total_written_bytes,
+        environment: take_within(proc.environ(), MAX_ENVIRON_BYTES),
+        open_files: files,
+        open_files_truncated,
+        listening,
+    }))
+}
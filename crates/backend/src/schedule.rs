@@ -0,0 +1,339 @@
+use std::{
+    fs,
+    io::Write,
+    process::{Command, Stdio},
+};
+
+use proto::{
+    backend::{CronEntry, CronResponse, CronSource, TimerInfo},
+    frontend::{CronAction, CronOperation},
+};
+
+use crate::{client::BackendContext, systemd, users};
+
+const SPOOL_DIR: &str = "/var/spool/cron/crontabs";
+const SYSTEM_CRONTAB: &str = "/etc/crontab";
+const SYSTEM_CRON_DIR: &str = "/etc/cron.d";
+
+const SPECIAL_SCHEDULES: &[&str] = &[
+    "@reboot",
+    "@yearly",
+    "@annually",
+    "@monthly",
+    "@weekly",
+    "@daily",
+    "@midnight",
+    "@hourly",
+];
+
+struct ParsedLine<'a> {
+    schedule: String,
+    user: &'a str,
+    command: &'a str,
+    enabled: bool,
+}
+
+// Splits off the first `n` whitespace separated fields
+fn split_fields(line: &str, n: usize) -> Option<(Vec<&str>, &str)> {
+    let mut rest = line.trim_start();
+    let mut fields = Vec::with_capacity(n);
+
+    for _ in 0..n {
+        let end = rest.find(char::is_whitespace)?;
+        fields.push(&rest[..end]);
+        rest = rest[end..].trim_start();
+    }
+
+    Some((fields, rest))
+}
+
+fn is_valid_field(field: &str, allow_names: bool) -> bool {
+    !field.is_empty()
+        && field.chars().all(|c| {
+            c.is_ascii_digit() || "*/,-".contains(c) || (allow_names && c.is_ascii_alphabetic())
+        })
+}
+
+fn is_valid_schedule(fields: &[&str]) -> bool {
+    match fields {
+        [special] => SPECIAL_SCHEDULES.contains(special),
+        // Only the month and day of week can be given as names, which stops comments from parsing as entries
+        [minute, hour, day, month, weekday] => {
+            [minute, hour, day].iter().all(|x| is_valid_field(x, false))
+                && [month, weekday].iter().all(|x| is_valid_field(x, true))
+        }
+        _ => false,
+    }
+}
+
+fn split_schedule(line: &str) -> Option<(String, &str)> {
+    let field_count = if line.starts_with('@') { 1 } else { 5 };
+    let (fields, rest) = split_fields(line, field_count)?;
+
+    is_valid_schedule(&fields).then(|| (fields.join(" "), rest))
+}
+
+// Entries that have been commented out are treated as disabled
+fn parse_line(line: &str, has_user: bool) -> Option<ParsedLine<'_>> {
+    let line = line.trim();
+    let (body, enabled) = match line.strip_prefix('#') {
+        Some(body) => (body.trim_start(), false),
+        None => (line, true),
+    };
+
+    let (schedule, rest) = split_schedule(body)?;
+    let (user, command) = if has_user {
+        let (fields, command) = split_fields(rest, 1)?;
+        (fields[0], command)
+    } else {
+        ("", rest)
+    };
+
+    if command.is_empty() {
+        return None;
+    }
+
+    Some(ParsedLine {
+        schedule,
+        user,
+        command,
+        enabled,
+    })
+}
+
+fn read_crontab(user: &str) -> Result<String, String> {
+    let output = Command::new("crontab")
+        .args(["-l", "-u", user])
+        .output()
+        .map_err(|err| err.to_string())?;
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    } else if stderr.contains("no crontab for") {
+        Ok(String::new())
+    } else {
+        Err(stderr.trim().to_string())
+    }
+}
+
+// Installing through crontab makes cron pick up the changes, which writing the spool file wouldn't
+fn write_crontab(user: &str, contents: &str) -> Result<(), String> {
+    let mut child = Command::new("crontab")
+        .args(["-u", user, "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|err| err.to_string())?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(contents.as_bytes())
+            .map_err(|err| err.to_string())?;
+    }
+
+    let output = child.wait_with_output().map_err(|err| err.to_string())?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+
+    Ok(())
+}
+
+fn entries_from(contents: &str, source: CronSource, owner: &str) -> Vec<CronEntry> {
+    let has_user = source != CronSource::User;
+
+    contents
+        .lines()
+        .enumerate()
+        .filter_map(|(idx, line)| {
+            let parsed = parse_line(line, has_user)?;
+
+            Some(CronEntry {
+                source: source.clone(),
+                line: idx as u32,
+                raw: line.to_string(),
+                user: if has_user { parsed.user } else { owner }.to_string(),
+                schedule: parsed.schedule,
+                command: parsed.command.to_string(),
+                enabled: parsed.enabled,
+            })
+        })
+        .collect()
+}
+
+pub fn crontabs(_ctx: BackendContext) -> CronResponse {
+    let mut entries = Vec::new();
+
+    let mut crontab_users: Vec<_> = fs::read_dir(SPOOL_DIR)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+        .collect();
+    crontab_users.sort();
+
+    for user in crontab_users {
+        if let Ok(contents) = read_crontab(&user) {
+            entries.extend(entries_from(&contents, CronSource::User, &user));
+        }
+    }
+
+    let mut system_files = vec![SYSTEM_CRONTAB.to_string()];
+    let mut cron_d: Vec<_> = fs::read_dir(SYSTEM_CRON_DIR)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| Some(entry.ok()?.path().to_str()?.to_string()))
+        .collect();
+    cron_d.sort();
+    system_files.extend(cron_d);
+
+    for path in system_files {
+        if let Ok(contents) = fs::read_to_string(&path) {
+            entries.extend(entries_from(&contents, CronSource::System(path), ""));
+        }
+    }
+
+    CronResponse { entries }
+}
+
+fn check_entry(schedule: &str, command: &str) -> Result<String, String> {
+    let schedule = schedule.split_whitespace().collect::<Vec<_>>();
+    if !is_valid_schedule(&schedule) {
+        return Err("invalid schedule, expected five fields or one like @daily".into());
+    }
+
+    let command = command.trim();
+    if command.is_empty() || command.contains(['\n', '\r']) {
+        return Err("command must not be empty or contain line breaks".into());
+    }
+
+    Ok(format!("{} {command}", schedule.join(" ")))
+}
+
+fn check_line(lines: &[&str], line: u32, original: &str) -> Result<usize, String> {
+    let idx = line as usize;
+
+    if lines.get(idx) != Some(&original) {
+        return Err("the crontab was changed since it was loaded, reload and try again".into());
+    }
+
+    Ok(idx)
+}
+
+pub fn cron_action(_ctx: BackendContext, action: CronAction) -> Result<(), String> {
+    if !users::is_valid_username(&action.user) {
+        return Err(format!("invalid user: {}", action.user));
+    }
+
+    let contents = read_crontab(&action.user)?;
+    let mut lines: Vec<String> = contents.lines().map(String::from).collect();
+    let current: Vec<&str> = contents.lines().collect();
+
+    match action.operation {
+        CronOperation::Add { schedule, command } => {
+            lines.push(check_entry(&schedule, &command)?);
+        }
+        CronOperation::Edit {
+            line,
+            original,
+            schedule,
+            command,
+        } => {
+            let idx = check_line(&current, line, &original)?;
+            let entry = check_entry(&schedule, &command)?;
+
+            // Editing shouldn't re-enable an entry by accident
+            let enabled = parse_line(&original, false).is_none_or(|x| x.enabled);
+            lines[idx] = if enabled { entry } else { format!("# {entry}") };
+        }
+        CronOperation::SetEnabled {
+            line,
+            original,
+            enabled,
+        } => {
+            let idx = check_line(&current, line, &original)?;
+            let body = original.trim().trim_start_matches('#').trim_start();
+
+            if parse_line(body, false).is_none() {
+                return Err("line isn't a crontab entry".into());
+            }
+            lines[idx] = if enabled {
+                body.to_string()
+            } else {
+                format!("# {body}")
+            };
+        }
+        CronOperation::Delete { line, original } => {
+            let idx = check_line(&current, line, &original)?;
+            lines.remove(idx);
+        }
+    }
+
+    // cron ignores the last line if it doesn't end with a newline
+    let mut contents = lines.join("\n");
+    contents.push('\n');
+
+    write_crontab(&action.user, &contents)
+}
+
+pub fn timers(_ctx: BackendContext) -> Result<Vec<TimerInfo>, String> {
+    // Getters run on a blocking thread, so it's fine to block on the D-Bus queries here
+    tokio::runtime::Handle::current()
+        .block_on(systemd::list_timers())
+        .map_err(|err| err.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_user_crontab_line() {
+        let line = parse_line("*/5 * * * * /usr/local/bin/backup --quiet", false).unwrap();
+
+        assert_eq!(line.schedule, "*/5 * * * *");
+        assert_eq!(line.user, "");
+        assert_eq!(line.command, "/usr/local/bin/backup --quiet");
+        assert!(line.enabled);
+    }
+
+    #[test]
+    fn parses_system_crontab_line() {
+        let line = parse_line(
+            "17 * * jan,feb mon-fri root cd / && run-parts /etc/cron.hourly",
+            true,
+        )
+        .unwrap();
+
+        assert_eq!(line.schedule, "17 * * jan,feb mon-fri");
+        assert_eq!(line.user, "root");
+        assert_eq!(line.command, "cd / && run-parts /etc/cron.hourly");
+    }
+
+    #[test]
+    fn parses_disabled_and_special_lines() {
+        let line = parse_line("#  @reboot   /opt/start.sh", false).unwrap();
+
+        assert_eq!(line.schedule, "@reboot");
+        assert_eq!(line.command, "/opt/start.sh");
+        assert!(!line.enabled);
+    }
+
+    #[test]
+    fn skips_comments_and_settings() {
+        assert!(parse_line("# m h  dom mon dow   command", false).is_none());
+        assert!(
+            parse_line(
+                "# Edit this file to introduce tasks to be run by cron.",
+                false
+            )
+            .is_none()
+        );
+        assert!(parse_line("SHELL=/bin/sh", false).is_none());
+        assert!(parse_line("@sometimes /opt/start.sh", false).is_none());
+        assert!(parse_line("* * * * *", false).is_none());
+        assert!(parse_line("* * * * * root", true).is_none());
+        assert!(parse_line("", false).is_none());
+    }
+}
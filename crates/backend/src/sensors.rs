@@ -0,0 +1,116 @@
+use std::{
+    fs,
+    process::Command,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use proto::backend::{FanInfo, ThrottleState};
+
+// Newer Raspberry Pi kernels expose the firmware's throttle flags here
+const THROTTLE_PATH: &str = "/sys/devices/platform/soc/soc:firmware/get_throttled";
+
+// Set once vcgencmd fails to start, so it isn't tried again on every refresh
+static NO_VCGENCMD: AtomicBool = AtomicBool::new(false);
+
+fn parse_throttle_bits(bits: u32) -> ThrottleState {
+    let bit = |n: u32| bits & (1 << n) != 0;
+
+    ThrottleState {
+        under_voltage: bit(0),
+        freq_capped: bit(1),
+        throttled: bit(2),
+        soft_temp_limit: bit(3),
+        under_voltage_occurred: bit(16),
+        freq_capped_occurred: bit(17),
+        throttled_occurred: bit(18),
+        soft_temp_limit_occurred: bit(19),
+    }
+}
+
+// Returns None on systems that aren't Raspberry Pis
+pub fn throttle_state() -> Option<ThrottleState> {
+    let bits = match fs::read_to_string(THROTTLE_PATH) {
+        Ok(bits) => u32::from_str_radix(bits.trim(), 16).ok()?,
+        Err(_) => {
+            if NO_VCGENCMD.load(Ordering::Relaxed) {
+                return None;
+            }
+
+            // Output looks like "throttled=0x50000"
+            let output = Command::new("vcgencmd")
+                .arg("get_throttled")
+                .output()
+                .inspect_err(|_| NO_VCGENCMD.store(true, Ordering::Relaxed))
+                .ok()?;
+            let output = String::from_utf8(output.stdout).ok()?;
+            let bits = output.trim().strip_prefix("throttled=0x")?;
+            u32::from_str_radix(bits, 16).ok()?
+        }
+    };
+
+    Some(parse_throttle_bits(bits))
+}
+
+pub fn fans() -> Vec<FanInfo> {
+    let Ok(hwmons) = fs::read_dir("/sys/class/hwmon") else {
+        return Vec::new();
+    };
+
+    let mut fans: Vec<FanInfo> = hwmons
+        .filter_map(Result::ok)
+        .flat_map(|hwmon| {
+            let path = hwmon.path();
+            let name = fs::read_to_string(path.join("name")).unwrap_or_default();
+            let name = name.trim().to_string();
+
+            fs::read_dir(&path)
+                .into_iter()
+                .flatten()
+                .filter_map(Result::ok)
+                .filter_map(move |entry| {
+                    let file_name = entry.file_name();
+                    let num = file_name
+                        .to_str()?
+                        .strip_prefix("fan")?
+                        .strip_suffix("_input")?;
+
+                    let rpm = fs::read_to_string(entry.path()).ok()?.trim().parse().ok()?;
+                    let label = fs::read_to_string(path.join(format!("fan{num}_label")))
+                        .map(|x| x.trim().to_string())
+                        .unwrap_or_else(|_| format!("{name} fan{num}"));
+
+                    Some(FanInfo { label, rpm })
+                })
+        })
+        .collect();
+    fans.sort_by(|a, b| a.label.cmp(&b.label));
+
+    fans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_current_and_past_flags() {
+        let state = parse_throttle_bits(0x50005);
+
+        assert!(state.under_voltage);
+        assert!(!state.freq_capped);
+        assert!(state.throttled);
+        assert!(!state.soft_temp_limit);
+        assert!(state.under_voltage_occurred);
+        assert!(!state.freq_capped_occurred);
+        assert!(state.throttled_occurred);
+        assert!(!state.soft_temp_limit_occurred);
+    }
+
+    #[test]
+    fn parses_no_flags() {
+        let state = parse_throttle_bits(0);
+
+        assert!(!state.under_voltage && !state.freq_capped && !state.throttled);
+        assert!(!state.under_voltage_occurred && !state.throttled_occurred);
+    }
+}
@@ -0,0 +1,327 @@
+use std::{
+    process::Command,
+    time::{Duration, SystemTime},
+};
+
+use log::warn;
+use proto::backend::{ServiceInfo, ServiceStatus, TimerInfo};
+use rustix::time::{ClockId, clock_gettime};
+use tokio::task::JoinSet;
+use zbus::{Connection, proxy, proxy::CacheProperties, zvariant::OwnedObjectPath};
+
+// Name, description, load state, active state, sub state, following, unit path, job id, job type, job path
+type UnitEntry = (
+    String,
+    String,
+    String,
+    String,
+    String,
+    String,
+    OwnedObjectPath,
+    u32,
+    String,
+    OwnedObjectPath,
+);
+
+#[proxy(
+    interface = "org.freedesktop.systemd1.Manager",
+    default_service = "org.freedesktop.systemd1",
+    default_path = "/org/freedesktop/systemd1"
+)]
+trait Manager {
+    fn list_units(&self) -> zbus::Result<Vec<UnitEntry>>;
+
+    // Unit file path and enablement state
+    fn list_unit_files_by_patterns(
+        &self,
+        states: &[&str],
+        patterns: &[&str],
+    ) -> zbus::Result<Vec<(String, String)>>;
+
+    fn load_unit(&self, name: &str) -> zbus::Result<OwnedObjectPath>;
+}
+
+#[proxy(
+    interface = "org.freedesktop.systemd1.Unit",
+    default_service = "org.freedesktop.systemd1"
+)]
+trait Unit {
+    #[zbus(property)]
+    fn id(&self) -> zbus::Result<String>;
+
+    #[zbus(property)]
+    fn description(&self) -> zbus::Result<String>;
+
+    #[zbus(property)]
+    fn load_state(&self) -> zbus::Result<String>;
+
+    #[zbus(property)]
+    fn active_state(&self) -> zbus::Result<String>;
+
+    #[zbus(property)]
+    fn sub_state(&self) -> zbus::Result<String>;
+
+    #[zbus(property)]
+    fn active_enter_timestamp(&self) -> zbus::Result<u64>;
+}
+
+#[proxy(
+    interface = "org.freedesktop.systemd1.Service",
+    default_service = "org.freedesktop.systemd1"
+)]
+trait Service {
+    #[zbus(property, name = "MainPID")]
+    fn main_pid(&self) -> zbus::Result<u32>;
+
+    #[zbus(property)]
+    fn memory_current(&self) -> zbus::Result<u64>;
+
+    #[zbus(property, name = "CPUUsageNSec")]
+    fn cpu_usage_nsec(&self) -> zbus::Result<u64>;
+}
+
+#[proxy(
+    interface = "org.freedesktop.systemd1.Timer",
+    default_service = "org.freedesktop.systemd1"
+)]
+trait Timer {
+    #[zbus(property)]
+    fn unit(&self) -> zbus::Result<String>;
+
+    #[zbus(property, name = "NextElapseUSecRealtime")]
+    fn next_elapse_realtime(&self) -> zbus::Result<u64>;
+
+    #[zbus(property, name = "NextElapseUSecMonotonic")]
+    fn next_elapse_monotonic(&self) -> zbus::Result<u64>;
+
+    #[zbus(property, name = "LastTriggerUSec")]
+    fn last_trigger(&self) -> zbus::Result<u64>;
+}
+
+// systemd uses UINT64_MAX to signal that accounting is disabled for a unit
+fn accounting_value(val: u64) -> Option<u64> {
+    (val != u64::MAX).then_some(val)
+}
+
+fn format_timestamp(usec: u64) -> String {
+    if usec == 0 {
+        return String::new();
+    }
+
+    let time = SystemTime::UNIX_EPOCH + Duration::from_micros(usec);
+    humantime::format_rfc3339_seconds(time).to_string()
+}
+
+fn status_log(unit: &str) -> String {
+    Command::new("systemctl")
+        .args(["status", "--no-pager", "--lines=20", "--", unit])
+        .output()
+        .map(|output| String::from_utf8_lossy(&output.stdout).into_owned())
+        .unwrap_or_default()
+}
+
+// Fetches every unit at once, leaving out (and logging) any that fail, like a unit that was just removed
+async fn fetch_all<E, T, F>(
+    conn: &Connection,
+    units: impl IntoIterator<Item = (String, E)>,
+    fetch: impl Fn(Connection, E) -> F,
+) -> Vec<T>
+where
+    T: Send + 'static,
+    F: Future<Output = zbus::Result<T>> + Send + 'static,
+{
+    let mut tasks = JoinSet::new();
+    for (name, entry) in units {
+        let fut = fetch(conn.clone(), entry);
+        tasks.spawn(async move { (name, fut.await) });
+    }
+
+    let mut infos = Vec::new();
+    while let Some(result) = tasks.join_next().await {
+        match result {
+            Ok((_, Ok(info))) => infos.push(info),
+            Ok((name, Err(err))) => warn!("Failed to get the status of {name}: {err}"),
+            Err(err) => warn!("Failed to get the status of a unit: {err}"),
+        }
+    }
+
+    infos
+}
+
+async fn service_info(conn: Connection, entry: UnitEntry) -> zbus::Result<ServiceInfo> {
+    let (unit_name, description, load_state, active_state, sub_state, _, path, ..) = entry;
+
+    let unit = UnitProxy::builder(&conn)
+        .path(path.clone())?
+        .cache_properties(CacheProperties::No)
+        .build()
+        .await?;
+    let service = ServiceProxy::builder(&conn)
+        .path(path)?
+        .cache_properties(CacheProperties::No)
+        .build()
+        .await?;
+
+    let status = match active_state.as_str() {
+        "active" => ServiceStatus::Active,
+        "inactive" => ServiceStatus::Inactive,
+        "failed" => ServiceStatus::Failed,
+        _ => ServiceStatus::Unknown,
+    };
+
+    let (start, main_pid, memory, cpu_time) = tokio::try_join!(
+        unit.active_enter_timestamp(),
+        service.main_pid(),
+        service.memory_current(),
+        service.cpu_usage_nsec(),
+    )?;
+
+    Ok(ServiceInfo {
+        name: unit_name.trim_end_matches(".service").into(),
+        description,
+        status,
+        load_state,
+        sub_state,
+        start: format_timestamp(start),
+        main_pid: (main_pid != 0).then_some(main_pid),
+        memory: accounting_value(memory),
+        cpu_time: accounting_value(cpu_time),
+        // Filled in afterwards, since it runs systemctl
+        err_log: String::new(),
+    })
+}
+
+// Units that nothing needs aren't loaded, like most stopped and disabled services, so they only show up
+// as unit files. Loading one gives the same entry `ListUnits` would, or `None` if the file is an alias.
+async fn load_entry(conn: Connection, name: String) -> zbus::Result<Option<UnitEntry>> {
+    let path = ManagerProxy::new(&conn).await?.load_unit(&name).await?;
+    let unit = UnitProxy::builder(&conn)
+        .path(path.clone())?
+        .cache_properties(CacheProperties::No)
+        .build()
+        .await?;
+
+    let (id, description, load_state, active_state, sub_state) = tokio::try_join!(
+        unit.id(),
+        unit.description(),
+        unit.load_state(),
+        unit.active_state(),
+        unit.sub_state(),
+    )?;
+    if id != name {
+        return Ok(None);
+    }
+
+    Ok(Some((
+        name,
+        description,
+        load_state,
+        active_state,
+        sub_state,
+        String::new(),
+        path,
+        0,
+        String::new(),
+        OwnedObjectPath::default(),
+    )))
+}
+
+pub async fn list_services() -> zbus::Result<Vec<ServiceInfo>> {
+    let conn = Connection::system().await?;
+    let manager = ManagerProxy::new(&conn).await?;
+
+    let mut units: Vec<_> = manager
+        .list_units()
+        .await?
+        .into_iter()
+        .filter(|entry| entry.0.ends_with(".service") && entry.2 != "not-found")
+        .collect();
+
+    // Templates can't run without an instance name
+    let unloaded = manager
+        .list_unit_files_by_patterns(&[], &["*.service"])
+        .await?
+        .into_iter()
+        .filter_map(|(path, _)| Some(path.rsplit_once('/')?.1.to_owned()))
+        .filter(|name| !name.ends_with("@.service"))
+        .filter(|name| !units.iter().any(|entry| &entry.0 == name))
+        .map(|name| (name.clone(), name));
+    let loaded = fetch_all(&conn, unloaded, load_entry).await;
+    units.extend(loaded.into_iter().flatten());
+
+    let units = units.into_iter().map(|entry| (entry.0.clone(), entry));
+    let mut services = fetch_all(&conn, units, service_info).await;
+
+    for service in &mut services {
+        if matches!(service.status, ServiceStatus::Failed) {
+            service.err_log = status_log(&format!("{}.service", service.name));
+        }
+    }
+
+    services.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok(services)
+}
+
+// Timers like OnBootSec elapse on the monotonic clock, so convert them to wall clock time
+fn monotonic_to_realtime(usec: u64) -> u64 {
+    if usec == 0 || usec == u64::MAX {
+        return 0;
+    }
+
+    let now = clock_gettime(ClockId::Monotonic);
+    let now_mono = now.tv_sec as u64 * 1_000_000 + now.tv_nsec as u64 / 1000;
+    let now_real = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_micros() as u64;
+
+    (now_real + usec).saturating_sub(now_mono)
+}
+
+async fn timer_info(conn: Connection, entry: UnitEntry) -> zbus::Result<TimerInfo> {
+    let (name, description, _, active_state, _, _, path, ..) = entry;
+
+    let timer = TimerProxy::builder(&conn)
+        .path(path)?
+        .cache_properties(CacheProperties::No)
+        .build()
+        .await?;
+
+    // A timer can have both kinds of triggers, in which case the earliest one wins
+    let next = [
+        timer.next_elapse_realtime().await?,
+        monotonic_to_realtime(timer.next_elapse_monotonic().await?),
+    ]
+    .into_iter()
+    .filter(|&x| x != 0 && x != u64::MAX)
+    .min()
+    .unwrap_or(0);
+
+    Ok(TimerInfo {
+        name: name.trim_end_matches(".timer").into(),
+        description,
+        unit: timer.unit().await?,
+        active: active_state == "active",
+        next: format_timestamp(next),
+        last: format_timestamp(timer.last_trigger().await?),
+    })
+}
+
+pub async fn list_timers() -> zbus::Result<Vec<TimerInfo>> {
+    let conn = Connection::system().await?;
+    let manager = ManagerProxy::new(&conn).await?;
+
+    let units = manager
+        .list_units()
+        .await?
+        .into_iter()
+        .filter(|entry| entry.0.ends_with(".timer") && entry.2 != "not-found")
+        .map(|entry| (entry.0.clone(), entry));
+
+    let mut timers = fetch_all(&conn, units, timer_info).await;
+
+    timers.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok(timers)
+}
@@ -1,3 +1,5 @@
+use std::path::PathBuf;
+
 use anyhow::{Context, Result};
 use proto::backend::{ActionBackendMessage, BackendMessage};
 use pty_process::{Command, Pty, Size};
@@ -6,46 +8,69 @@ use tokio::{
     sync::mpsc,
 };
 
-fn create_pty() -> Result<Pty> {
+#[derive(Debug)]
+pub enum TerminalInput {
+    Data(Vec<u8>),
+    /// Replaces the current session with another program, until it exits
+    Spawn(PathBuf, Vec<String>),
+}
+
+fn create_pty(cmd: Command) -> Result<Pty> {
     let (pty, pts) = pty_process::open().context("failed to open pty")?;
     pty.resize(Size::new(24, 80))
         .context("failed to resize pty")?;
 
-    let cmd = Command::new("login");
     cmd.spawn(pts).context("failed to spawn terminal")?;
 
     Ok(pty)
 }
 
+fn login_pty() -> Result<Pty> {
+    create_pty(Command::new("login"))
+}
+
 pub struct Terminal {
     socket_tx: mpsc::UnboundedSender<BackendMessage>,
-    rx: mpsc::UnboundedReceiver<Vec<u8>>,
+    rx: mpsc::UnboundedReceiver<TerminalInput>,
     pty: Pty,
 }
 
 impl Terminal {
     pub fn new(
         socket_tx: mpsc::UnboundedSender<BackendMessage>,
-        rx: mpsc::UnboundedReceiver<Vec<u8>>,
+        rx: mpsc::UnboundedReceiver<TerminalInput>,
     ) -> Result<Self> {
-        let pty = create_pty()?;
+        let pty = login_pty()?;
 
         Ok(Self { socket_tx, rx, pty })
     }
 
+    fn send_output(&self, data: Vec<u8>) {
+        let msg = ActionBackendMessage::Terminal(data);
+        let _ = self.socket_tx.send(BackendMessage::Action(msg));
+    }
+
     pub async fn run(mut self) {
         let mut buf = [0; 512];
 
         loop {
             loop {
                 tokio::select! {
-                    data = self.rx.recv() => {
-                        let Some(data): Option<Vec<u8>> = data else {
-                            break;
-                        };
-
-                        if self.pty.write_all(&data).await.is_err() {
-                            break;
+                    input = self.rx.recv() => {
+                        match input {
+                            Some(TerminalInput::Data(data)) => {
+                                if self.pty.write_all(&data).await.is_err() {
+                                    break;
+                                }
+                            }
+                            Some(TerminalInput::Spawn(program, args)) => {
+                                let cmd = Command::new(program).args(args);
+                                match create_pty(cmd) {
+                                    Ok(pty) => self.pty = pty,
+                                    Err(err) => self.send_output(format!("\r\n{err:#}\r\n").into_bytes()),
+                                }
+                            }
+                            None => break,
                         }
                     }
                     n = self.pty.read(&mut buf) => {
@@ -57,15 +82,12 @@ impl Terminal {
                             break;
                         }
 
-                        let msg = ActionBackendMessage::Terminal(buf[..n].to_vec());
-                        let msg = BackendMessage::Action(msg);
-
-                        let _ = self.socket_tx.send(msg);
+                        self.send_output(buf[..n].to_vec());
                     }
                 }
             }
 
-            if let Ok(pty) = create_pty() {
+            if let Ok(pty) = login_pty() {
                 self.pty = pty;
             } else {
                 break;
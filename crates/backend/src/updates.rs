@@ -0,0 +1,130 @@
+use std::{fs, process::Command, time::SystemTime};
+
+use proto::backend::{UpdatesResponse, UpgradablePackage};
+
+use crate::client::BackendContext;
+
+// Written by DietPi's daily update check
+const DIETPI_UPDATE_FILE: &str = "/run/dietpi/.update_available";
+// These change whenever packages are installed, the package lists are updated or DietPi checks for updates
+const STATE_PATHS: [&str; 3] = [
+    "/var/lib/dpkg/status",
+    "/var/lib/apt/lists",
+    DIETPI_UPDATE_FILE,
+];
+
+fn update_state() -> Vec<Option<SystemTime>> {
+    STATE_PATHS
+        .iter()
+        .map(|path| fs::metadata(path).and_then(|x| x.modified()).ok())
+        .collect()
+}
+
+// Parses a line like "bash/stable-security 5.2.15-2+b7 arm64 [upgradable from: 5.2.15-2+b2]"
+fn parse_upgradable(line: &str) -> Option<UpgradablePackage> {
+    let (name, rest) = line.split_once('/')?;
+    let mut fields = rest.split_whitespace();
+
+    let origin = fields.next()?;
+    let candidate = fields.next()?;
+    let current = rest
+        .split_once("[upgradable from: ")?
+        .1
+        .trim_end_matches(']');
+
+    Some(UpgradablePackage {
+        name: name.into(),
+        current: current.into(),
+        candidate: candidate.into(),
+        origin: origin.into(),
+        security: origin.split(',').any(|x| x.contains("security")),
+    })
+}
+
+// Uses the package lists from the last `apt-get update`, so this doesn't need network access.
+// Listing upgradable packages is slow, so the result is reused until any of the state changes.
+pub fn updates(mut ctx: BackendContext) -> UpdatesResponse {
+    let state = update_state();
+
+    if let Some((cached_state, resp)) = &ctx.system().updates_cache
+        && *cached_state == state
+    {
+        return resp.clone();
+    }
+
+    let resp = list_updates();
+    if resp.error.is_none() {
+        ctx.system().updates_cache = Some((state, resp.clone()));
+    }
+
+    resp
+}
+
+fn list_updates() -> UpdatesResponse {
+    // "-1" means the installed version is too old to be updated in place
+    let dietpi_update = fs::read_to_string(DIETPI_UPDATE_FILE)
+        .ok()
+        .map(|x| x.trim().to_string())
+        .filter(|x| !x.is_empty() && x != "-1");
+
+    let output = match Command::new("apt").args(["list", "--upgradable"]).output() {
+        Ok(output) => output,
+        Err(err) => {
+            return UpdatesResponse {
+                packages: Vec::new(),
+                dietpi_update,
+                error: Some(err.to_string()),
+            };
+        }
+    };
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut packages: Vec<_> = stdout.lines().filter_map(parse_upgradable).collect();
+    packages.sort_by(|a, b| b.security.cmp(&a.security).then(a.name.cmp(&b.name)));
+
+    UpdatesResponse {
+        packages,
+        dietpi_update,
+        error: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_upgradable_line() {
+        let pkg = parse_upgradable(
+            "bash/stable-security 5.2.15-2+b7 arm64 [upgradable from: 5.2.15-2+b2]",
+        )
+        .unwrap();
+
+        assert_eq!(pkg.name, "bash");
+        assert_eq!(pkg.origin, "stable-security");
+        assert_eq!(pkg.candidate, "5.2.15-2+b7");
+        assert_eq!(pkg.current, "5.2.15-2+b2");
+        assert!(pkg.security);
+    }
+
+    #[test]
+    fn detects_security_in_any_origin() {
+        let pkg = parse_upgradable(
+            "libc6/stable,stable-security 2.36-9+deb12u7 arm64 [upgradable from: 2.36-9+deb12u4]",
+        )
+        .unwrap();
+        assert!(pkg.security);
+
+        let pkg =
+            parse_upgradable("curl/stable 7.88.1-10+deb12u8 arm64 [upgradable from: 7.88.1-10]")
+                .unwrap();
+        assert!(!pkg.security);
+    }
+
+    #[test]
+    fn rejects_other_lines() {
+        assert!(parse_upgradable("Listing...").is_none());
+        assert!(parse_upgradable("bash/stable 5.2.15-2+b7 arm64 [installed]").is_none());
+        assert!(parse_upgradable("").is_none());
+    }
+}
@@ -0,0 +1,580 @@
+use std::{
+    collections::HashMap,
+    fs::{self, File, OpenOptions},
+    io::{self, Read, Write},
+    os::{
+        fd::OwnedFd,
+        unix::fs::{MetadataExt, OpenOptionsExt, fchown},
+    },
+    process::{Command, Stdio},
+    time::SystemTime,
+};
+
+use log::{info, warn};
+use proto::{
+    backend::{AuthorizedKey, GroupInfo, UserInfo, UsersResponse},
+    frontend::UserAction,
+};
+use rustix::{
+    fs::{AtFlags, FileType, Mode, OFlags},
+    io::Errno,
+};
+
+use crate::{client::BackendContext, getters};
+
+const AUDIT_LOG_LINES: usize = 50;
+
+// Accounts below this are created by packages, and are left alone
+const MIN_UID: u32 = 1000;
+const NOBODY_UID: u32 = 65534;
+
+const KEY_TYPES: &[&str] = &[
+    "ssh-ed25519",
+    "ssh-rsa",
+    "ecdsa-sha2-nistp256",
+    "ecdsa-sha2-nistp384",
+    "ecdsa-sha2-nistp521",
+    "sk-ssh-ed25519@openssh.com",
+    "sk-ecdsa-sha2-nistp256@openssh.com",
+];
+
+struct PasswdEntry {
+    name: String,
+    uid: u32,
+    gid: u32,
+    home: String,
+    shell: String,
+}
+
+fn read_passwd() -> Vec<PasswdEntry> {
+    let file = fs::read_to_string("/etc/passwd").unwrap_or_default();
+
+    file.lines()
+        .filter_map(|line| {
+            let mut fields = line.split(':');
+
+            let name = fields.next()?.to_string();
+            let uid = fields.nth(1)?.parse().ok()?;
+            let gid = fields.next()?.parse().ok()?;
+            let home = fields.nth(1)?.to_string();
+            let shell = fields.next().unwrap_or_default().to_string();
+
+            Some(PasswdEntry {
+                name,
+                uid,
+                gid,
+                home,
+                shell,
+            })
+        })
+        .collect()
+}
+
+fn read_groups() -> Vec<GroupInfo> {
+    let file = fs::read_to_string("/etc/group").unwrap_or_default();
+
+    file.lines()
+        .filter_map(|line| {
+            let mut fields = line.split(':');
+
+            let name = fields.next()?.to_string();
+            let gid = fields.nth(1)?.parse().ok()?;
+            let members = fields
+                .next()
+                .unwrap_or_default()
+                .split(',')
+                .filter(|x| !x.is_empty())
+                .map(String::from)
+                .collect();
+
+            Some(GroupInfo { name, gid, members })
+        })
+        .collect()
+}
+
+fn is_regular_user(uid: u32) -> bool {
+    uid == 0 || (MIN_UID..NOBODY_UID).contains(&uid)
+}
+
+fn find_user(name: &str) -> Result<PasswdEntry, String> {
+    read_passwd()
+        .into_iter()
+        .find(|user| user.name == name && is_regular_user(user.uid))
+        .ok_or_else(|| format!("no such user: {name}"))
+}
+
+pub fn users(_ctx: BackendContext) -> UsersResponse {
+    let groups = read_groups();
+
+    // Only readable as root, so the lock state is unknown otherwise
+    let shadow: Option<HashMap<String, bool>> =
+        fs::read_to_string("/etc/shadow").ok().map(|file| {
+            file.lines()
+                .filter_map(|line| {
+                    let mut fields = line.split(':');
+                    let name = fields.next()?.to_string();
+                    let locked = fields.next()?.starts_with('!');
+                    Some((name, locked))
+                })
+                .collect()
+        });
+
+    let users = read_passwd()
+        .into_iter()
+        .filter(|user| is_regular_user(user.uid))
+        .map(|user| {
+            let user_groups = groups
+                .iter()
+                .filter(|group| group.gid == user.gid || group.members.contains(&user.name))
+                .map(|group| group.name.clone())
+                .collect();
+            let locked = shadow
+                .as_ref()
+                .map(|shadow| shadow.get(&user.name).copied().unwrap_or_default());
+
+            UserInfo {
+                name: user.name,
+                uid: user.uid,
+                gid: user.gid,
+                home: user.home,
+                shell: user.shell,
+                groups: user_groups,
+                locked,
+            }
+        })
+        .collect();
+
+    // Package-created groups with nobody in them aren't worth listing
+    let groups = groups
+        .into_iter()
+        .filter(|group| group.gid >= MIN_UID || !group.members.is_empty())
+        .collect();
+
+    UsersResponse { users, groups }
+}
+
+fn parse_key_line(line: &str) -> Option<AuthorizedKey> {
+    let line = line.trim();
+    if line.starts_with('#') {
+        return None;
+    }
+
+    // Options come before the key type, so find where the key itself starts
+    let start = line
+        .split_whitespace()
+        .position(|x| KEY_TYPES.contains(&x))?;
+    let mut tokens = line.split_whitespace();
+
+    let options = tokens.by_ref().take(start).collect::<Vec<_>>().join(" ");
+    let key_type = tokens.next()?.to_string();
+    let data = tokens.next()?.to_string();
+    let comment = tokens.collect::<Vec<_>>().join(" ");
+
+    Some(AuthorizedKey {
+        options,
+        key_type,
+        data,
+        comment,
+    })
+}
+
+fn is_valid_key_data(data: &str) -> bool {
+    !data.is_empty()
+        && data
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "+/=".contains(c))
+}
+
+const KEYS_FILE: &str = "authorized_keys";
+const KEYS_TMP_FILE: &str = "authorized_keys.dashboard-tmp";
+
+// The user controls ~/.ssh and could swap anything in it for a symlink at any moment, which would
+// then be written as root. So everything is opened relative to the directory, without following links.
+fn open_ssh_dir(user: &PasswdEntry, create: bool) -> Result<Option<OwnedFd>, String> {
+    let home = rustix::fs::open(
+        &user.home,
+        OFlags::RDONLY | OFlags::DIRECTORY | OFlags::CLOEXEC,
+        Mode::empty(),
+    )
+    .map_err(|err| format!("failed to open {}: {err}", user.home))?;
+
+    let flags = OFlags::RDONLY | OFlags::DIRECTORY | OFlags::NOFOLLOW | OFlags::CLOEXEC;
+    match rustix::fs::openat(&home, ".ssh", flags, Mode::empty()) {
+        Ok(dir) => Ok(Some(dir)),
+        Err(Errno::NOENT) if create => {
+            // sshd ignores keys if these are accessible by other users
+            rustix::fs::mkdirat(&home, ".ssh", Mode::RWXU).map_err(|err| err.to_string())?;
+            let dir = rustix::fs::openat(&home, ".ssh", flags, Mode::empty())
+                .map_err(|err| err.to_string())?;
+            fchown(&dir, Some(user.uid), Some(user.gid)).map_err(|err| err.to_string())?;
+
+            Ok(Some(dir))
+        }
+        Err(Errno::NOENT) => Ok(None),
+        Err(Errno::LOOP | Errno::NOTDIR) => Err("~/.ssh isn't a directory".into()),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+fn open_keys(dir: &OwnedFd, flags: OFlags) -> io::Result<File> {
+    let fd = rustix::fs::openat(
+        dir,
+        KEYS_FILE,
+        flags | OFlags::NOFOLLOW | OFlags::CLOEXEC,
+        Mode::RUSR | Mode::WUSR,
+    )?;
+
+    // A hard link would let the user point it at another file just like a symlink
+    let stat = rustix::fs::fstat(&fd)?;
+    if FileType::from_raw_mode(stat.st_mode) != FileType::RegularFile || stat.st_nlink != 1 {
+        return Err(io::Error::other(format!(
+            "{KEYS_FILE} isn't a regular file"
+        )));
+    }
+
+    Ok(File::from(fd))
+}
+
+fn read_keys(dir: &OwnedFd) -> Result<String, String> {
+    let mut file = match open_keys(dir, OFlags::RDONLY) {
+        Ok(file) => file,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(String::new()),
+        Err(err) => return Err(err.to_string()),
+    };
+
+    let mut contents = String::new();
+    file.read_to_string(&mut contents)
+        .map_err(|err| err.to_string())?;
+
+    Ok(contents)
+}
+
+pub fn authorized_keys(_ctx: BackendContext, name: String) -> Result<Vec<AuthorizedKey>, String> {
+    let user = find_user(&name)?;
+    let Some(dir) = open_ssh_dir(&user, false)? else {
+        return Ok(Vec::new());
+    };
+
+    Ok(read_keys(&dir)?
+        .lines()
+        .filter_map(parse_key_line)
+        .collect())
+}
+
+pub fn is_valid_username(name: &str) -> bool {
+    // The portable subset accepted by useradd, which also can't be mistaken for an option
+    (1..=32).contains(&name.len())
+        && name.starts_with(|c: char| c.is_ascii_lowercase() || c == '_')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || "_-".contains(c))
+}
+
+fn check_password(password: &str) -> Result<(), String> {
+    // chpasswd reads one user per line
+    if password.is_empty() || password.contains(['\n', '\r']) {
+        return Err("password must not be empty or contain line breaks".into());
+    }
+
+    Ok(())
+}
+
+fn set_password(name: &str, password: &str) -> Result<(), String> {
+    check_password(password)?;
+
+    // Passed through stdin, so the password never shows up in the process list
+    let mut child = Command::new("chpasswd")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|err| err.to_string())?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        writeln!(stdin, "{name}:{password}").map_err(|err| err.to_string())?;
+    }
+
+    let output = child.wait_with_output().map_err(|err| err.to_string())?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+
+    Ok(())
+}
+
+fn add_user(name: &str, password: &str, groups: &[String]) -> Result<(), String> {
+    if !is_valid_username(name) {
+        return Err(format!("invalid username: {name}"));
+    }
+    if let Some(group) = groups.iter().find(|x| !is_valid_username(x)) {
+        return Err(format!("invalid group name: {group}"));
+    }
+    check_password(password)?;
+
+    let mut cmd = Command::new("useradd");
+    cmd.args(["--create-home", "--shell", "/bin/bash"]);
+    if !groups.is_empty() {
+        cmd.args(["--groups", &groups.join(",")]);
+    }
+    getters::run_checked(cmd.args(["--", name]))?;
+
+    set_password(name, password)
+}
+
+fn modifiable_user(name: &str) -> Result<PasswdEntry, String> {
+    let user = find_user(name)?;
+    if user.uid == 0 {
+        return Err("refusing to modify the root account".into());
+    }
+
+    Ok(user)
+}
+
+fn add_key(name: &str, key: &str) -> Result<(), String> {
+    let user = find_user(name)?;
+
+    let parsed = parse_key_line(key).ok_or("unrecognised key type")?;
+    if !parsed.options.is_empty() || key.contains(['\n', '\r']) {
+        return Err("expected a single key without options".into());
+    }
+    if !is_valid_key_data(&parsed.data) {
+        return Err("key data isn't valid base64".into());
+    }
+
+    let dir = open_ssh_dir(&user, true)?.ok_or("failed to create ~/.ssh")?;
+
+    let existing = read_keys(&dir)?;
+    if existing
+        .lines()
+        .filter_map(parse_key_line)
+        .any(|x| x.data == parsed.data)
+    {
+        return Err("key is already authorized".into());
+    }
+
+    let flags = OFlags::WRONLY | OFlags::APPEND;
+    let mut file = match open_keys(&dir, flags | OFlags::CREATE | OFlags::EXCL) {
+        Ok(file) => {
+            fchown(&file, Some(user.uid), Some(user.gid)).map_err(|err| err.to_string())?;
+            file
+        }
+        Err(err) if err.kind() == io::ErrorKind::AlreadyExists => {
+            open_keys(&dir, flags).map_err(|err| err.to_string())?
+        }
+        Err(err) => return Err(err.to_string()),
+    };
+
+    let separator = if existing.is_empty() || existing.ends_with('\n') {
+        ""
+    } else {
+        "\n"
+    };
+    writeln!(file, "{separator}{}", key.trim()).map_err(|err| err.to_string())
+}
+
+fn remove_key(name: &str, data: &str) -> Result<(), String> {
+    let user = find_user(name)?;
+    let dir = open_ssh_dir(&user, false)?.ok_or("key not found")?;
+
+    let mut file = open_keys(&dir, OFlags::RDONLY).map_err(|err| err.to_string())?;
+    let meta = file.metadata().map_err(|err| err.to_string())?;
+    let mut existing = String::new();
+    file.read_to_string(&mut existing)
+        .map_err(|err| err.to_string())?;
+
+    let remaining: Vec<_> = existing
+        .lines()
+        .filter(|line| parse_key_line(line).is_none_or(|key| key.data != data))
+        .collect();
+    if remaining.len() == existing.lines().count() {
+        return Err("key not found".into());
+    }
+
+    let mut contents = remaining.join("\n");
+    if !contents.is_empty() {
+        contents.push('\n');
+    }
+
+    // Write next to the original and move it into place, so sshd never sees a partial file.
+    // Whatever is left at the temporary path is only unlinked, never written through.
+    let _ = rustix::fs::unlinkat(&dir, KEYS_TMP_FILE, AtFlags::empty());
+    let tmp = rustix::fs::openat(
+        &dir,
+        KEYS_TMP_FILE,
+        OFlags::WRONLY | OFlags::CREATE | OFlags::EXCL | OFlags::NOFOLLOW | OFlags::CLOEXEC,
+        Mode::RUSR | Mode::WUSR,
+    )
+    .map_err(|err| err.to_string())?;
+    let mut tmp = File::from(tmp);
+
+    tmp.write_all(contents.as_bytes())
+        .and_then(|_| tmp.set_permissions(meta.permissions()))
+        .and_then(|_| fchown(&tmp, Some(meta.uid()), Some(meta.gid())))
+        .and_then(|_| Ok(rustix::fs::renameat(&dir, KEYS_TMP_FILE, &dir, KEYS_FILE)?))
+        .map_err(|err| {
+            let _ = rustix::fs::unlinkat(&dir, KEYS_TMP_FILE, AtFlags::empty());
+            err.to_string()
+        })
+}
+
+fn describe(action: &UserAction) -> String {
+    match action {
+        UserAction::Add { name, groups, .. } if groups.is_empty() => format!("add user {name}"),
+        UserAction::Add { name, groups, .. } => {
+            format!("add user {name} in groups {}", groups.join(","))
+        }
+        UserAction::Remove {
+            name,
+            remove_home: true,
+        } => format!("remove user {name} and their home directory"),
+        UserAction::Remove { name, .. } => format!("remove user {name}"),
+        UserAction::SetPassword { name, .. } => format!("change password of {name}"),
+        UserAction::Lock(name) => format!("lock {name}"),
+        UserAction::Unlock(name) => format!("unlock {name}"),
+        UserAction::AddKey { name, key } => match parse_key_line(key) {
+            Some(key) => format!("add {} key \"{}\" for {name}", key.key_type, key.comment),
+            None => format!("add key for {name}"),
+        },
+        UserAction::RemoveKey { name, key } => {
+            let end = key.len().saturating_sub(12);
+            format!("remove key ...{} for {name}", key.get(end..).unwrap_or(key))
+        }
+    }
+}
+
+fn audit(ctx: &BackendContext, description: &str, result: &Result<(), String>) {
+    let outcome = match result {
+        Ok(()) => "ok".to_string(),
+        Err(err) => format!("failed: {err}"),
+    };
+    info!("User management: {description}: {outcome}");
+
+    if ctx.config.audit_log.is_empty() {
+        return;
+    }
+
+    let timestamp = humantime::format_rfc3339_seconds(SystemTime::now());
+    let written = OpenOptions::new()
+        .append(true)
+        .create(true)
+        .mode(0o600)
+        .open(&ctx.config.audit_log)
+        .and_then(|mut file| writeln!(file, "{timestamp} {description}: {outcome}"));
+
+    if let Err(err) = written {
+        warn!("Failed to write to audit log: {err}");
+    }
+}
+
+pub fn user_action(ctx: BackendContext, action: UserAction) -> Result<(), String> {
+    let description = describe(&action);
+
+    let result = match &action {
+        UserAction::Add {
+            name,
+            password,
+            groups,
+        } => add_user(name, password, groups),
+        UserAction::Remove { name, remove_home } => modifiable_user(name).and_then(|user| {
+            let mut cmd = Command::new("userdel");
+            if *remove_home {
+                cmd.arg("--remove");
+            }
+            getters::run_checked(cmd.args(["--", &user.name]))
+        }),
+        UserAction::SetPassword { name, password } => {
+            find_user(name).and_then(|user| set_password(&user.name, password))
+        }
+        // Expiring the account also stops logins with SSH keys, which a password lock doesn't
+        UserAction::Lock(name) => modifiable_user(name).and_then(|user| {
+            getters::run_checked(Command::new("usermod").args([
+                "--lock",
+                "--expiredate",
+                "1",
+                "--",
+                &user.name,
+            ]))
+        }),
+        UserAction::Unlock(name) => modifiable_user(name).and_then(|user| {
+            getters::run_checked(Command::new("usermod").args([
+                "--unlock",
+                "--expiredate",
+                "",
+                "--",
+                &user.name,
+            ]))
+        }),
+        UserAction::AddKey { name, key } => add_key(name, key),
+        UserAction::RemoveKey { name, key } => remove_key(name, key),
+    };
+
+    audit(&ctx, &description, &result);
+
+    result
+}
+
+pub fn audit_log(ctx: BackendContext) -> Vec<String> {
+    let file = fs::read_to_string(&ctx.config.audit_log).unwrap_or_default();
+
+    let mut lines: Vec<_> = file
+        .lines()
+        .rev()
+        .take(AUDIT_LOG_LINES)
+        .map(String::from)
+        .collect();
+    lines.reverse();
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_key_with_comment() {
+        let key =
+            parse_key_line("ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIG user@laptop work").unwrap();
+
+        assert_eq!(key.options, "");
+        assert_eq!(key.key_type, "ssh-ed25519");
+        assert_eq!(key.data, "AAAAC3NzaC1lZDI1NTE5AAAAIG");
+        assert_eq!(key.comment, "user@laptop work");
+    }
+
+    #[test]
+    fn parses_key_with_options() {
+        let key = parse_key_line("  no-pty,from=\"10.0.0.1\" ssh-rsa AAAAB3NzaC1yc2E= \n").unwrap();
+
+        assert_eq!(key.options, "no-pty,from=\"10.0.0.1\"");
+        assert_eq!(key.key_type, "ssh-rsa");
+        assert_eq!(key.data, "AAAAB3NzaC1yc2E=");
+        assert_eq!(key.comment, "");
+    }
+
+    #[test]
+    fn skips_comments_and_invalid_lines() {
+        assert!(parse_key_line("# ssh-ed25519 AAAAC3NzaC1lZDI1NTE5 old key").is_none());
+        assert!(parse_key_line("").is_none());
+        assert!(parse_key_line("ssh-dss AAAAB3NzaC1kc3M=").is_none());
+        assert!(parse_key_line("ssh-ed25519").is_none());
+    }
+
+    #[test]
+    fn accepts_valid_usernames() {
+        assert!(is_valid_username("dietpi"));
+        assert!(is_valid_username("_apt"));
+        assert!(is_valid_username("user-2"));
+        assert!(is_valid_username(&"a".repeat(32)));
+    }
+
+    #[test]
+    fn rejects_invalid_usernames() {
+        assert!(!is_valid_username(""));
+        assert!(!is_valid_username("-r"));
+        assert!(!is_valid_username("2fast"));
+        assert!(!is_valid_username("Root"));
+        assert!(!is_valid_username("a b"));
+        assert!(!is_valid_username("../etc"));
+        assert!(!is_valid_username(&"a".repeat(33)));
+    }
+}
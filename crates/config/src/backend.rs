@@ -7,7 +7,7 @@ use toml_migrate::build_migration_chain;
 
 use crate::generate_config_file;
 
-pub type BackendConfig = BackendConfigV1;
+pub type BackendConfig = BackendConfigV2;
 
 pub fn get_config() -> Result<BackendConfig> {
     crate::read_config("config-backend.toml", generate_config_file)
@@ -19,11 +19,82 @@ fn generate_config_file(config: &BackendConfig) -> String {
         log_level = config.log_level,
         frontend_addr = config.frontend_addr,
         nickname = config.nickname,
-        disks = config.disks
+        tags = config.tags,
+        disks = config.disks,
+        auto_disks = config.auto_disks,
+        allowed_commands = config.allowed_commands,
+        file_roots = config.file_roots,
+        writable_file_roots = config.writable_file_roots,
+        hidden_interfaces = config.hidden_interfaces,
+        audit_log = config.audit_log,
+        container_socket = config.container_socket
     )
 }
 
-build_migration_chain!(BackendConfigV0 = 0, BackendConfigV1 = 1);
+build_migration_chain!(
+    BackendConfigV0 = 0,
+    BackendConfigV1 = 1,
+    BackendConfigV2 = 2
+);
+
+#[derive(Deserialize)]
+pub struct BackendConfigV2 {
+    pub log_level: LevelFilter,
+    pub frontend_addr: SocketAddr,
+    pub nickname: String,
+    pub tags: Vec<String>,
+    pub disks: Vec<String>,
+    pub auto_disks: bool,
+    pub allowed_commands: Vec<String>,
+    pub file_roots: Vec<String>,
+    pub writable_file_roots: Vec<String>,
+    pub hidden_interfaces: Vec<String>,
+    pub audit_log: String,
+    pub container_socket: String,
+}
+
+impl Default for BackendConfigV2 {
+    fn default() -> Self {
+        Self {
+            log_level: LevelFilter::Info,
+            frontend_addr: ([127, 0, 0, 1], 5353).into(),
+            nickname: String::new(),
+            tags: Vec::new(),
+            disks: vec!["/".into()],
+            auto_disks: false,
+            allowed_commands: vec![
+                "/boot/dietpi/dietpi-software install <id>...".into(),
+                "/boot/dietpi/dietpi-software uninstall <id>...".into(),
+            ],
+            file_roots: vec!["/var/log".into(), "/mnt".into()],
+            writable_file_roots: Vec::new(),
+            hidden_interfaces: vec!["lo".into(), "docker*".into(), "veth*".into(), "br-*".into()],
+            audit_log: "/var/log/dietpi-dashboard-audit.log".into(),
+            container_socket: String::new(),
+        }
+    }
+}
+
+impl From<BackendConfigV1> for BackendConfigV2 {
+    fn from(val: BackendConfigV1) -> Self {
+        let default = Self::default();
+
+        Self {
+            log_level: val.log_level,
+            frontend_addr: val.frontend_addr,
+            nickname: val.nickname,
+            tags: default.tags,
+            disks: val.disks,
+            auto_disks: default.auto_disks,
+            allowed_commands: default.allowed_commands,
+            file_roots: default.file_roots,
+            writable_file_roots: default.writable_file_roots,
+            hidden_interfaces: default.hidden_interfaces,
+            audit_log: default.audit_log,
+            container_socket: default.container_socket,
+        }
+    }
+}
 
 #[derive(Deserialize)]
 pub struct BackendConfigV1 {
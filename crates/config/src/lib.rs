@@ -9,7 +9,7 @@ pub mod backend;
 pub mod frontend;
 
 pub const APP_VERSION: &str = env!("CARGO_PKG_VERSION");
-pub const PROTOCOL_VERSION: u32 = 1;
+pub const PROTOCOL_VERSION: u32 = 2;
 
 macro_rules! generate_config_file {
     ($template:literal, $($key:ident = $val:expr),*) => {{
@@ -1,4 +1,7 @@
 use bitcode::{Decode, Encode};
+use serde::Deserialize;
+
+use crate::frontend::{AddressMethod, FirewallRule, Job};
 
 #[derive(Debug, Clone, Encode, Decode)]
 pub enum BackendMessage {
@@ -10,6 +13,8 @@ pub enum BackendMessage {
 pub enum ActionBackendMessage {
     Handshake(Handshake),
     Terminal(Vec<u8>),
+    LogFollow(u16, LogEntry),
+    Job(JobEvent),
 }
 
 #[derive(Debug, Clone, Encode, Decode)]
@@ -20,15 +25,55 @@ pub enum ResponseBackendMessage {
     Disk(DiskResponse),
     NetIO(NetworkResponse),
     Processes(ProcessResponse),
+    ProcessDetail(Option<Box<ProcessDetails>>),
+    Signal(SignalResult),
+    Renice(CommandResponse),
+    IoNice(CommandResponse),
+    Affinity(CommandResponse),
     Host(HostResponse),
     Software(SoftwareResponse),
     Command(CommandResponse),
     Services(ServiceResponse),
+    ServiceAction(CommandResponse),
+    Logs(LogResponse),
+    ListDir(Result<DirResponse, String>),
+    ReadFile(Result<FileChunk, String>),
+    WriteFile(Result<(), String>),
+    CreateDir(Result<(), String>),
+    Rename(Result<(), String>),
+    Delete(Result<(), String>),
+    Updates(UpdatesResponse),
+    Power(CommandResponse),
+    SetHostname(CommandResponse),
+    SetTimezone(CommandResponse),
+    Timezones(Vec<String>),
+    Users(UsersResponse),
+    AuthorizedKeys(Result<Vec<AuthorizedKey>, String>),
+    UserAction(Result<(), String>),
+    AuditLog(Vec<String>),
+    Crontabs(CronResponse),
+    CronAction(Result<(), String>),
+    Timers(Result<Vec<TimerInfo>, String>),
+    NetworkConfig(NetworkConfigResponse),
+    /// The number of seconds before the change is rolled back, unless it's confirmed
+    ApplyNetwork(Result<u32, String>),
+    ConfirmNetwork(Result<(), String>),
+    WifiScan(Result<Vec<WifiNetwork>, String>),
+    WifiConnect(Result<(), String>),
+    Firewall(FirewallResponse),
+    /// The number of seconds before the change is rolled back, unless it's confirmed
+    FirewallAction(Result<u32, String>),
+    ConfirmFirewall(Result<(), String>),
+    Containers(Result<ContainersResponse, String>),
+    ContainerAction(Result<(), String>),
+    /// The last lines of the container's output
+    ContainerLogs(Result<String, String>),
 }
 
 #[derive(Debug, Clone, Encode, Decode)]
 pub struct Handshake {
     pub nickname: String,
+    pub tags: Vec<String>,
     pub version: u32,
 }
 
@@ -36,17 +81,97 @@ pub struct Handshake {
 pub struct CpuResponse {
     pub global_cpu: f32,
     pub cpus: Vec<f32>,
+    pub frequencies: Vec<CpuFrequency>,
+    /// 1, 5 and 15 minute load averages
+    pub load_avg: [f32; 3],
+    /// Per second
+    pub context_switches: u64,
+    pub interrupts: u64,
+    pub brand: String,
+}
+
+/// All in MHz
+#[derive(Debug, Clone, Encode, Decode)]
+pub struct CpuFrequency {
+    pub current: u64,
+    pub min: Option<u64>,
+    pub max: Option<u64>,
 }
 
 #[derive(Debug, Clone, Encode, Decode)]
 pub struct TempResponse {
+    /// The sensor most likely to be the CPU
     pub temp: Option<f32>,
+    pub sensors: Vec<SensorInfo>,
+    pub fans: Vec<FanInfo>,
+    /// Only available on Raspberry Pis
+    pub throttle: Option<ThrottleState>,
+}
+
+#[derive(Debug, Clone, Encode, Decode)]
+pub struct SensorInfo {
+    pub label: String,
+    pub temp: f32,
+    /// Highest temperature seen since the backend started
+    pub max: f32,
+    pub critical: Option<f32>,
+}
+
+#[derive(Debug, Clone, Encode, Decode)]
+pub struct FanInfo {
+    pub label: String,
+    pub rpm: u32,
+}
+
+#[derive(Debug, Clone, Encode, Decode)]
+pub struct ThrottleState {
+    pub under_voltage: bool,
+    pub freq_capped: bool,
+    pub throttled: bool,
+    pub soft_temp_limit: bool,
+    pub under_voltage_occurred: bool,
+    pub freq_capped_occurred: bool,
+    pub throttled_occurred: bool,
+    pub soft_temp_limit_occurred: bool,
 }
 
 #[derive(Debug, Clone, Encode, Decode)]
 pub struct MemResponse {
     pub ram: UsageData,
     pub swap: UsageData,
+    pub breakdown: Option<MemBreakdown>,
+    pub zram: Vec<ZramInfo>,
+    pub pressure: Vec<PressureInfo>,
+}
+
+/// All in bytes
+#[derive(Debug, Clone, Encode, Decode)]
+pub struct MemBreakdown {
+    pub available: u64,
+    pub buffers: u64,
+    pub cached: u64,
+    pub shared: u64,
+    pub dirty: u64,
+    pub zswap: Option<u64>,
+    pub zswapped: Option<u64>,
+}
+
+/// All in bytes
+#[derive(Debug, Clone, Encode, Decode)]
+pub struct ZramInfo {
+    pub name: String,
+    pub orig_data: u64,
+    pub compr_data: u64,
+    pub mem_used: u64,
+}
+
+#[derive(Debug, Clone, Encode, Decode)]
+pub struct PressureInfo {
+    pub resource: String,
+    /// Percentage of time at least one task was stalled, averaged over 10, 60 and 300 seconds
+    pub some: [f32; 3],
+    /// Same as `some`, but for all tasks at once. Not reported for CPU on older kernels.
+    pub full: Option<[f32; 3]>,
 }
 
 #[derive(Debug, Clone, Encode, Decode)]
@@ -58,30 +183,79 @@ pub struct UsageData {
 #[derive(Debug, Clone, Encode, Decode)]
 pub struct DiskResponse {
     pub disks: Vec<DiskInfo>,
+    /// Bytes per second, summed across all shown devices
+    pub read: u64,
+    pub write: u64,
 }
 
 #[derive(Debug, Clone, Encode, Decode)]
 pub struct DiskInfo {
     pub name: String,
     pub mnt_point: String,
+    pub fs_type: String,
     pub usage: UsageData,
+    pub inodes: Option<UsageData>,
+    pub read_only: bool,
+    /// Read-only even though /etc/fstab mounts it read-write
+    pub remounted_ro: bool,
+    pub io: Option<DiskIo>,
+}
+
+#[derive(Debug, Clone, Encode, Decode)]
+pub struct DiskIo {
+    /// Bytes per second
+    pub read_rate: u64,
+    pub write_rate: u64,
+    pub read_iops: f32,
+    pub write_iops: f32,
 }
 
 #[derive(Debug, Clone, Encode, Decode)]
 pub struct NetworkResponse {
+    /// Bytes per second, summed across all shown interfaces
+    pub sent: u64,
+    pub recv: u64,
+    pub interfaces: Vec<InterfaceInfo>,
+}
+
+#[derive(Debug, Clone, Encode, Decode)]
+pub struct InterfaceInfo {
+    pub name: String,
+    pub mac: String,
+    /// In CIDR notation
+    pub addresses: Vec<String>,
+    pub up: bool,
+    /// Link speed in Mbit/s, if known
+    pub speed: Option<u32>,
+    /// Bytes per second
     pub sent: u64,
     pub recv: u64,
+    pub total_sent: u64,
+    pub total_recv: u64,
+    pub packets_sent: u64,
+    pub packets_recv: u64,
+    pub errors_sent: u64,
+    pub errors_recv: u64,
+    pub dropped_sent: u64,
+    pub dropped_recv: u64,
 }
 
 #[derive(Debug, Clone, Encode, Decode)]
 pub struct ProcessResponse {
     pub processes: Vec<ProcessInfo>,
+    /// How many processes matched, which is more than were sent if the list had to be cut short
+    pub total: u32,
 }
 
 #[derive(Debug, Clone, Encode, Decode)]
 pub struct ProcessInfo {
     pub pid: u32,
+    pub parent: Option<u32>,
     pub name: String,
+    /// Truncated, see `ProcessDetails` for the full command line
+    pub cmdline: String,
+    pub user: String,
+    pub kernel_thread: bool,
     pub cpu: f32,
     pub mem: u64,
     pub status: ProcessStatus,
@@ -95,6 +269,61 @@ pub enum ProcessStatus {
     Other,
 }
 
+#[derive(Debug, Clone, Encode, Decode)]
+pub enum SignalResult {
+    Sent,
+    NoSuchProcess,
+    PermissionDenied,
+    Unsupported,
+    Failed(String),
+}
+
+#[derive(Debug, Clone, Encode, Decode)]
+pub struct ProcessDetails {
+    pub pid: u32,
+    pub name: String,
+    pub cmdline: Vec<String>,
+    pub exe: Option<String>,
+    pub user: Option<String>,
+    pub group: Option<String>,
+    pub parent: Option<u32>,
+    /// Seconds since the Unix epoch
+    pub start_time: u64,
+    pub threads: u32,
+    pub nice: i32,
+    /// In the kernel's list format, e.g. "0-3"
+    pub cpu_affinity: String,
+    pub status: ProcessStatus,
+    pub cpu: f32,
+    pub mem: u64,
+    pub disk_read: u64,
+    pub disk_written: u64,
+    pub environment: Vec<String>,
+    pub open_files: Vec<OpenFile>,
+    /// Set if there were too many open files to send them all
+    pub open_files_truncated: bool,
+    pub listening: Vec<ListeningSocket>,
+}
+
+#[derive(Debug, Clone, Encode, Decode)]
+pub struct OpenFile {
+    pub fd: u32,
+    pub target: String,
+}
+
+#[derive(Debug, Clone, Encode, Decode)]
+pub struct ListeningSocket {
+    pub protocol: SocketProtocol,
+    pub address: String,
+}
+
+#[derive(Debug, Clone, Copy, Encode, Decode, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SocketProtocol {
+    Tcp,
+    Udp,
+}
+
 #[derive(Debug, Clone, Encode, Decode)]
 pub struct HostResponse {
     pub hostname: String,
@@ -105,6 +334,179 @@ pub struct HostResponse {
     pub os_version: String,
     pub dp_version: String,
     pub num_pkgs: usize,
+    pub timezone: String,
+}
+
+#[derive(Debug, Clone, Encode, Decode)]
+pub struct UsersResponse {
+    pub users: Vec<UserInfo>,
+    pub groups: Vec<GroupInfo>,
+}
+
+#[derive(Debug, Clone, Encode, Decode)]
+pub struct UserInfo {
+    pub name: String,
+    pub uid: u32,
+    pub gid: u32,
+    pub home: String,
+    pub shell: String,
+    pub groups: Vec<String>,
+    /// None if the shadow file couldn't be read
+    pub locked: Option<bool>,
+}
+
+#[derive(Debug, Clone, Encode, Decode)]
+pub struct GroupInfo {
+    pub name: String,
+    pub gid: u32,
+    pub members: Vec<String>,
+}
+
+#[derive(Debug, Clone, Encode, Decode)]
+pub struct AuthorizedKey {
+    pub options: String,
+    pub key_type: String,
+    pub data: String,
+    pub comment: String,
+}
+
+#[derive(Debug, Clone, Encode, Decode)]
+pub struct CronResponse {
+    pub entries: Vec<CronEntry>,
+}
+
+#[derive(Debug, Clone, Encode, Decode)]
+pub struct CronEntry {
+    pub source: CronSource,
+    /// Index of the line within its crontab
+    pub line: u32,
+    pub raw: String,
+    pub user: String,
+    pub schedule: String,
+    pub command: String,
+    pub enabled: bool,
+}
+
+#[derive(Debug, Clone, Encode, Decode, PartialEq, Eq)]
+pub enum CronSource {
+    /// A user's crontab, which can be edited
+    User,
+    /// A system crontab like /etc/crontab, which is read-only
+    System(String),
+}
+
+#[derive(Debug, Clone, Encode, Decode)]
+pub struct TimerInfo {
+    pub name: String,
+    pub description: String,
+    /// The unit started when the timer elapses
+    pub unit: String,
+    pub active: bool,
+    pub next: String,
+    pub last: String,
+}
+
+#[derive(Debug, Clone, Encode, Decode)]
+pub struct NetworkConfigResponse {
+    pub interfaces: Vec<InterfaceConfig>,
+    pub dns: Vec<String>,
+    /// Only ifupdown configurations can be edited
+    pub editable: bool,
+    pub rollback_pending: bool,
+    pub wifi: Option<WifiBackend>,
+}
+
+#[derive(Debug, Clone, Encode, Decode)]
+pub struct InterfaceConfig {
+    pub name: String,
+    pub mac: String,
+    pub up: bool,
+    /// Addresses currently assigned, in CIDR notation
+    pub addresses: Vec<String>,
+    pub gateway: Option<String>,
+    pub wireless: bool,
+    /// None if the interface isn't configured in /etc/network/interfaces
+    pub method: Option<AddressMethod>,
+    pub static_address: String,
+    pub static_gateway: String,
+}
+
+#[derive(Debug, Clone, Copy, Encode, Decode, PartialEq, Eq)]
+pub enum WifiBackend {
+    NetworkManager,
+    WpaSupplicant,
+}
+
+#[derive(Debug, Clone, Encode, Decode)]
+pub struct WifiNetwork {
+    pub ssid: String,
+    /// Signal strength in percent
+    pub signal: u8,
+    pub secured: bool,
+    pub connected: bool,
+}
+
+#[derive(Debug, Clone, Encode, Decode)]
+pub struct FirewallResponse {
+    pub sockets: Vec<ExposedSocket>,
+    pub backend: Option<FirewallBackend>,
+    /// The full ruleset, as printed by nft or iptables-save
+    pub ruleset: Result<String, String>,
+    /// Rules managed by the dashboard, in the order they're applied
+    pub rules: Vec<FirewallRule>,
+    pub rollback_pending: bool,
+}
+
+#[derive(Debug, Clone, Encode, Decode)]
+pub struct ExposedSocket {
+    pub socket: ListeningSocket,
+    /// None if the owning process couldn't be found
+    pub pid: Option<u32>,
+    pub process: String,
+}
+
+#[derive(Debug, Clone, Copy, Encode, Decode, PartialEq, Eq)]
+pub enum FirewallBackend {
+    Nftables,
+    Iptables,
+}
+
+#[derive(Debug, Clone, Encode, Decode)]
+pub struct ContainersResponse {
+    /// Name and version of the container engine
+    pub engine: String,
+    pub containers: Vec<ContainerInfo>,
+    pub images: Vec<ImageInfo>,
+}
+
+#[derive(Debug, Clone, Encode, Decode)]
+pub struct ContainerInfo {
+    pub id: String,
+    pub name: String,
+    pub image: String,
+    /// Like "running" or "exited"
+    pub state: String,
+    /// Human readable status, like "Up 2 hours"
+    pub status: String,
+    pub ports: Vec<String>,
+    /// Only available for running containers
+    pub stats: Option<ContainerStats>,
+}
+
+#[derive(Debug, Clone, Encode, Decode)]
+pub struct ContainerStats {
+    pub cpu: f32,
+    pub mem_used: u64,
+    pub mem_limit: u64,
+}
+
+#[derive(Debug, Clone, Encode, Decode)]
+pub struct ImageInfo {
+    pub id: String,
+    pub tags: Vec<String>,
+    pub size: u64,
+    /// Unix timestamp
+    pub created: u64,
 }
 
 #[derive(Debug, Clone, Encode, Decode)]
@@ -120,11 +522,28 @@ pub struct SoftwareInfo {
     pub desc: String,
     pub deps: String,
     pub docs: String,
+    pub category: String,
+    pub dep_ids: Vec<u16>,
 }
 
 #[derive(Debug, Clone, Encode, Decode)]
-pub struct CommandResponse {
-    pub output: Vec<u8>,
+pub enum CommandResponse {
+    Finished(CommandOutput),
+    SpawnFailed(String),
+    Denied,
+}
+
+#[derive(Debug, Clone, Encode, Decode)]
+pub struct CommandOutput {
+    pub stdout: Vec<u8>,
+    pub stderr: Vec<u8>,
+    pub status: CommandStatus,
+}
+
+#[derive(Debug, Clone, Copy, Encode, Decode, PartialEq, Eq)]
+pub enum CommandStatus {
+    Exited(i32),
+    Signaled(i32),
 }
 
 #[derive(Debug, Clone, Encode, Decode)]
@@ -135,8 +554,14 @@ pub struct ServiceResponse {
 #[derive(Debug, Clone, Encode, Decode)]
 pub struct ServiceInfo {
     pub name: String,
+    pub description: String,
     pub status: ServiceStatus,
+    pub load_state: String,
+    pub sub_state: String,
     pub start: String,
+    pub main_pid: Option<u32>,
+    pub memory: Option<u64>,
+    pub cpu_time: Option<u64>,
     pub err_log: String,
 }
 
@@ -147,3 +572,81 @@ pub enum ServiceStatus {
     Failed,
     Unknown,
 }
+
+#[derive(Debug, Clone, Encode, Decode)]
+pub struct LogResponse {
+    pub entries: Vec<LogEntry>,
+    pub next_cursor: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Encode, Decode)]
+pub struct LogEntry {
+    pub timestamp: u64,
+    pub priority: u8,
+    pub unit: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Encode, Decode)]
+pub struct DirResponse {
+    pub path: String,
+    pub entries: Vec<DirEntryInfo>,
+    pub next_offset: Option<u32>,
+    /// Whether entries in this directory can be changed
+    pub writable: bool,
+}
+
+#[derive(Debug, Clone, Encode, Decode)]
+pub struct DirEntryInfo {
+    pub name: String,
+    pub kind: FileKind,
+    pub size: u64,
+    pub mode: u32,
+    pub owner: String,
+    pub group: String,
+    pub mtime: i64,
+}
+
+#[derive(Debug, Clone, Copy, Encode, Decode, PartialEq, Eq, PartialOrd, Ord)]
+pub enum FileKind {
+    Dir,
+    File,
+    Symlink,
+    Other,
+}
+
+#[derive(Debug, Clone, Encode, Decode)]
+pub struct FileChunk {
+    pub data: Vec<u8>,
+    pub total_size: u64,
+    // Modification time in nanoseconds
+    pub mtime: u64,
+}
+
+#[derive(Debug, Clone, Encode, Decode)]
+pub struct UpdatesResponse {
+    pub packages: Vec<UpgradablePackage>,
+    /// New DietPi version, if one is available
+    pub dietpi_update: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Encode, Decode)]
+pub struct UpgradablePackage {
+    pub name: String,
+    pub current: String,
+    pub candidate: String,
+    pub origin: String,
+    pub security: bool,
+}
+
+#[derive(Debug, Clone, Encode, Decode)]
+pub enum JobEvent {
+    Started(Job),
+    Output(String),
+    Finished(CommandStatus),
+    Failed(String),
+    /// A job that was never started, which isn't part of the one that came before it
+    Rejected(String),
+}
@@ -1,6 +1,8 @@
 use bitcode::{Decode, Encode};
 use serde::Deserialize;
 
+use crate::backend::SocketProtocol;
+
 #[derive(Debug, Encode, Decode)]
 pub enum FrontendMessage {
     Request(u16, RequestFrontendMessage),
@@ -14,17 +16,58 @@ pub enum RequestFrontendMessage {
     Mem,
     Disk,
     NetIO,
-    Processes,
+    Processes(ProcessListQuery),
+    ProcessDetail(u32),
+    Signal(SignalAction),
+    Renice(ReniceAction),
+    IoNice(IoNiceAction),
+    Affinity(AffinityAction),
     Host,
     Software,
     Command(CommandAction),
     Services,
+    ServiceAction(ServiceAction),
+    Logs(LogQuery),
+    ListDir(ListDirAction),
+    ReadFile(ReadFileAction),
+    WriteFile(WriteFileAction),
+    CreateDir(String),
+    Rename(RenameAction),
+    Delete(String),
+    Updates,
+    Power(PowerAction),
+    SetHostname(String),
+    SetTimezone(String),
+    Timezones,
+    Users,
+    AuthorizedKeys(String),
+    UserAction(UserAction),
+    AuditLog,
+    Crontabs,
+    CronAction(CronAction),
+    Timers,
+    NetworkConfig,
+    ApplyNetwork(NetworkChange),
+    ConfirmNetwork,
+    WifiScan,
+    WifiConnect(WifiConnectAction),
+    Firewall,
+    FirewallAction(FirewallAction),
+    ConfirmFirewall,
+    Containers,
+    ContainerAction(ContainerAction),
+    ContainerLogs(String),
 }
 
 #[derive(Debug, Encode, Decode)]
 pub enum ActionFrontendMessage {
     Terminal(Vec<u8>),
-    Signal(SignalAction),
+    LogFollow(LogFollowAction),
+    StartJob(Job),
+    /// Replaces the terminal session with a shell in the given container
+    ContainerExec(String),
+    /// Removes what was written of an upload that won't be finished
+    CancelUpload(CancelUploadAction),
 }
 
 #[derive(Debug, Encode, Decode, Deserialize)]
@@ -40,6 +83,166 @@ pub enum Signal {
     Pause,
     Resume,
     Kill,
+    Hup,
+    Int,
+    Usr1,
+    Usr2,
+}
+
+#[derive(Debug, Encode, Decode, Deserialize)]
+pub struct ReniceAction {
+    pub pid: u32,
+    pub nice: i32,
+}
+
+#[derive(Debug, Encode, Decode, Deserialize)]
+pub struct IoNiceAction {
+    pub pid: u32,
+    pub class: IoClass,
+    /// Only used by the realtime and best-effort classes
+    pub level: u8,
+}
+
+#[derive(Debug, Encode, Decode, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum IoClass {
+    Realtime,
+    BestEffort,
+    Idle,
+}
+
+#[derive(Debug, Encode, Decode)]
+pub struct AffinityAction {
+    pub pid: u32,
+    pub cpus: Vec<u32>,
+}
+
+#[derive(Debug, Encode, Decode, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PowerAction {
+    Reboot,
+    Poweroff,
+}
+
+#[derive(Debug, Encode, Decode)]
+pub enum UserAction {
+    Add {
+        name: String,
+        password: String,
+        groups: Vec<String>,
+    },
+    Remove {
+        name: String,
+        remove_home: bool,
+    },
+    SetPassword {
+        name: String,
+        password: String,
+    },
+    Lock(String),
+    Unlock(String),
+    /// A full `authorized_keys` line, without options
+    AddKey {
+        name: String,
+        key: String,
+    },
+    /// Removes every entry with the given base64 key data
+    RemoveKey {
+        name: String,
+        key: String,
+    },
+}
+
+#[derive(Debug, Encode, Decode)]
+pub struct CronAction {
+    pub user: String,
+    pub operation: CronOperation,
+}
+
+/// Lines are identified by their index and contents, so edits fail if the crontab changed since it was read
+#[derive(Debug, Encode, Decode)]
+pub enum CronOperation {
+    Add {
+        schedule: String,
+        command: String,
+    },
+    Edit {
+        line: u32,
+        original: String,
+        schedule: String,
+        command: String,
+    },
+    SetEnabled {
+        line: u32,
+        original: String,
+        enabled: bool,
+    },
+    Delete {
+        line: u32,
+        original: String,
+    },
+}
+
+#[derive(Debug, Encode, Decode)]
+pub struct NetworkChange {
+    pub interface: String,
+    pub method: AddressMethod,
+    /// In CIDR notation, only used with a static address
+    pub address: String,
+    pub gateway: String,
+    /// Left unchanged if empty
+    pub dns: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, Encode, Decode, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum AddressMethod {
+    Dhcp,
+    Static,
+}
+
+#[derive(Debug, Encode, Decode)]
+pub struct WifiConnectAction {
+    pub ssid: String,
+    /// Empty for open networks
+    pub password: String,
+}
+
+#[derive(Debug, Encode, Decode)]
+pub enum FirewallAction {
+    Add(FirewallRule),
+    Remove(FirewallRule),
+}
+
+#[derive(Debug, Clone, Encode, Decode, PartialEq, Eq)]
+pub struct FirewallRule {
+    pub action: RuleAction,
+    pub protocol: SocketProtocol,
+    pub port: u16,
+    /// An address or network in CIDR notation, empty to match any source
+    pub source: String,
+}
+
+#[derive(Debug, Clone, Copy, Encode, Decode, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RuleAction {
+    Allow,
+    Deny,
+}
+
+#[derive(Debug, Encode, Decode, Deserialize)]
+pub struct ContainerAction {
+    pub id: String,
+    pub operation: ContainerOperation,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Encode, Decode, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ContainerOperation {
+    Start,
+    Stop,
+    Restart,
+    Remove,
 }
 
 #[derive(Debug, Encode, Decode)]
@@ -47,3 +250,103 @@ pub struct CommandAction {
     pub cmd: String,
     pub args: Vec<String>,
 }
+
+#[derive(Debug, Encode, Decode, Deserialize)]
+pub struct ServiceAction {
+    pub name: String,
+    pub operation: ServiceOperation,
+}
+
+#[derive(Debug, Encode, Decode, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ServiceOperation {
+    Start,
+    Stop,
+    Restart,
+    Enable,
+    Disable,
+}
+
+/// Filtered and sorted on the backend, so the processes that matter most are the ones that fit in a response
+#[derive(Debug, Clone, Encode, Decode)]
+pub struct ProcessListQuery {
+    pub filter: String,
+    pub kernel: bool,
+    pub sort: ProcessSort,
+    pub reverse: bool,
+}
+
+#[derive(Debug, Clone, Copy, Encode, Decode)]
+pub enum ProcessSort {
+    Pid,
+    Name,
+    Status,
+    Cpu,
+    Ram,
+}
+
+#[derive(Debug, Clone, Default, Encode, Decode)]
+pub struct LogQuery {
+    pub unit: Option<String>,
+    pub priority: Option<u8>,
+    pub since: Option<String>,
+    pub until: Option<String>,
+    pub filter: Option<String>,
+    pub cursor: Option<String>,
+}
+
+#[derive(Debug, Encode, Decode)]
+pub enum LogFollowAction {
+    Start(u16, LogQuery),
+    Stop(u16),
+}
+
+#[derive(Debug, Encode, Decode)]
+pub struct ListDirAction {
+    pub path: String,
+    pub offset: u32,
+}
+
+#[derive(Debug, Encode, Decode)]
+pub struct ReadFileAction {
+    pub path: String,
+    pub offset: u64,
+    pub len: u32,
+}
+
+#[derive(Debug, Encode, Decode)]
+pub struct WriteFileAction {
+    pub path: String,
+    // Chosen by the frontend for each upload, so concurrent uploads to one path don't share a temporary file
+    pub upload_id: u64,
+    pub offset: u64,
+    pub data: Vec<u8>,
+    // Moves the uploaded data into place once all chunks have been written
+    pub last: bool,
+    // Refuse to replace the file if it was modified since it was read
+    pub expected_mtime: Option<u64>,
+    // Copy the file being replaced to a .bak file
+    pub backup: bool,
+}
+
+#[derive(Debug, Encode, Decode)]
+pub struct CancelUploadAction {
+    pub path: String,
+    pub upload_id: u64,
+}
+
+#[derive(Debug, Encode, Decode, Deserialize)]
+pub struct RenameAction {
+    pub from: String,
+    pub to: String,
+}
+
+/// Long-running jobs, whose output is streamed back as it's produced
+#[derive(Debug, Clone, Copy, Encode, Decode, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Job {
+    AptUpdate,
+    AptUpgrade,
+    #[serde(rename = "dietpi_update")]
+    DietPiUpdate,
+}
@@ -6,6 +6,9 @@ use tokio_util::codec::{Framed, LengthDelimitedCodec};
 pub mod backend;
 pub mod frontend;
 
+// Keeps file transfer frames well below the 64KiB frame limit
+pub const MAX_CHUNK_SIZE: u32 = 32 * 1024;
+
 pub struct DashboardSocket(Framed<TcpStream, LengthDelimitedCodec>);
 
 impl DashboardSocket {
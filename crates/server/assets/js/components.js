@@ -30,12 +30,16 @@
             const trigger = this.getAttribute("trigger") || "click";
             const targetAttr = this.getAttribute("target");
             const disableAttr = this.getAttribute("disable");
+            const confirmAttr = this.getAttribute("confirm");
 
             const form = this.querySelector("form");
 
             const swap = async (evt) => {
                 evt.preventDefault();
 
+                if (confirmAttr && !window.confirm(confirmAttr))
+                    return;
+
                 const target = !targetAttr ? this : targetAttr === "none" ? null : document.querySelector(targetAttr);
                 const disableEl = document.querySelector(disableAttr);
 
@@ -82,7 +86,7 @@
             const term = new Terminal();
             term.open(this);
 
-            const socket = new WebSocket("/terminal/ws");
+            const socket = new WebSocket(this.getAttribute("src") ?? "/terminal/ws");
             socket.binaryType = "arraybuffer";
 
             socket.onmessage = (e) => term.write(new Uint8Array(e.data));
@@ -91,6 +95,108 @@
         }
     });
 
+    customElements.define("log-follow", class extends HTMLElement {
+        connectedCallback() {
+            const checkbox = this.querySelector("input[type='checkbox']");
+            const target = document.querySelector(this.getAttribute("target"));
+
+            checkbox.addEventListener("change", () => {
+                if (checkbox.checked) {
+                    this.socket = new WebSocket(this.getAttribute("src"));
+                    this.socket.onmessage = (e) => target.insertAdjacentHTML("afterbegin", e.data);
+                } else {
+                    this.socket?.close();
+                }
+            });
+        }
+
+        disconnectedCallback() {
+            this.socket?.close();
+        }
+    });
+
+    customElements.define("job-output", class extends HTMLElement {
+        connectedCallback() {
+            const output = this.querySelector("pre");
+
+            this.socket = new WebSocket(this.getAttribute("src"));
+            this.socket.onmessage = (e) => {
+                // Only follow the output if the user hasn't scrolled up to read something
+                const atBottom = output.scrollTop + output.clientHeight >= output.scrollHeight - 10;
+
+                output.insertAdjacentHTML("beforeend", e.data);
+
+                if (atBottom)
+                    output.scrollTop = output.scrollHeight;
+            };
+        }
+
+        disconnectedCallback() {
+            this.socket?.close();
+        }
+    });
+
+    customElements.define("file-upload", class extends HTMLElement {
+        connectedCallback() {
+            const input = this.querySelector("input[type='file']");
+            const action = this.getAttribute("action");
+            const target = document.querySelector(this.getAttribute("target"));
+
+            input.addEventListener("change", async () => {
+                input.disabled = true;
+
+                try {
+                    // Each response only reports its own file, so gather the banners of all of them
+                    let text;
+                    const banners = [];
+                    for (const file of input.files) {
+                        const url = `${action}&name=${encodeURIComponent(file.name)}`;
+                        const resp = await fetch(url, { method: "POST", body: file, headers: { "fx-request": "true" } });
+                        text = await resp.text();
+
+                        if (!resp.ok)
+                            throw new Error(`${resp.statusText}: ${text}`);
+
+                        const template = document.createElement("template");
+                        template.innerHTML = text;
+                        banners.push(...template.content.querySelectorAll(".file-results > .command-banner"));
+                    }
+
+                    if (text) {
+                        target.outerHTML = text;
+                        document.querySelector(this.getAttribute("target"))
+                            ?.querySelector(".file-results")
+                            ?.replaceChildren(...banners);
+                    }
+                } catch (err) {
+                    document.querySelector("main").innerText = `Error: ${err.message}`;
+                }
+
+                input.disabled = false;
+            });
+        }
+    });
+
+    customElements.define("code-editor", class extends HTMLElement {
+        connectedCallback() {
+            const textarea = this.querySelector("textarea");
+            const highlight = this.querySelector("pre");
+
+            const syncScroll = () => {
+                highlight.scrollTop = textarea.scrollTop;
+                highlight.scrollLeft = textarea.scrollLeft;
+            };
+
+            textarea.addEventListener("input", () => {
+                // A trailing newline wouldn't be rendered without something after it
+                highlight.textContent = textarea.value + " ";
+                microlight.reset();
+                syncScroll();
+            });
+            textarea.addEventListener("scroll", syncScroll);
+        }
+    });
+
     customElements.define("array-form", class extends HTMLElement {
         connectedCallback() {
             const form = this.querySelector("form");
@@ -1,12 +1,21 @@
-use std::{collections::VecDeque, net::IpAddr};
+use std::{
+    collections::VecDeque,
+    net::IpAddr,
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
 
 use anyhow::{Context, Result, anyhow};
 use config::PROTOCOL_VERSION;
 use log::{error, info, warn};
 use proto::{
     DashboardSocket,
-    backend::{ActionBackendMessage, BackendMessage, Handshake, ResponseBackendMessage},
-    frontend::{ActionFrontendMessage, FrontendMessage, RequestFrontendMessage},
+    backend::{
+        ActionBackendMessage, BackendMessage, Handshake, JobEvent, LogEntry, ResponseBackendMessage,
+    },
+    frontend::{
+        ActionFrontendMessage, FrontendMessage, LogFollowAction, LogQuery, RequestFrontendMessage,
+    },
 };
 use slab::Slab;
 use tokio::{
@@ -16,9 +25,16 @@ use tokio::{
 
 use super::{SharedBackendRegistry, cache::BackendCache};
 
-#[derive(Debug)]
+const JOB_BUF_LEN: usize = 2000;
+// How often to check for log sockets that have closed, so quiet logs don't keep being followed
+const LOG_SWEEP_INTERVAL: Duration = Duration::from_secs(5);
+
+static NEXT_CONNECTION_ID: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, Clone)]
 pub struct BackendInfo {
     pub nickname: String,
+    pub tags: Vec<String>,
     pub handle: BackendHandle,
 }
 
@@ -34,6 +50,13 @@ enum BackendRequest {
     PushTerminalHandle {
         term_tx: mpsc::UnboundedSender<Vec<u8>>,
     },
+    FollowLogs {
+        query: LogQuery,
+        log_tx: mpsc::UnboundedSender<LogEntry>,
+    },
+    FollowJob {
+        job_tx: mpsc::UnboundedSender<JobEvent>,
+    },
 }
 
 pub struct BackendConnection {
@@ -75,6 +98,7 @@ impl BackendConnection {
 
         let conn_info = BackendInfo {
             nickname,
+            tags: handshake.tags,
             handle: BackendHandle::new(tx),
         };
 
@@ -106,6 +130,16 @@ impl BackendConnection {
         Ok(handshake)
     }
 
+    async fn stop_following_logs(&mut self, id: u16) -> Result<()> {
+        let msg = ActionFrontendMessage::LogFollow(LogFollowAction::Stop(id));
+        let msg = FrontendMessage::Action(msg);
+
+        self.socket
+            .write_frame(msg)
+            .await
+            .context("failed to write log follow frame")
+    }
+
     async fn handle_requests(
         &mut self,
         mut rx: mpsc::UnboundedReceiver<BackendRequest>,
@@ -113,10 +147,27 @@ impl BackendConnection {
         let mut in_progress: Slab<oneshot::Sender<ResponseBackendMessage>> = Slab::new();
         let mut term_txs = Vec::new();
         let mut term_buf = VecDeque::with_capacity(10_000);
+        let mut log_txs: Slab<mpsc::UnboundedSender<LogEntry>> = Slab::new();
+        let mut job_txs = Vec::new();
+        // Output of the most recent job, so it can be shown after navigating away and back
+        let mut job_buf = VecDeque::with_capacity(JOB_BUF_LEN);
         let mut cache = BackendCache::new();
+        let mut log_sweep = tokio::time::interval(LOG_SWEEP_INTERVAL);
 
         loop {
             tokio::select! {
+                _ = log_sweep.tick() => {
+                    let closed: Vec<_> = log_txs
+                        .iter()
+                        .filter(|(_, tx)| tx.is_closed())
+                        .map(|(id, _)| id)
+                        .collect();
+
+                    for id in closed {
+                        log_txs.remove(id);
+                        self.stop_following_logs(id as u16).await?;
+                    }
+                }
                 chan_result = rx.recv() => {
                     let Some(conn_req) = chan_result else {
                         break;
@@ -152,6 +203,22 @@ impl BackendConnection {
                                 term_txs.push(term_tx);
                             }
                         },
+                        BackendRequest::FollowJob { job_tx } => {
+                            if job_buf.iter().all(|event: &JobEvent| job_tx.send(event.clone()).is_ok()) {
+                                job_txs.push(job_tx);
+                            }
+                        },
+                        BackendRequest::FollowLogs { query, log_tx } => {
+                            let id = log_txs.insert(log_tx) as u16;
+
+                            let msg = ActionFrontendMessage::LogFollow(LogFollowAction::Start(id, query));
+                            let msg = FrontendMessage::Action(msg);
+
+                            self.socket
+                                .write_frame(msg)
+                                .await
+                                .context("failed to write log follow frame")?;
+                        },
                     }
                 }
                 resp_result = self.read_frame() => {
@@ -187,6 +254,32 @@ impl BackendConnection {
 
                                     term_txs.retain(|tx| tx.send(data.clone()).is_ok());
                                 }
+                                // Only shown to whoever is watching, it isn't part of the last job's output
+                                ActionBackendMessage::Job(event @ JobEvent::Rejected(_)) => {
+                                    job_txs.retain(|tx| tx.send(event.clone()).is_ok());
+                                }
+                                ActionBackendMessage::Job(event) => {
+                                    if matches!(event, JobEvent::Started(_)) {
+                                        job_buf.clear();
+                                    }
+                                    if job_buf.len() == job_buf.capacity() {
+                                        job_buf.pop_front();
+                                    }
+                                    job_buf.push_back(event.clone());
+
+                                    job_txs.retain(|tx| tx.send(event.clone()).is_ok());
+                                }
+                                ActionBackendMessage::LogFollow(id, entry) => {
+                                    let is_open = log_txs
+                                        .get(id as usize)
+                                        .is_some_and(|tx| tx.send(entry).is_ok());
+
+                                    // Tell the backend to stop following once nobody is listening anymore
+                                    if !is_open {
+                                        log_txs.try_remove(id as usize);
+                                        self.stop_following_logs(id).await?;
+                                    }
+                                }
                             }
                         }
                     }
@@ -201,11 +294,19 @@ impl BackendConnection {
 #[derive(Debug, Clone)]
 pub struct BackendHandle {
     tx: mpsc::UnboundedSender<BackendRequest>,
+    id: u64,
 }
 
 impl BackendHandle {
     fn new(tx: mpsc::UnboundedSender<BackendRequest>) -> Self {
-        Self { tx }
+        let id = NEXT_CONNECTION_ID.fetch_add(1, Ordering::Relaxed);
+
+        Self { tx, id }
+    }
+
+    /// Unique to each connection, so it changes when a backend reconnects
+    pub fn id(&self) -> u64 {
+        self.id
     }
 
     pub async fn send_req(&self, req: RequestFrontendMessage) -> Result<ResponseBackendMessage> {
@@ -242,4 +343,28 @@ impl BackendHandle {
 
         Ok(term_rx)
     }
+
+    pub async fn follow_job(&self) -> Result<mpsc::UnboundedReceiver<JobEvent>> {
+        let (job_tx, job_rx) = mpsc::unbounded_channel();
+
+        let msg = BackendRequest::FollowJob { job_tx };
+
+        self.tx
+            .send(msg)
+            .context("failed to follow job, connection likely closed")?;
+
+        Ok(job_rx)
+    }
+
+    pub async fn follow_logs(&self, query: LogQuery) -> Result<mpsc::UnboundedReceiver<LogEntry>> {
+        let (log_tx, log_rx) = mpsc::unbounded_channel();
+
+        let msg = BackendRequest::FollowLogs { query, log_tx };
+
+        self.tx
+            .send(msg)
+            .context("failed to follow logs, connection likely closed")?;
+
+        Ok(log_rx)
+    }
 }
@@ -5,14 +5,14 @@ use std::{
 };
 
 use anyhow::{Context, Result};
-use conn::{BackendConnection, BackendInfo};
+use conn::BackendConnection;
 use log::{error, info};
 use tokio::net::TcpListener;
 
 mod cache;
 mod conn;
 
-pub use conn::BackendHandle;
+pub use conn::{BackendHandle, BackendInfo};
 
 pub type BackendRegistry = HashMap<IpAddr, BackendInfo>;
 pub type SharedBackendRegistry = Arc<Mutex<BackendRegistry>>;
@@ -9,7 +9,7 @@ use data_encoding::BASE64;
 use http_body_util::BodyExt;
 use hyper::{
     StatusCode,
-    body::Incoming,
+    body::{Bytes, Incoming},
     header,
     http::request::Parts as RequestParts,
     upgrade::{self, Upgraded},
@@ -22,7 +22,7 @@ use proto::{
 use ring::digest::SHA1_FOR_LEGACY_USE_ONLY;
 use tokio_tungstenite::{WebSocketStream, tungstenite::protocol::Role};
 
-use crate::backend::BackendHandle;
+use crate::backend::{BackendHandle, BackendInfo};
 
 use super::{
     FrontendContext,
@@ -113,6 +113,30 @@ impl ServerRequest {
         })
     }
 
+    /// Unlike `extract_backends`, this doesn't fall back to another backend when it's disconnected
+    pub fn backend_connection_id(&self, addr: IpAddr) -> Option<u64> {
+        let backends = self.context.backends.lock().unwrap();
+
+        backends.get(&addr).map(|info| info.handle.id())
+    }
+
+    /// Like `backend_connection_id`, for talking to a specific backend instead of the selected one
+    pub fn backend_handle(&self, addr: IpAddr) -> Option<BackendHandle> {
+        let backends = self.context.backends.lock().unwrap();
+
+        backends.get(&addr).map(|info| info.handle.clone())
+    }
+
+    /// Every connected backend, for pages that show all of them at once
+    pub fn all_backends(&self) -> Vec<(IpAddr, BackendInfo)> {
+        let backends = self.context.backends.lock().unwrap();
+
+        backends
+            .iter()
+            .map(|(addr, info)| (*addr, info.clone()))
+            .collect()
+    }
+
     pub async fn send_backend_req(
         &self,
         req: RequestFrontendMessage,
@@ -149,14 +173,17 @@ impl ServerRequest {
         })
     }
 
-    pub async fn extract_form<T: serde::de::DeserializeOwned>(
-        &mut self,
-    ) -> Result<T, ServerResponse> {
-        let Some(body) = self.body.take() else {
-            return Err(ServerResponse::new()
+    /// Takes the body without reading it, so it can be processed as it arrives
+    pub fn take_body(&mut self) -> Result<Incoming, ServerResponse> {
+        self.body.take().ok_or_else(|| {
+            ServerResponse::new()
                 .status(StatusCode::INTERNAL_SERVER_ERROR)
-                .body("form already extracted"));
-        };
+                .body("body already extracted")
+        })
+    }
+
+    pub async fn extract_body(&mut self) -> Result<Bytes, ServerResponse> {
+        let body = self.take_body()?;
 
         let body = body.collect().await.map_err(|_| {
             ServerResponse::new()
@@ -164,7 +191,15 @@ impl ServerRequest {
                 .body("needs body")
         })?;
 
-        serde_urlencoded::from_bytes(&body.to_bytes()).map_err(|_| {
+        Ok(body.to_bytes())
+    }
+
+    pub async fn extract_form<T: serde::de::DeserializeOwned>(
+        &mut self,
+    ) -> Result<T, ServerResponse> {
+        let body = self.extract_body().await?;
+
+        serde_urlencoded::from_bytes(&body).map_err(|_| {
             ServerResponse::new()
                 .status(StatusCode::BAD_REQUEST)
                 .body("invalid form body")
@@ -252,6 +287,25 @@ impl ServerRequest {
         Ok(())
     }
 
+    // There's only a single login, so any authenticated session counts as an admin
+    pub fn is_admin(&self) -> bool {
+        self.config().enable_login
+            && self
+                .cookies
+                .get("token")
+                .is_some_and(|token| self.context.logins.get().contains_token(token))
+    }
+
+    pub fn check_admin(&self) -> Result<(), ServerResponse> {
+        if !self.is_admin() {
+            return Err(ServerResponse::new()
+                .status(StatusCode::FORBIDDEN)
+                .body("admin access required"));
+        }
+
+        Ok(())
+    }
+
     pub fn extract_logins(&self) -> SharedLoginMap {
         self.context.logins.clone()
     }
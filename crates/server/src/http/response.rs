@@ -1,16 +1,28 @@
-use http_body_util::Full;
+use std::io;
+
+use futures_util::{Stream, TryStreamExt};
+use http_body_util::{BodyExt, Full, StreamBody, combinators::UnsyncBoxBody};
 use hyper::{
     StatusCode,
-    body::Bytes,
+    body::{Bytes, Frame},
     header::{self, HeaderName, HeaderValue},
     http::response::Builder as ResponseBuilder,
 };
+use log::error;
+
+type ResponseBody = UnsyncBoxBody<Bytes, io::Error>;
 
 pub struct ServerResponse {
     builder: ResponseBuilder,
-    body: Bytes,
+    body: ResponseBody,
+}
+pub type BuiltResponse = hyper::Response<ResponseBody>;
+
+fn full_body(body: Bytes) -> ResponseBody {
+    Full::new(body)
+        .map_err(|never| match never {})
+        .boxed_unsync()
 }
-pub type BuiltResponse = hyper::Response<Full<Bytes>>;
 
 pub enum RedirectType {
     Permanent,
@@ -21,7 +33,7 @@ impl ServerResponse {
     pub fn new() -> Self {
         Self {
             builder: ResponseBuilder::new(),
-            body: Bytes::new(),
+            body: full_body(Bytes::new()),
         }
     }
 
@@ -51,13 +63,28 @@ impl ServerResponse {
     }
 
     pub fn body<T: Into<Bytes>>(mut self, body: T) -> Self {
-        self.body = body.into();
+        self.body = full_body(body.into());
+        self
+    }
+
+    /// Sends the body as it's produced, for responses too large to hold in memory.
+    /// An error ends the response early, which the client sees as a failed download.
+    pub fn stream_body<S>(mut self, stream: S) -> Self
+    where
+        S: Stream<Item = io::Result<Bytes>> + Send + 'static,
+    {
+        self.body = StreamBody::new(stream.map_ok(Frame::data)).boxed_unsync();
         self
     }
 
     pub fn build(self) -> BuiltResponse {
-        let body = Full::from(self.body);
+        // An invalid header or status shouldn't take down the whole server
+        self.builder.body(self.body).unwrap_or_else(|err| {
+            error!("Failed to build response: {err}");
 
-        self.builder.body(body).unwrap()
+            let mut resp = hyper::Response::new(full_body(Bytes::new()));
+            *resp.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
+            resp
+        })
     }
 }
@@ -37,17 +37,76 @@ pub async fn router(req: ServerRequest) -> Result<BuiltResponse, std::convert::I
         (GET, ["login"]) => login::page,
         (POST, ["login"]) => login::form,
 
+        (GET, ["overview"]) => overview::page,
+        (GET, ["overview", "select"]) => overview::select,
         (GET, ["system"]) => system::page,
 
         (GET, ["process"]) => process::page,
+        (GET, ["process", "detail"]) => process::detail,
+        (POST, ["process", "renice"]) => process::renice,
+        (POST, ["process", "ionice"]) => process::ionice,
+        (POST, ["process", "affinity"]) => process::affinity,
         (GET, ["process", "signal"]) => process::signal,
 
         (GET, ["software"]) => software::page,
         (POST, ["software"]) => software::form,
 
         (GET, ["service"]) => service::page,
+        (POST, ["service", "action"]) => service::action,
+
+        (GET, ["logs"]) => logs::page,
+        (GET, ["logs", "ws"]) => logs::socket,
+
+        (GET, ["files"]) => files::page,
+        (GET, ["files", "download"]) => files::download,
+        (POST, ["files", "upload"]) => files::upload,
+        (POST, ["files", "mkdir"]) => files::mkdir,
+        (POST, ["files", "rename"]) => files::rename,
+        (POST, ["files", "delete"]) => files::delete,
+        (GET, ["files", "edit"]) => editor::page,
+        (POST, ["files", "edit"]) => editor::form,
+
+        (GET, ["schedule"]) => schedule::page,
+        (POST, ["schedule", "cron", "add"]) => schedule::add,
+        (POST, ["schedule", "cron", "edit"]) => schedule::edit,
+        (POST, ["schedule", "cron", "toggle"]) => schedule::toggle,
+        (POST, ["schedule", "cron", "delete"]) => schedule::delete,
+        (POST, ["schedule", "run"]) => schedule::run,
+
+        (GET, ["network"]) => network::page,
+        (POST, ["network", "apply"]) => network::apply,
+        (GET, ["network", "status"]) => network::status,
+        (POST, ["network", "confirm"]) => network::confirm,
+        (GET, ["network", "wifi", "scan"]) => network::wifi_scan,
+        (POST, ["network", "wifi", "connect"]) => network::wifi_connect,
+
+        (GET, ["firewall"]) => firewall::page,
+        (POST, ["firewall", "add"]) => firewall::add,
+        (POST, ["firewall", "remove"]) => firewall::remove,
+        (POST, ["firewall", "confirm"]) => firewall::confirm,
+
+        (GET, ["containers"]) => containers::page,
+        (POST, ["containers", "action"]) => containers::action,
+        (GET, ["containers", "logs"]) => containers::logs,
+        (GET, ["containers", "logs", "output"]) => containers::logs_refresh,
 
         (GET, ["management"]) => management::page,
+        (POST, ["management", "power"]) => management::power,
+        (GET, ["management", "status"]) => management::status,
+        (POST, ["management", "hostname"]) => management::hostname,
+        (POST, ["management", "timezone"]) => management::timezone,
+        (GET, ["management", "users", "detail"]) => users::page,
+        (POST, ["management", "users", "add"]) => users::add,
+        (POST, ["management", "users", "password"]) => users::password,
+        (POST, ["management", "users", "lock"]) => users::lock,
+        (POST, ["management", "users", "remove"]) => users::remove,
+        (POST, ["management", "users", "keys", "add"]) => users::add_key,
+        (POST, ["management", "users", "keys", "remove"]) => users::remove_key,
+
+        (GET, ["updates"]) => updates::page,
+        (POST, ["updates", "start"]) => updates::start,
+        (GET, ["updates", "badge"]) => updates::badge,
+        (GET, ["updates", "ws"]) => updates::socket,
 
         (GET, ["terminal"]) => terminal::page,
         (GET, ["terminal", "ws"]) => terminal::socket,
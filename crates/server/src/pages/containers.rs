@@ -0,0 +1,244 @@
+use std::time::{Duration, UNIX_EPOCH};
+
+use maud::{Markup, html};
+use pretty_bytes_typed::pretty_bytes_binary;
+use proto::{
+    backend::{ContainerInfo, ContainersResponse},
+    frontend::{ContainerAction, ContainerOperation},
+};
+use serde::Deserialize;
+
+use crate::http::{request::ServerRequest, response::ServerResponse};
+
+use super::template::{send_req, template};
+
+#[derive(Deserialize)]
+pub struct LogsQuery {
+    id: String,
+}
+
+fn operation_label(operation: ContainerOperation) -> &'static str {
+    match operation {
+        ContainerOperation::Start => "Start",
+        ContainerOperation::Stop => "Stop",
+        ContainerOperation::Restart => "Restart",
+        ContainerOperation::Remove => "Remove",
+    }
+}
+
+fn action_button(container: &ContainerInfo, operation: ContainerOperation) -> Markup {
+    let op_name = match operation {
+        ContainerOperation::Start => "start",
+        ContainerOperation::Stop => "stop",
+        ContainerOperation::Restart => "restart",
+        ContainerOperation::Remove => "remove",
+    };
+    let confirm_msg = format!("Remove {}? This can't be undone.", container.name);
+
+    html! {
+        server-swap
+            action={"/containers/action?operation=" (op_name) "&id=" (container.id)}
+            method="POST"
+            target="#containers"
+            confirm=[(operation == ContainerOperation::Remove).then_some(confirm_msg)]
+        {
+            button { (operation_label(operation)) }
+        }
+    }
+}
+
+fn container_row(container: &ContainerInfo, admin: bool) -> Markup {
+    let running = container.state == "running";
+
+    html! {
+        tr {
+            td title=(container.id) { (container.name) }
+            td { (container.image) }
+            td .container-state.-running[running] { (container.status) }
+            td {
+                @if let Some(stats) = &container.stats {
+                    (format!("{:.1}", stats.cpu)) "%"
+                }
+            }
+            td {
+                @if let Some(stats) = &container.stats {
+                    (pretty_bytes_binary(stats.mem_used, Some(0)))
+                    @if stats.mem_limit > 0 {
+                        " / " (pretty_bytes_binary(stats.mem_limit, Some(0)))
+                    }
+                }
+            }
+            td {
+                @for port in &container.ports {
+                    div { code { (port) } }
+                }
+            }
+            td {
+                @if admin {
+                    .actions-cell {
+                        @if running {
+                            (action_button(container, ContainerOperation::Stop))
+                            (action_button(container, ContainerOperation::Restart))
+                        } @else {
+                            (action_button(container, ContainerOperation::Start))
+                            (action_button(container, ContainerOperation::Remove))
+                        }
+                    }
+                }
+                a href={"/containers/logs?id=" (container.id)} { "View logs" }
+                @if admin && running {
+                    " "
+                    a href={"/terminal?container=" (container.id)} { "Open shell" }
+                }
+            }
+        }
+    }
+}
+
+fn containers_section(
+    data: &Result<ContainersResponse, String>,
+    admin: bool,
+    banner: Markup,
+) -> Markup {
+    html! {
+        section #containers {
+            h2 { "Containers" }
+            (banner)
+            @match data {
+                Ok(data) => {
+                    p { "Using " (data.engine) }
+                    @if data.containers.is_empty() {
+                        p { "No containers have been created" }
+                    } @else {
+                        table .container-table {
+                            tr {
+                                th { "Name" }
+                                th { "Image" }
+                                th { "Status" }
+                                th { "CPU" }
+                                th { "Memory" }
+                                th { "Ports" }
+                                th { "Actions" }
+                            }
+                            @for container in &data.containers {
+                                (container_row(container, admin))
+                            }
+                        }
+                    }
+                }
+                Err(err) => {
+                    p .command-banner.-failure { "Failed to connect to the container engine: " (err) }
+                    p { "Make sure Docker or Podman is running, or set the socket in the backend config" }
+                }
+            }
+        }
+    }
+}
+
+pub async fn page(req: ServerRequest) -> Result<ServerResponse, ServerResponse> {
+    req.check_login()?;
+
+    let data = send_req!(req, Containers)?;
+
+    let content = html! {
+        (containers_section(&data, req.is_admin(), html! {}))
+
+        @if let Ok(data) = &data {
+            section {
+                h2 { "Images" }
+                @if data.images.is_empty() {
+                    p { "No images have been pulled" }
+                } @else {
+                    table .container-table {
+                        tr {
+                            th { "Tags" }
+                            th { "ID" }
+                            th { "Size" }
+                            th { "Created" }
+                        }
+                        @for image in &data.images {
+                            tr {
+                                td {
+                                    @if image.tags.is_empty() {
+                                        "None"
+                                    }
+                                    @for tag in &image.tags {
+                                        div { (tag) }
+                                    }
+                                }
+                                td { code { (image.id) } }
+                                td { (pretty_bytes_binary(image.size, Some(0))) }
+                                td {
+                                    (humantime::format_rfc3339_seconds(UNIX_EPOCH + Duration::from_secs(image.created)))
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    };
+
+    template(&req, content)
+}
+
+pub async fn action(req: ServerRequest) -> Result<ServerResponse, ServerResponse> {
+    req.check_login()?;
+    req.check_admin()?;
+
+    let action: ContainerAction = req.extract_query()?;
+    let label = operation_label(action.operation);
+
+    let result = send_req!(req, ContainerAction(action))?;
+    let data = send_req!(req, Containers)?;
+
+    let banner = html! {
+        @match result {
+            Ok(()) => p .command-banner.-success { (label) " succeeded" },
+            Err(err) => p .command-banner.-failure { (label) " failed: " (err) },
+        }
+    };
+
+    template(&req, containers_section(&data, true, banner))
+}
+
+fn logs_output(result: Result<String, String>) -> Markup {
+    html! {
+        div #container-logs {
+            @match result {
+                Ok(logs) if logs.is_empty() => p { "No output yet" },
+                Ok(logs) => pre .container-logs { (logs) },
+                Err(err) => p .command-banner.-failure { "Failed to get the logs: " (err) },
+            }
+        }
+    }
+}
+
+pub async fn logs(req: ServerRequest) -> Result<ServerResponse, ServerResponse> {
+    req.check_login()?;
+
+    let query: LogsQuery = req.extract_query()?;
+    let result = send_req!(req, ContainerLogs(query.id.clone()))?;
+
+    let content = html! {
+        section {
+            h2 { "Logs for " (query.id) }
+            a href="/containers" { "Back to containers" }
+            server-swap action={"/containers/logs/output?id=" (query.id)} trigger="click" target="#container-logs" {
+                button { "Refresh" }
+            }
+            (logs_output(result))
+        }
+    };
+
+    template(&req, content)
+}
+
+pub async fn logs_refresh(req: ServerRequest) -> Result<ServerResponse, ServerResponse> {
+    req.check_login()?;
+
+    let query: LogsQuery = req.extract_query()?;
+    let result = send_req!(req, ContainerLogs(query.id))?;
+
+    template(&req, logs_output(result))
+}
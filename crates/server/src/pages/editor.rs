@@ -0,0 +1,228 @@
+use maud::{Markup, html};
+use proto::{
+    backend::{CommandResponse, CommandStatus},
+    frontend::{ServiceAction, ServiceOperation},
+};
+use ring::digest::{SHA256, digest};
+use serde::Deserialize;
+
+use crate::http::{request::ServerRequest, response::ServerResponse};
+
+use super::{
+    files::{read_whole_file, write_whole_file},
+    template::{send_req, template},
+};
+
+// Larger files are better off being downloaded
+const MAX_EDIT_SIZE: u64 = 1024 * 1024;
+
+#[derive(Deserialize)]
+pub struct EditorQuery {
+    path: String,
+}
+
+#[derive(Deserialize)]
+pub struct EditorForm {
+    path: String,
+    hash: String,
+    mtime: u64,
+    content: String,
+    #[serde(default)]
+    backup: bool,
+    #[serde(default)]
+    service: String,
+}
+
+struct EditorState<'a> {
+    path: &'a str,
+    content: &'a str,
+    hash: String,
+    mtime: u64,
+    backup: bool,
+    service: &'a str,
+}
+
+fn hash_content(data: &[u8]) -> String {
+    data_encoding::HEXLOWER.encode(digest(&SHA256, data).as_ref())
+}
+
+fn editor(state: &EditorState, messages: Markup) -> Markup {
+    html! {
+        section #editor {
+            h2 { "Edit " (state.path) }
+
+            (messages)
+
+            server-swap action="/files/edit" method="POST" trigger="submit" target="#editor" {
+                form .editor-form {
+                    input type="hidden" name="path" value=(state.path);
+                    input type="hidden" name="hash" value=(state.hash);
+                    input type="hidden" name="mtime" value=(state.mtime);
+
+                    code-editor {
+                        // Browsers strip a single leading newline from textareas
+                        textarea name="content" spellcheck="false" { "\n" (state.content) }
+                        pre .microlight aria-hidden="true" { (state.content) }
+                    }
+
+                    .editor-options {
+                        label {
+                            input type="checkbox" name="backup" value="true" checked[state.backup];
+                            " Create .bak backup"
+                        }
+                        label {
+                            "Restart service after save: "
+                            input name="service" placeholder="e.g. nginx" value=(state.service);
+                        }
+                        input type="submit" value="Save";
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn error_section(path: &str, err: &str) -> Markup {
+    html! {
+        section #editor {
+            h2 { "Edit " (path) }
+            p .command-banner.-failure { (err) }
+        }
+    }
+}
+
+pub async fn page(req: ServerRequest) -> Result<ServerResponse, ServerResponse> {
+    req.check_login()?;
+    req.check_admin()?;
+
+    let query: EditorQuery = req.extract_query()?;
+
+    let content = match read_whole_file(&req, &query.path, MAX_EDIT_SIZE).await? {
+        Ok((data, mtime)) => match std::str::from_utf8(&data) {
+            Ok(content) => {
+                let state = EditorState {
+                    path: &query.path,
+                    content,
+                    hash: hash_content(&data),
+                    mtime,
+                    backup: true,
+                    service: "",
+                };
+
+                editor(&state, html! {})
+            }
+            Err(_) => error_section(&query.path, "File is not valid UTF-8 text"),
+        },
+        Err(err) => error_section(&query.path, &format!("Failed to read file: {err}")),
+    };
+
+    template(&req, content)
+}
+
+pub async fn form(mut req: ServerRequest) -> Result<ServerResponse, ServerResponse> {
+    req.check_login()?;
+    req.check_admin()?;
+
+    let form: EditorForm = req.extract_form().await?;
+
+    // Check that nobody else changed the file since it was opened
+    let (current_data, current_mtime) =
+        match read_whole_file(&req, &form.path, MAX_EDIT_SIZE).await? {
+            Ok(current) => current,
+            Err(err) => {
+                let content = error_section(&form.path, &format!("Failed to read file: {err}"));
+                return template(&req, content);
+            }
+        };
+    let current_hash = hash_content(&current_data);
+
+    // Textareas always submit CRLF line endings, so only keep them if the file already used them
+    let uses_crlf = current_data.windows(2).any(|x| x == b"\r\n");
+    let content = if uses_crlf {
+        form.content.clone()
+    } else {
+        form.content.replace("\r\n", "\n")
+    };
+
+    let mut state = EditorState {
+        path: &form.path,
+        content: &content,
+        hash: form.hash.clone(),
+        mtime: form.mtime,
+        backup: form.backup,
+        service: form.service.trim(),
+    };
+
+    if current_hash != form.hash {
+        // Let the user keep their changes, and overwrite if they save again
+        state.hash = current_hash;
+        state.mtime = current_mtime;
+
+        let messages = html! {
+            p .command-banner.-failure {
+                "The file was changed by someone else since it was opened, so your changes were not saved. "
+                "Saving again will overwrite those changes."
+            }
+        };
+
+        return template(&req, editor(&state, messages));
+    }
+
+    let write_result = write_whole_file(
+        &req,
+        &form.path,
+        content.as_bytes(),
+        Some(form.mtime),
+        form.backup,
+    )
+    .await?;
+
+    if let Err(err) = write_result {
+        let messages = html! {
+            p .command-banner.-failure { "Failed to save file: " (err) }
+        };
+
+        return template(&req, editor(&state, messages));
+    }
+
+    // Pick up the new hash and mtime, so the next save doesn't look like a conflict
+    if let Ok((data, mtime)) = read_whole_file(&req, &form.path, MAX_EDIT_SIZE).await? {
+        state.hash = hash_content(&data);
+        state.mtime = mtime;
+    }
+
+    let restart_result = if !state.service.is_empty() {
+        let action = ServiceAction {
+            name: state.service.into(),
+            operation: ServiceOperation::Restart,
+        };
+
+        Some(send_req!(req, ServiceAction(action))?)
+    } else {
+        None
+    };
+
+    let messages = html! {
+        p .command-banner.-success { "File saved" }
+        @match restart_result {
+            Some(CommandResponse::Finished(output)) if output.status == CommandStatus::Exited(0) => {
+                p .command-banner.-success { "Restarted " (state.service) }
+            }
+            Some(CommandResponse::Finished(output)) => {
+                details .command-banner.-failure {
+                    summary { "Failed to restart " (state.service) }
+                    pre { (String::from_utf8_lossy(&output.stderr)) }
+                }
+            }
+            Some(CommandResponse::SpawnFailed(err)) => {
+                p .command-banner.-failure { "Failed to restart " (state.service) ": " (err) }
+            }
+            Some(CommandResponse::Denied) => {
+                p .command-banner.-failure { "Failed to restart " (state.service) ": invalid service name" }
+            }
+            None => {}
+        }
+    };
+
+    template(&req, editor(&state, messages))
+}
@@ -0,0 +1,584 @@
+use std::io;
+
+use futures_util::stream;
+use http_body_util::BodyExt;
+use hyper::{
+    StatusCode,
+    body::{Bytes, Incoming},
+    header,
+};
+use maud::{Markup, html};
+use pretty_bytes_typed::pretty_bytes;
+use proto::{
+    MAX_CHUNK_SIZE,
+    backend::{DirEntryInfo, FileChunk, FileKind, ResponseBackendMessage},
+    frontend::{
+        ActionFrontendMessage, CancelUploadAction, ListDirAction, ReadFileAction, RenameAction,
+        RequestFrontendMessage, WriteFileAction,
+    },
+};
+use serde::Deserialize;
+
+use crate::{
+    backend::BackendHandle,
+    http::{request::ServerRequest, response::ServerResponse},
+};
+
+use super::template::{send_req, template};
+
+#[derive(Deserialize)]
+pub struct FilesQuery {
+    #[serde(default)]
+    path: String,
+    #[serde(default)]
+    offset: u32,
+}
+
+#[derive(Deserialize)]
+pub struct EntryForm {
+    path: String,
+    name: String,
+}
+
+#[derive(Deserialize)]
+pub struct RenameForm {
+    path: String,
+    from: String,
+    to: String,
+}
+
+fn join_path(dir: &str, name: &str) -> String {
+    // Roots are listed with their full path
+    if dir.is_empty() {
+        return name.into();
+    }
+
+    format!("{}/{name}", dir.trim_end_matches('/'))
+}
+
+fn url_with_path(endpoint: &str, path: &str) -> String {
+    let query = serde_urlencoded::to_string([("path", path)]).unwrap();
+    format!("{endpoint}?{query}")
+}
+
+fn format_mode(kind: FileKind, mode: u32) -> String {
+    let type_char = match kind {
+        FileKind::Dir => 'd',
+        FileKind::Symlink => 'l',
+        FileKind::File | FileKind::Other => '-',
+    };
+
+    let perms = (0..9).rev().map(|bit| {
+        let is_set = mode & (1 << bit) != 0;
+        match (is_set, bit % 3) {
+            (false, _) => '-',
+            (true, 2) => 'r',
+            (true, 1) => 'w',
+            (true, _) => 'x',
+        }
+    });
+
+    std::iter::once(type_char).chain(perms).collect()
+}
+
+fn breadcrumbs(path: &str) -> Markup {
+    // Pair each path component with the full path up to it
+    let crumbs = path
+        .split('/')
+        .filter(|x| !x.is_empty())
+        .scan(String::new(), |prefix, part| {
+            prefix.push('/');
+            prefix.push_str(part);
+            Some((part, prefix.clone()))
+        });
+
+    html! {
+        p .breadcrumbs {
+            a href="/files" { "Roots" }
+            @for (part, prefix) in crumbs {
+                " / "
+                a href=(url_with_path("/files", &prefix)) { (part) }
+            }
+        }
+    }
+}
+
+fn entry_row(dir: &str, entry: &DirEntryInfo, writable: bool) -> Markup {
+    let full_path = join_path(dir, &entry.name);
+    let mtime = std::time::UNIX_EPOCH + std::time::Duration::from_secs(entry.mtime.max(0) as u64);
+
+    html! {
+        tr {
+            td {
+                @if entry.kind == FileKind::Dir {
+                    a href=(url_with_path("/files", &full_path)) { (entry.name) "/" }
+                } @else {
+                    a href=(url_with_path("/files/download", &full_path)) download { (entry.name) }
+                }
+            }
+            td {
+                @if entry.kind != FileKind::Dir {
+                    (pretty_bytes(entry.size, Some(1)))
+                }
+            }
+            td .mode { (format_mode(entry.kind, entry.mode)) }
+            td { (entry.owner) ":" (entry.group) }
+            td { (humantime::format_rfc3339_seconds(mtime)) }
+            td {
+                // Roots themselves can't be modified
+                @if writable && !dir.is_empty() {
+                    .actions-cell {
+                        @if entry.kind == FileKind::File {
+                            a href=(url_with_path("/files/edit", &full_path)) { "Edit" }
+                        }
+                        details {
+                            summary { "Rename" }
+                            server-swap action="/files/rename" method="POST" trigger="submit" target="#file-browser" {
+                                form {
+                                    input type="hidden" name="path" value=(dir);
+                                    input type="hidden" name="from" value=(entry.name);
+                                    input name="to" value=(entry.name) required;
+                                    input type="submit" value="Rename";
+                                }
+                            }
+                        }
+                        server-swap
+                            action="/files/delete"
+                            method="POST"
+                            trigger="submit"
+                            target="#file-browser"
+                            confirm={"Are you sure you want to delete " (full_path) "?"}
+                        {
+                            form {
+                                input type="hidden" name="path" value=(dir);
+                                input type="hidden" name="name" value=(entry.name);
+                                input type="submit" value="Delete";
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+async fn browser(
+    req: &ServerRequest,
+    path: &str,
+    offset: u32,
+    result: Option<Result<(), String>>,
+) -> Result<Markup, ServerResponse> {
+    let action = ListDirAction {
+        path: path.into(),
+        offset,
+    };
+    let listing = send_req!(req, ListDir(action))?;
+    let admin = req.is_admin();
+
+    Ok(html! {
+        section #file-browser {
+            h2 { "Files" }
+
+            (breadcrumbs(path))
+
+            // Always present, so uploads of several files can show the results of each one here
+            .file-results {
+                @if let Some(Err(err)) = result {
+                    p .command-banner.-failure { (err) }
+                }
+            }
+
+            @match listing {
+                Ok(listing) => {
+                    @let writable = admin && listing.writable;
+
+                    @if writable {
+                        .file-forms {
+                            server-swap action="/files/mkdir" method="POST" trigger="submit" target="#file-browser" {
+                                form {
+                                    input type="hidden" name="path" value=(listing.path);
+                                    input name="name" placeholder="New directory" required;
+                                    input type="submit" value="Create";
+                                }
+                            }
+                            file-upload action=(url_with_path("/files/upload", &listing.path)) target="#file-browser" {
+                                label {
+                                    "Upload: "
+                                    input type="file" multiple;
+                                }
+                            }
+                        }
+                    }
+
+                    table .files-table {
+                        tr {
+                            th { "Name" }
+                            th { "Size" }
+                            th { "Mode" }
+                            th { "Owner" }
+                            th { "Modified" }
+                            th { "Actions" }
+                        }
+                        @for entry in &listing.entries {
+                            (entry_row(&listing.path, entry, writable))
+                        }
+                    }
+
+                    @if let Some(next_offset) = listing.next_offset {
+                        @let next = serde_urlencoded::to_string([("path", listing.path.clone()), ("offset", next_offset.to_string())]).unwrap();
+                        a href={"/files?" (next)} { "More entries" }
+                    }
+                }
+                Err(err) => {
+                    p .command-banner.-failure { "Failed to list directory: " (err) }
+                }
+            }
+        }
+    })
+}
+
+pub async fn page(req: ServerRequest) -> Result<ServerResponse, ServerResponse> {
+    req.check_login()?;
+
+    let query: FilesQuery = req.extract_query()?;
+
+    let content = browser(&req, &query.path, query.offset, None).await?;
+
+    template(&req, content)
+}
+
+// Requests the file in chunks, so that no single frame goes over the size limit.
+// Returns the file's contents along with its modification time.
+// Files over `max_size` are rejected as soon as the first chunk shows their size.
+pub async fn read_whole_file(
+    req: &ServerRequest,
+    path: &str,
+    max_size: u64,
+) -> Result<Result<(Vec<u8>, u64), String>, ServerResponse> {
+    let mut data = Vec::new();
+
+    loop {
+        let action = ReadFileAction {
+            path: path.into(),
+            offset: data.len() as u64,
+            len: MAX_CHUNK_SIZE,
+        };
+
+        let chunk = match send_req!(req, ReadFile(action))? {
+            Ok(chunk) => chunk,
+            Err(err) => return Ok(Err(err)),
+        };
+
+        // Checked on every chunk, since the file could grow while it's being read
+        if chunk.total_size > max_size || (data.len() + chunk.data.len()) as u64 > max_size {
+            return Ok(Err(format!(
+                "file is larger than {}",
+                pretty_bytes(max_size, Some(0))
+            )));
+        }
+
+        let is_done =
+            chunk.data.is_empty() || data.len() + chunk.data.len() >= chunk.total_size as usize;
+        data.extend(chunk.data);
+
+        if is_done {
+            return Ok(Ok((data, chunk.mtime)));
+        }
+    }
+}
+
+// Has the backend remove a partial upload unless it was finished, including when the handler
+// is dropped part way through because the client went away
+struct UploadGuard {
+    backend: BackendHandle,
+    path: String,
+    upload_id: u64,
+    finished: bool,
+}
+
+impl UploadGuard {
+    fn new(req: &ServerRequest, path: &str) -> Result<Self, ServerResponse> {
+        Ok(Self {
+            backend: req.extract_backends()?.current_backend.1,
+            path: path.into(),
+            upload_id: rand::random(),
+            finished: false,
+        })
+    }
+}
+
+impl Drop for UploadGuard {
+    fn drop(&mut self) {
+        if self.finished {
+            return;
+        }
+
+        let action = CancelUploadAction {
+            path: std::mem::take(&mut self.path),
+            upload_id: self.upload_id,
+        };
+        let backend = self.backend.clone();
+        tokio::spawn(async move {
+            let msg = ActionFrontendMessage::CancelUpload(action);
+            let _ = backend.send_action(msg).await;
+        });
+    }
+}
+
+pub async fn write_whole_file(
+    req: &ServerRequest,
+    path: &str,
+    data: &[u8],
+    expected_mtime: Option<u64>,
+    backup: bool,
+) -> Result<Result<(), String>, ServerResponse> {
+    let mut chunks: Vec<_> = data.chunks(MAX_CHUNK_SIZE as usize).collect();
+    // Empty files still need a single write to be created
+    if chunks.is_empty() {
+        chunks.push(&[]);
+    }
+
+    let num_chunks = chunks.len();
+    let mut guard = UploadGuard::new(req, path)?;
+    let mut offset = 0;
+
+    for (i, chunk) in chunks.into_iter().enumerate() {
+        let action = WriteFileAction {
+            path: path.into(),
+            upload_id: guard.upload_id,
+            offset,
+            data: chunk.to_vec(),
+            last: i + 1 == num_chunks,
+            expected_mtime,
+            backup,
+        };
+        offset += chunk.len() as u64;
+
+        if let Err(err) = send_req!(req, WriteFile(action))? {
+            return Ok(Err(err));
+        }
+    }
+
+    guard.finished = true;
+    Ok(Ok(()))
+}
+
+async fn read_chunk(backend: &BackendHandle, path: &str, offset: u64) -> Result<FileChunk, String> {
+    let action = ReadFileAction {
+        path: path.into(),
+        offset,
+        len: MAX_CHUNK_SIZE,
+    };
+
+    match backend
+        .send_req(RequestFrontendMessage::ReadFile(action))
+        .await
+    {
+        Ok(ResponseBackendMessage::ReadFile(result)) => result,
+        Ok(_) => unreachable!(),
+        Err(err) => Err(format!("backend request failed: {err}")),
+    }
+}
+
+struct DownloadState {
+    backend: BackendHandle,
+    path: String,
+    offset: u64,
+    total_size: u64,
+    first_chunk: Option<Vec<u8>>,
+}
+
+// File names can hold any byte but `/`, which a header can't, so the full name is percent-encoded,
+// with a plain ASCII fallback for clients that don't understand `filename*`
+fn content_disposition(file_name: &str) -> String {
+    let fallback: String = file_name
+        .chars()
+        .map(|c| match c {
+            ' '..='~' if c != '"' && c != '\\' => c,
+            _ => '_',
+        })
+        .collect();
+
+    let mut encoded = String::new();
+    for &byte in file_name.as_bytes() {
+        if byte.is_ascii_alphanumeric() || b"!#$&+-.^_`|~".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{byte:02X}"));
+        }
+    }
+
+    format!("attachment; filename=\"{fallback}\"; filename*=UTF-8''{encoded}")
+}
+
+pub async fn download(req: ServerRequest) -> Result<ServerResponse, ServerResponse> {
+    req.check_login()?;
+
+    let query: FilesQuery = req.extract_query()?;
+    let backend = req.extract_backends()?.current_backend.1;
+
+    // Reading the first chunk up front means a missing file still gets a proper error
+    let first_chunk = read_chunk(&backend, &query.path, 0).await.map_err(|err| {
+        ServerResponse::new()
+            .status(StatusCode::NOT_FOUND)
+            .body(format!("failed to read file: {err}"))
+    })?;
+
+    let file_name = query.path.rsplit('/').next().unwrap_or_default();
+    let content_disposition = content_disposition(file_name);
+
+    let total_size = first_chunk.total_size;
+    let state = DownloadState {
+        backend,
+        path: query.path,
+        offset: first_chunk.data.len() as u64,
+        total_size,
+        first_chunk: Some(first_chunk.data),
+    };
+
+    // The rest is only requested as the client reads it, so the whole file is never held in memory
+    let stream = stream::try_unfold(state, |mut state| async move {
+        if let Some(data) = state.first_chunk.take() {
+            return Ok(Some((Bytes::from(data), state)));
+        }
+        if state.offset >= state.total_size {
+            return Ok(None);
+        }
+
+        let mut data = read_chunk(&state.backend, &state.path, state.offset)
+            .await
+            .map_err(io::Error::other)?
+            .data;
+        if data.is_empty() {
+            return Err(io::Error::other("file was truncated while downloading"));
+        }
+
+        // Stick to the size that was sent in the headers, even if the file grew since
+        data.truncate((state.total_size - state.offset) as usize);
+        state.offset += data.len() as u64;
+
+        Ok(Some((Bytes::from(data), state)))
+    });
+
+    Ok(ServerResponse::new()
+        .header(header::CONTENT_TYPE, "application/octet-stream")
+        .header(header::CONTENT_LENGTH, total_size)
+        .header(header::CONTENT_DISPOSITION, content_disposition)
+        .stream_body(stream))
+}
+
+#[derive(Deserialize)]
+pub struct UploadQuery {
+    path: String,
+    name: String,
+}
+
+// Passes the body on to the backend as it arrives, so large uploads are never held in memory
+async fn stream_upload(
+    req: &ServerRequest,
+    path: &str,
+    body: &mut Incoming,
+) -> Result<Result<(), String>, ServerResponse> {
+    let chunk_size = MAX_CHUNK_SIZE as usize;
+    let mut buf = Vec::with_capacity(chunk_size);
+    let mut guard = UploadGuard::new(req, path)?;
+    let mut offset = 0;
+
+    loop {
+        let frame = body.frame().await.transpose().map_err(|_| {
+            ServerResponse::new()
+                .status(StatusCode::BAD_REQUEST)
+                .body("failed to read upload")
+        })?;
+        let is_end = frame.is_none();
+
+        if let Some(data) = frame.and_then(|frame| frame.into_data().ok()) {
+            buf.extend_from_slice(&data);
+        }
+
+        // The final write is always sent, even when empty, since it moves the file into place
+        while buf.len() >= chunk_size || is_end {
+            let data: Vec<_> = buf.drain(..buf.len().min(chunk_size)).collect();
+            let last = is_end && buf.is_empty();
+
+            let action = WriteFileAction {
+                path: path.into(),
+                upload_id: guard.upload_id,
+                offset,
+                data,
+                last,
+                expected_mtime: None,
+                backup: false,
+            };
+            offset += action.data.len() as u64;
+
+            if let Err(err) = send_req!(req, WriteFile(action))? {
+                return Ok(Err(err));
+            }
+            if last {
+                guard.finished = true;
+                return Ok(Ok(()));
+            }
+        }
+    }
+}
+
+pub async fn upload(mut req: ServerRequest) -> Result<ServerResponse, ServerResponse> {
+    req.check_login()?;
+    req.check_admin()?;
+
+    let query: UploadQuery = req.extract_query()?;
+    let mut body = req.take_body()?;
+
+    let path = join_path(&query.path, &query.name);
+    let result = stream_upload(&req, &path, &mut body)
+        .await?
+        .map_err(|err| format!("Failed to upload {}: {err}", query.name));
+
+    let content = browser(&req, &query.path, 0, Some(result)).await?;
+
+    template(&req, content)
+}
+
+pub async fn mkdir(mut req: ServerRequest) -> Result<ServerResponse, ServerResponse> {
+    req.check_login()?;
+    req.check_admin()?;
+
+    let form: EntryForm = req.extract_form().await?;
+
+    let result = send_req!(req, CreateDir(join_path(&form.path, &form.name)))?;
+
+    let content = browser(&req, &form.path, 0, Some(result)).await?;
+
+    template(&req, content)
+}
+
+pub async fn rename(mut req: ServerRequest) -> Result<ServerResponse, ServerResponse> {
+    req.check_login()?;
+    req.check_admin()?;
+
+    let form: RenameForm = req.extract_form().await?;
+
+    let action = RenameAction {
+        from: join_path(&form.path, &form.from),
+        to: join_path(&form.path, &form.to),
+    };
+    let result = send_req!(req, Rename(action))?;
+
+    let content = browser(&req, &form.path, 0, Some(result)).await?;
+
+    template(&req, content)
+}
+
+pub async fn delete(mut req: ServerRequest) -> Result<ServerResponse, ServerResponse> {
+    req.check_login()?;
+    req.check_admin()?;
+
+    let form: EntryForm = req.extract_form().await?;
+
+    let result = send_req!(req, Delete(join_path(&form.path, &form.name)))?;
+
+    let content = browser(&req, &form.path, 0, Some(result)).await?;
+
+    template(&req, content)
+}
@@ -0,0 +1,282 @@
+use maud::{Markup, html};
+use proto::{
+    backend::{FirewallBackend, FirewallResponse, SocketProtocol},
+    frontend::{FirewallAction, FirewallRule, RuleAction},
+};
+use serde::Deserialize;
+
+use crate::http::{request::ServerRequest, response::ServerResponse};
+
+use super::template::{send_req, template};
+
+#[derive(Deserialize)]
+pub struct RuleForm {
+    action: RuleAction,
+    protocol: SocketProtocol,
+    port: u16,
+    #[serde(default)]
+    source: String,
+}
+
+impl From<RuleForm> for FirewallRule {
+    fn from(form: RuleForm) -> Self {
+        Self {
+            action: form.action,
+            protocol: form.protocol,
+            port: form.port,
+            source: form.source.trim().to_string(),
+        }
+    }
+}
+
+fn protocol_label(protocol: SocketProtocol) -> &'static str {
+    match protocol {
+        SocketProtocol::Tcp => "TCP",
+        SocketProtocol::Udp => "UDP",
+    }
+}
+
+fn rule_fields(rule: &FirewallRule) -> Markup {
+    html! {
+        input type="hidden" name="action" value=(match rule.action {
+            RuleAction::Allow => "allow",
+            RuleAction::Deny => "deny",
+        });
+        input type="hidden" name="protocol" value=(match rule.protocol {
+            SocketProtocol::Tcp => "tcp",
+            SocketProtocol::Udp => "udp",
+        });
+        input type="hidden" name="port" value=(rule.port);
+        input type="hidden" name="source" value=(rule.source);
+    }
+}
+
+fn pending_banner(seconds: Option<u32>) -> Markup {
+    html! {
+        p .command-banner {
+            @if let Some(seconds) = seconds {
+                "The rules have been applied, and will be reverted in " (seconds) "s unless you keep them. "
+                "If this page stops responding, wait for them to be reverted."
+            } @else {
+                "A firewall change is waiting to be kept, and will be reverted soon otherwise."
+            }
+        }
+        server-swap action="/firewall/confirm" method="POST" trigger="click" target="#firewall-rules" {
+            button { "Keep Changes" }
+        }
+    }
+}
+
+// `seconds` is how long until the rollback, if the change was just made
+fn rules_section(
+    data: &FirewallResponse,
+    admin: bool,
+    banner: Markup,
+    seconds: Option<u32>,
+) -> Markup {
+    html! {
+        section #firewall-rules {
+            h2 { "Dashboard Rules" }
+            (banner)
+            @if data.rollback_pending && admin {
+                (pending_banner(seconds))
+            }
+            p {
+                "These rules are kept separately from the rest of the firewall. "
+                "Allow rules are checked first, so they can make exceptions to deny rules."
+            }
+
+            @if data.rules.is_empty() {
+                p { "No rules have been added" }
+            } @else {
+                table .firewall-table {
+                    tr {
+                        th { "Action" }
+                        th { "Protocol" }
+                        th { "Port" }
+                        th { "Source" }
+                        @if admin {
+                            th { "Actions" }
+                        }
+                    }
+                    @for rule in &data.rules {
+                        tr {
+                            td .firewall-action.-deny[rule.action == RuleAction::Deny] {
+                                @match rule.action {
+                                    RuleAction::Allow => "Allow",
+                                    RuleAction::Deny => "Deny",
+                                }
+                            }
+                            td { (protocol_label(rule.protocol)) }
+                            td { (rule.port) }
+                            td { @if rule.source.is_empty() { "Any" } @else { (rule.source) } }
+                            @if admin {
+                                td {
+                                    @if !data.rollback_pending {
+                                        server-swap action="/firewall/remove" method="POST" trigger="submit"
+                                            target="#firewall-rules" confirm="Remove this rule?" {
+                                            form {
+                                                (rule_fields(rule))
+                                                input type="submit" value="Remove";
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+
+            @if admin && data.backend.is_some() && !data.rollback_pending {
+                h3 { "Add Rule" }
+                server-swap action="/firewall/add" method="POST" trigger="submit" target="#firewall-rules" {
+                    form .management-form {
+                        select name="action" {
+                            option value="allow" { "Allow" }
+                            option value="deny" { "Deny" }
+                        }
+                        select name="protocol" {
+                            option value="tcp" { "TCP" }
+                            option value="udp" { "UDP" }
+                        }
+                        input type="number" name="port" min="1" max="65535" placeholder="Port" required;
+                        input type="text" name="source" placeholder="Source, e.g. 192.168.1.0/24, empty for any";
+                        input type="submit" value="Add";
+                    }
+                }
+            }
+        }
+    }
+}
+
+pub async fn page(req: ServerRequest) -> Result<ServerResponse, ServerResponse> {
+    req.check_login()?;
+
+    let data = send_req!(req, Firewall)?;
+
+    let content = html! {
+        section {
+            h2 { "Listening Ports" }
+            @if data.sockets.is_empty() {
+                p { "Nothing is listening" }
+            } @else {
+                table .firewall-table {
+                    tr {
+                        th { "Protocol" }
+                        th { "Address" }
+                        th { "Process" }
+                    }
+                    @for socket in &data.sockets {
+                        tr {
+                            td { (protocol_label(socket.socket.protocol)) }
+                            td { code { (socket.socket.address) } }
+                            td {
+                                @if let Some(pid) = socket.pid {
+                                    a href={"/process/detail?pid=" (pid)} { (socket.process) " (" (pid) ")" }
+                                } @else {
+                                    "Unknown"
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        (rules_section(&data, req.is_admin(), html! {}, None))
+
+        section {
+            h2 { "Firewall" }
+            @match data.backend {
+                Some(FirewallBackend::Nftables) => p { "Using nftables" },
+                Some(FirewallBackend::Iptables) => p { "Using iptables" },
+                None => p { "No supported firewall was found, install nftables or iptables to add rules" },
+            }
+            @match &data.ruleset {
+                Ok(ruleset) if ruleset.trim().is_empty() => p { "The ruleset is empty" },
+                Ok(ruleset) => {
+                    details {
+                        summary { "Full Ruleset" }
+                        pre .firewall-ruleset { (ruleset) }
+                    }
+                }
+                Err(err) => p .command-banner.-failure { "Failed to read the ruleset: " (err) },
+            }
+        }
+    };
+
+    template(&req, content)
+}
+
+async fn rule_action(
+    req: &ServerRequest,
+    label: &str,
+    action: FirewallAction,
+) -> Result<ServerResponse, ServerResponse> {
+    // Warn when a rule could cut off the dashboard itself, since the rollback is all that's left then
+    let config = req.config();
+    let affects_dashboard = match &action {
+        FirewallAction::Add(rule) | FirewallAction::Remove(rule) => {
+            rule.protocol == SocketProtocol::Tcp
+                && [config.http_port, config.backend_port].contains(&rule.port)
+        }
+    };
+
+    let result = send_req!(req, FirewallAction(action))?;
+    let data = send_req!(req, Firewall)?;
+
+    let banner = html! {
+        @match &result {
+            Ok(_) => {
+                p .command-banner.-success { (label) " succeeded" }
+                @if affects_dashboard {
+                    p .command-banner { "This rule matches a port used by the dashboard." }
+                }
+            }
+            Err(err) => p .command-banner.-failure { (label) " failed: " (err) },
+        }
+    };
+
+    template(req, rules_section(&data, true, banner, result.ok()))
+}
+
+pub async fn add(mut req: ServerRequest) -> Result<ServerResponse, ServerResponse> {
+    req.check_login()?;
+    req.check_admin()?;
+
+    let form: RuleForm = req.extract_form().await?;
+
+    rule_action(&req, "Adding the rule", FirewallAction::Add(form.into())).await
+}
+
+pub async fn remove(mut req: ServerRequest) -> Result<ServerResponse, ServerResponse> {
+    req.check_login()?;
+    req.check_admin()?;
+
+    let form: RuleForm = req.extract_form().await?;
+
+    rule_action(
+        &req,
+        "Removing the rule",
+        FirewallAction::Remove(form.into()),
+    )
+    .await
+}
+
+pub async fn confirm(req: ServerRequest) -> Result<ServerResponse, ServerResponse> {
+    req.check_login()?;
+    req.check_admin()?;
+
+    let result = send_req!(req, ConfirmFirewall)?;
+    let data = send_req!(req, Firewall)?;
+
+    let banner = html! {
+        @match result {
+            Ok(()) => p .command-banner.-success { "The changes have been kept" },
+            Err(err) => p .command-banner.-failure { "Keeping the changes failed: " (err) },
+        }
+    };
+
+    template(&req, rules_section(&data, true, banner, None))
+}
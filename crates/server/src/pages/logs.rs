@@ -0,0 +1,174 @@
+use std::time::{Duration, SystemTime};
+
+use futures_util::{SinkExt, StreamExt};
+use maud::{Markup, html};
+use proto::{backend::LogEntry, frontend::LogQuery};
+use serde::{Deserialize, Serialize};
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::http::{request::ServerRequest, response::ServerResponse};
+
+use super::template::{send_req, template};
+
+const PRIORITY_NAMES: [&str; 8] = [
+    "emerg", "alert", "crit", "err", "warning", "notice", "info", "debug",
+];
+
+#[derive(Default, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct LogsQuery {
+    unit: String,
+    priority: String,
+    since: String,
+    until: String,
+    filter: String,
+    cursor: String,
+}
+
+fn non_empty(s: &str) -> Option<String> {
+    let s = s.trim();
+    (!s.is_empty()).then(|| s.to_string())
+}
+
+impl LogsQuery {
+    fn to_log_query(&self) -> LogQuery {
+        LogQuery {
+            unit: non_empty(&self.unit),
+            priority: self.priority.parse().ok(),
+            since: non_empty(&self.since),
+            until: non_empty(&self.until),
+            filter: non_empty(&self.filter),
+            cursor: non_empty(&self.cursor),
+        }
+    }
+}
+
+fn log_row(entry: &LogEntry) -> Markup {
+    let time = SystemTime::UNIX_EPOCH + Duration::from_micros(entry.timestamp);
+    let priority = PRIORITY_NAMES
+        .get(entry.priority as usize)
+        .copied()
+        .unwrap_or("unknown");
+
+    html! {
+        tr .{"-priority-" (entry.priority.min(7))} {
+            td { (humantime::format_rfc3339_seconds(time)) }
+            td { (priority) }
+            td { (entry.unit) }
+            td .message { (entry.message) }
+        }
+    }
+}
+
+fn filter_form(query: &LogsQuery) -> Markup {
+    html! {
+        form .logs-form method="GET" action="/logs" {
+            input name="unit" placeholder="Unit (e.g. ssh.service)" value=(query.unit);
+            select name="priority" {
+                option value="" { "Any priority" }
+                @for (num, name) in PRIORITY_NAMES.iter().enumerate() {
+                    @let selected = query.priority == num.to_string();
+                    option value=(num) selected[selected] { (name) " and above" }
+                }
+            }
+            input name="since" placeholder="Since (e.g. -1h, 2024-01-01)" value=(query.since);
+            input name="until" placeholder="Until" value=(query.until);
+            input name="filter" placeholder="Search messages" value=(query.filter);
+            input type="submit" value="Search";
+        }
+    }
+}
+
+pub async fn page(req: ServerRequest) -> Result<ServerResponse, ServerResponse> {
+    req.check_login()?;
+
+    let query: LogsQuery = req.extract_query()?;
+
+    let data = send_req!(req, Logs(query.to_log_query()))?;
+
+    // Following always starts from the newest entry, so the cursor doesn't apply
+    let follow_query = LogsQuery {
+        cursor: String::new(),
+        ..query.clone()
+    };
+    let follow_query = serde_urlencoded::to_string(&follow_query).unwrap();
+
+    let content = html! {
+        section {
+            h2 { "Logs" }
+
+            (filter_form(&query))
+
+            log-follow src={"/logs/ws?" (follow_query)} target="#log-entries" {
+                label {
+                    input type="checkbox";
+                    " Live follow"
+                }
+            }
+
+            @if let Some(err) = &data.error {
+                p .command-banner.-failure { "Failed to read journal: " (err) }
+            }
+
+            table .logs-table {
+                thead {
+                    tr {
+                        th { "Time" }
+                        th { "Priority" }
+                        th { "Unit" }
+                        th { "Message" }
+                    }
+                }
+                tbody #log-entries {
+                    @for entry in &data.entries {
+                        (log_row(entry))
+                    }
+                }
+            }
+
+            @if let Some(cursor) = data.next_cursor {
+                @let older_query = LogsQuery { cursor, ..query };
+                @let older_query = serde_urlencoded::to_string(&older_query).unwrap();
+
+                a href={"/logs?" (older_query)} { "Older entries" }
+            }
+        }
+    };
+
+    template(&req, content)
+}
+
+pub async fn socket(req: ServerRequest) -> Result<ServerResponse, ServerResponse> {
+    req.check_login()?;
+
+    let query: LogsQuery = req.extract_query()?;
+    let backend = req.extract_backends()?.current_backend.1;
+
+    req.extract_websocket(async move |mut ws| {
+        let Ok(mut log_rx) = backend.follow_logs(query.to_log_query()).await else {
+            return;
+        };
+
+        loop {
+            tokio::select! {
+                entry = log_rx.recv() => {
+                    let Some(entry) = entry else {
+                        break;
+                    };
+
+                    let row = log_row(&entry).into_string();
+
+                    if ws.send(Message::text(row)).await.is_err() {
+                        break;
+                    }
+                }
+                data = ws.next() => {
+                    // The client never sends anything meaningful, so this only detects disconnects
+                    let Some(Ok(_)) = data else {
+                        break;
+                    };
+                }
+            }
+        }
+    })
+}
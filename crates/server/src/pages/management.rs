@@ -1,16 +1,77 @@
-use std::time::Duration;
+use std::{
+    net::IpAddr,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 
-use maud::html;
+use maud::{Markup, html};
+use proto::{
+    backend::{CommandResponse, CommandStatus},
+    frontend::PowerAction,
+};
+use serde::Deserialize;
 
 use crate::http::{request::ServerRequest, response::ServerResponse};
 
-use super::template::{send_req, template};
+use super::{
+    template::{send_req, template},
+    users::users_section,
+};
+
+// Stop polling eventually, in case the backend never comes back
+const RECONNECT_TIMEOUT: Duration = Duration::from_secs(300);
+
+#[derive(Deserialize)]
+pub struct PowerForm {
+    action: PowerAction,
+}
+
+#[derive(Deserialize)]
+pub struct HostnameForm {
+    hostname: String,
+}
+
+#[derive(Deserialize)]
+pub struct TimezoneForm {
+    timezone: String,
+}
+
+#[derive(Deserialize)]
+pub struct PowerStatusQuery {
+    addr: IpAddr,
+    conn: u64,
+    action: PowerAction,
+    since: u64,
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+fn power_verb(action: PowerAction) -> &'static str {
+    match action {
+        PowerAction::Reboot => "reboot",
+        PowerAction::Poweroff => "poweroff",
+    }
+}
 
 pub async fn page(req: ServerRequest) -> Result<ServerResponse, ServerResponse> {
     req.check_login()?;
 
     let data = send_req!(req, Host)?;
 
+    let (timezones, users, audit_log) = if req.is_admin() {
+        (
+            send_req!(req, Timezones)?,
+            Some(send_req!(req, Users)?),
+            send_req!(req, AuditLog)?,
+        )
+    } else {
+        (Vec::new(), None, Vec::new())
+    };
+
     let pretty_time = humantime::format_duration(Duration::from_secs(data.uptime));
 
     let content = html! {
@@ -30,6 +91,10 @@ pub async fn page(req: ServerRequest) -> Result<ServerResponse, ServerResponse>
                     td { "Uptime" }
                     td { (pretty_time) }
                 }
+                tr {
+                    td { "Timezone" }
+                    td { (data.timezone) }
+                }
                 tr {
                     td { "Installed Packages" }
                     td { (data.num_pkgs) }
@@ -52,7 +117,192 @@ pub async fn page(req: ServerRequest) -> Result<ServerResponse, ServerResponse>
                 }
             }
         }
+
+        @if req.is_admin() {
+            section {
+                h2 { "Host Settings" }
+
+                server-swap action="/management/hostname" method="POST" trigger="submit" target="#management-result" {
+                    form .management-form {
+                        label for="hostname" { "Hostname" }
+                        input #hostname type="text" name="hostname" value=(data.hostname) required
+                            maxlength="253" pattern="[A-Za-z0-9\\-\\.]+";
+                        input type="submit" value="Change";
+                    }
+                }
+                server-swap action="/management/timezone" method="POST" trigger="submit" target="#management-result" {
+                    form .management-form {
+                        label for="timezone" { "Timezone" }
+                        input #timezone type="text" name="timezone" value=(data.timezone) list="timezone-list" required;
+                        datalist #timezone-list {
+                            @for timezone in &timezones {
+                                option value=(timezone) {}
+                            }
+                        }
+                        input type="submit" value="Change";
+                    }
+                }
+            }
+
+            section {
+                h2 { "Power" }
+
+                .management-power {
+                    server-swap action="/management/power" method="POST" trigger="submit" target="#management-result"
+                        confirm={"Reboot " (data.hostname) "?"} {
+                        form {
+                            input type="hidden" name="action" value="reboot";
+                            input .-reboot type="submit" value="Reboot";
+                        }
+                    }
+                    server-swap action="/management/power" method="POST" trigger="submit" target="#management-result"
+                        confirm={"Power off " (data.hostname) "? It will have to be turned back on manually."} {
+                        form {
+                            input type="hidden" name="action" value="poweroff";
+                            input .-poweroff type="submit" value="Power Off";
+                        }
+                    }
+                }
+            }
+
+            #management-result {}
+        }
+
+        @if let Some(users) = &users {
+            (users_section(users, &audit_log))
+        }
+    };
+
+    template(&req, content)
+}
+
+fn command_result(label: &str, resp: &CommandResponse) -> Markup {
+    html! {
+        div #management-result {
+            @match resp {
+                CommandResponse::Finished(output) if output.status == CommandStatus::Exited(0) => {
+                    p .command-banner.-success { (label) " succeeded" }
+                }
+                CommandResponse::Finished(output) => {
+                    p .command-banner.-failure {
+                        (label) " failed: " (String::from_utf8_lossy(&output.stderr).trim())
+                    }
+                }
+                CommandResponse::SpawnFailed(err) => {
+                    p .command-banner.-failure { (label) " failed: " (err) }
+                }
+                CommandResponse::Denied => {
+                    p .command-banner.-failure { (label) " failed: invalid value" }
+                }
+            }
+        }
+    }
+}
+
+pub async fn hostname(mut req: ServerRequest) -> Result<ServerResponse, ServerResponse> {
+    req.check_login()?;
+    req.check_admin()?;
+
+    let form: HostnameForm = req.extract_form().await?;
+    let resp = send_req!(req, SetHostname(form.hostname.trim().to_string()))?;
+
+    template(&req, command_result("Changing the hostname", &resp))
+}
+
+pub async fn timezone(mut req: ServerRequest) -> Result<ServerResponse, ServerResponse> {
+    req.check_login()?;
+    req.check_admin()?;
+
+    let form: TimezoneForm = req.extract_form().await?;
+    let resp = send_req!(req, SetTimezone(form.timezone.trim().to_string()))?;
+
+    template(&req, command_result("Changing the timezone", &resp))
+}
+
+pub async fn power(mut req: ServerRequest) -> Result<ServerResponse, ServerResponse> {
+    req.check_login()?;
+    req.check_admin()?;
+
+    let form: PowerForm = req.extract_form().await?;
+
+    // Remember which connection this was, so a reconnect can be told apart from it
+    let (addr, handle) = req.extract_backends()?.current_backend;
+    let conn = handle.id();
+
+    let resp = send_req!(req, Power(form.action))?;
+
+    let label = match form.action {
+        PowerAction::Reboot => "Rebooting",
+        PowerAction::Poweroff => "Powering off",
+    };
+
+    let content = match resp {
+        CommandResponse::Finished(output) if output.status == CommandStatus::Exited(0) => {
+            let status = PowerStatusQuery {
+                addr,
+                conn,
+                action: form.action,
+                since: unix_now(),
+            };
+
+            html! {
+                div #management-result {
+                    (power_status(&status, &req))
+                }
+            }
+        }
+        resp => command_result(label, &resp),
     };
 
     template(&req, content)
 }
+
+fn power_status(query: &PowerStatusQuery, req: &ServerRequest) -> Markup {
+    let elapsed = unix_now().saturating_sub(query.since);
+    let current = req.backend_connection_id(query.addr);
+
+    let url = format!(
+        "/management/status?addr={}&conn={}&action={}&since={}",
+        query.addr,
+        query.conn,
+        power_verb(query.action),
+        query.since
+    );
+
+    let waiting = |message: &str| {
+        html! {
+            server-swap action=(url) trigger="delay" {
+                p .command-banner {
+                    (message) " (" (elapsed) "s)"
+                }
+            }
+        }
+    };
+
+    match (query.action, current) {
+        (_, Some(id)) if id != query.conn => html! {
+            p .command-banner.-success {
+                "The backend is back online after " (elapsed) "s. "
+                a href="/management" { "Reload" }
+            }
+        },
+        _ if elapsed > RECONNECT_TIMEOUT.as_secs() => html! {
+            p .command-banner.-failure {
+                "Gave up waiting for the backend after " (elapsed) "s"
+            }
+        },
+        (PowerAction::Poweroff, None) => html! {
+            p .command-banner.-success { "The system has powered off" }
+        },
+        (_, Some(_)) => waiting("Waiting for the system to go down"),
+        (PowerAction::Reboot, None) => waiting("Waiting for the backend to reconnect"),
+    }
+}
+
+pub async fn status(req: ServerRequest) -> Result<ServerResponse, ServerResponse> {
+    req.check_login()?;
+
+    let query: PowerStatusQuery = req.extract_query()?;
+
+    template(&req, power_status(&query, &req))
+}
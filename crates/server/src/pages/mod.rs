@@ -1,8 +1,18 @@
+pub mod containers;
+pub mod editor;
+pub mod files;
+pub mod firewall;
 pub mod login;
+pub mod logs;
 pub mod management;
+pub mod network;
+pub mod overview;
 pub mod process;
+pub mod schedule;
 pub mod service;
 pub mod software;
 pub mod system;
 mod template;
 pub mod terminal;
+pub mod updates;
+pub mod users;
@@ -0,0 +1,426 @@
+use std::{
+    net::IpAddr,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use maud::{Markup, html};
+use proto::{
+    backend::{
+        InterfaceConfig, NetworkConfigResponse, ResponseBackendMessage, WifiBackend, WifiNetwork,
+    },
+    frontend::{AddressMethod, NetworkChange, RequestFrontendMessage, WifiConnectAction},
+};
+use serde::Deserialize;
+
+use crate::http::{request::ServerRequest, response::ServerResponse};
+
+use super::template::{send_req, template};
+
+// Give the interface time to come back up before trying to confirm over it
+const CONFIRM_DELAY: Duration = Duration::from_secs(5);
+// A half-dead connection can take a long time to notice, so don't wait on it
+const CONFIRM_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Deserialize)]
+pub struct ApplyForm {
+    interface: String,
+    method: AddressMethod,
+    #[serde(default)]
+    address: String,
+    #[serde(default)]
+    gateway: String,
+    dns: String,
+    current_dns: String,
+}
+
+#[derive(Deserialize)]
+pub struct NetworkStatusQuery {
+    addr: IpAddr,
+    since: u64,
+    timeout: u64,
+}
+
+#[derive(Deserialize)]
+pub struct WifiConnectForm {
+    ssid: String,
+    #[serde(default)]
+    password: String,
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+fn interface_form(iface: &InterfaceConfig, dns: &str) -> Markup {
+    let is_static = iface.method == Some(AddressMethod::Static);
+
+    html! {
+        details {
+            summary { "Edit" }
+            server-swap action="/network/apply" method="POST" trigger="submit" target="#network-result"
+                confirm={"Apply these settings to " (iface.name) "? They will be rolled back if the backend can't reconnect."} {
+                form .management-form {
+                    input type="hidden" name="interface" value=(iface.name);
+                    input type="hidden" name="current_dns" value=(dns);
+                    label {
+                        "Method "
+                        select name="method" {
+                            option value="dhcp" selected[!is_static] { "DHCP" }
+                            option value="static" selected[is_static] { "Static" }
+                        }
+                    }
+                    input type="text" name="address" value=(iface.static_address) placeholder="Address, e.g. 192.168.1.2/24";
+                    input type="text" name="gateway" value=(iface.static_gateway) placeholder="Gateway";
+                    input type="text" name="dns" value=(dns) placeholder="DNS servers, e.g. 1.1.1.1, 9.9.9.9";
+                    input type="submit" value="Apply";
+                }
+            }
+        }
+    }
+}
+
+fn interface_card(iface: &InterfaceConfig, editable: bool, dns: &str) -> Markup {
+    html! {
+        section .network-interface {
+            h2 {
+                (iface.name)
+                span .network-state.-up[iface.up] { (if iface.up { "Up" } else { "Down" }) }
+            }
+            table .management-table {
+                tr { td { "MAC Address" } td { (iface.mac) } }
+                tr {
+                    td { "Addresses" }
+                    td {
+                        @if iface.addresses.is_empty() {
+                            "None"
+                        }
+                        @for address in &iface.addresses {
+                            div { (address) }
+                        }
+                    }
+                }
+                tr { td { "Gateway" } td { (iface.gateway.as_deref().unwrap_or("None")) } }
+                tr {
+                    td { "Configuration" }
+                    td {
+                        @match iface.method {
+                            Some(AddressMethod::Dhcp) => "DHCP",
+                            Some(AddressMethod::Static) => "Static",
+                            None => "Not configured in /etc/network/interfaces",
+                        }
+                    }
+                }
+            }
+            @if editable {
+                (interface_form(iface, dns))
+            }
+        }
+    }
+}
+
+fn wifi_list(networks: &Result<Vec<WifiNetwork>, String>) -> Markup {
+    html! {
+        div #wifi-networks {
+            @match networks {
+                Ok(networks) if networks.is_empty() => p { "No networks found" },
+                Ok(networks) => {
+                    table .network-wifi {
+                        tr {
+                            th { "SSID" }
+                            th { "Signal" }
+                            th { "Security" }
+                        }
+                        @for network in networks {
+                            tr .-connected[network.connected] {
+                                td { (network.ssid) @if network.connected { " (connected)" } }
+                                td { meter min="0" max="100" value=(network.signal) {} " " (network.signal) "%" }
+                                td { (if network.secured { "Secured" } else { "Open" }) }
+                            }
+                        }
+                    }
+                    // Lets the connect form suggest the networks that were found
+                    datalist #wifi-ssids {
+                        @for network in networks {
+                            option value=(network.ssid) {}
+                        }
+                    }
+                }
+                Err(err) => p .command-banner.-failure { "Failed to scan: " (err) },
+            }
+        }
+    }
+}
+
+fn wifi_section(backend: WifiBackend, admin: bool) -> Markup {
+    html! {
+        section {
+            h2 { "Wi-Fi" }
+            p {
+                "Managed by "
+                (match backend {
+                    WifiBackend::NetworkManager => "NetworkManager",
+                    WifiBackend::WpaSupplicant => "wpa_supplicant",
+                })
+            }
+            server-swap action="/network/wifi/scan" trigger="click" target="#wifi-networks" disable="#wifi-scan" {
+                button #wifi-scan { "Scan" }
+            }
+            #wifi-networks {}
+
+            @if admin {
+                h3 { "Connect" }
+                server-swap action="/network/wifi/connect" method="POST" trigger="submit" target="#wifi-result"
+                    disable="#wifi-connect" {
+                    form .management-form {
+                        input type="text" name="ssid" list="wifi-ssids" placeholder="SSID" required maxlength="32";
+                        input type="password" name="password" placeholder="Password, empty for open networks"
+                            autocomplete="off" maxlength="63";
+                        input #wifi-connect type="submit" value="Connect";
+                    }
+                }
+                #wifi-result {}
+            }
+        }
+    }
+}
+
+pub async fn page(req: ServerRequest) -> Result<ServerResponse, ServerResponse> {
+    req.check_login()?;
+
+    let data: NetworkConfigResponse = send_req!(req, NetworkConfig)?;
+    let admin = req.is_admin();
+    let dns = data.dns.join(", ");
+
+    let content = html! {
+        section {
+            h2 { "Network" }
+            table .management-table {
+                tr {
+                    td { "DNS Servers" }
+                    td { @if data.dns.is_empty() { "None" } @else { (dns) } }
+                }
+            }
+            @if !data.editable {
+                p { "Interfaces can only be edited on systems using ifupdown" }
+            }
+            #network-result {
+                @if data.rollback_pending {
+                    (pending_banner(admin))
+                }
+            }
+        }
+
+        @for iface in &data.interfaces {
+            (interface_card(iface, admin && data.editable && !data.rollback_pending, &dns))
+        }
+
+        @if let Some(backend) = data.wifi {
+            (wifi_section(backend, admin))
+        }
+    };
+
+    template(&req, content)
+}
+
+fn pending_banner(admin: bool) -> Markup {
+    html! {
+        p .command-banner {
+            "A network change is waiting to be confirmed, and will be rolled back soon otherwise."
+        }
+        @if admin {
+            server-swap action="/network/confirm" method="POST" trigger="click" target="#network-result" {
+                button { "Keep Changes" }
+            }
+        }
+    }
+}
+
+fn split_list(list: &str) -> Vec<String> {
+    list.split([',', ' '])
+        .map(str::trim)
+        .filter(|x| !x.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+pub async fn apply(mut req: ServerRequest) -> Result<ServerResponse, ServerResponse> {
+    req.check_login()?;
+    req.check_admin()?;
+
+    let form: ApplyForm = req.extract_form().await?;
+    let (addr, _) = req.extract_backends()?.current_backend;
+
+    // resolv.conf is often managed by something else, so only touch it when the servers were changed
+    let dns = split_list(&form.dns);
+    let dns = if dns == split_list(&form.current_dns) {
+        Vec::new()
+    } else {
+        dns
+    };
+
+    // If the backend connects over the interface being changed, it will come back from the new address
+    let data = send_req!(req, NetworkConfig)?;
+    let new_addr = form
+        .address
+        .split_once('/')
+        .and_then(|(x, _)| x.trim().parse::<IpAddr>().ok());
+    let reconnect_addr = match (form.method, new_addr) {
+        (AddressMethod::Static, Some(new_addr))
+            if data.interfaces.iter().any(|iface| {
+                iface.name == form.interface
+                    && iface
+                        .addresses
+                        .iter()
+                        .any(|x| x.split('/').next() == Some(&addr.to_string()))
+            }) =>
+        {
+            new_addr
+        }
+        _ => addr,
+    };
+
+    let change = NetworkChange {
+        interface: form.interface,
+        method: form.method,
+        address: form.address.trim().to_string(),
+        gateway: form.gateway.trim().to_string(),
+        dns,
+    };
+    let result = send_req!(req, ApplyNetwork(change))?;
+
+    let status = match result {
+        Ok(timeout) => {
+            let query = NetworkStatusQuery {
+                addr: reconnect_addr,
+                since: unix_now(),
+                timeout: timeout.into(),
+            };
+            network_status(&query, &req).await
+        }
+        Err(err) => html! {
+            p .command-banner.-failure { "Applying the changes failed: " (err) }
+        },
+    };
+
+    let content = html! {
+        div #network-result {
+            (status)
+        }
+    };
+
+    template(&req, content)
+}
+
+async fn network_status(query: &NetworkStatusQuery, req: &ServerRequest) -> Markup {
+    let elapsed = unix_now().saturating_sub(query.since);
+
+    let url = format!(
+        "/network/status?addr={}&since={}&timeout={}",
+        query.addr, query.since, query.timeout
+    );
+    let waiting = |message: &str| {
+        html! {
+            server-swap action=(url) trigger="delay" {
+                p .command-banner {
+                    (message) " (" (query.timeout.saturating_sub(elapsed)) "s until rollback)"
+                }
+            }
+        }
+    };
+
+    if elapsed > query.timeout {
+        return html! {
+            p .command-banner.-failure {
+                "The backend didn't reconnect in time, so the changes have been rolled back. "
+                a href="/network" { "Reload" }
+            }
+        };
+    }
+    if elapsed < CONFIRM_DELAY.as_secs() {
+        return waiting("Applying the changes");
+    }
+    let Some(handle) = req.backend_handle(query.addr) else {
+        return waiting("Waiting for the backend to reconnect");
+    };
+
+    let resp = tokio::time::timeout(
+        CONFIRM_TIMEOUT,
+        handle.send_req(RequestFrontendMessage::ConfirmNetwork),
+    )
+    .await;
+
+    match resp {
+        Ok(Ok(ResponseBackendMessage::ConfirmNetwork(Ok(())))) => html! {
+            p .command-banner.-success {
+                "The backend reconnected and the changes have been kept. "
+                a href="/network" { "Reload" }
+            }
+        },
+        Ok(Ok(ResponseBackendMessage::ConfirmNetwork(Err(err)))) => html! {
+            p .command-banner.-failure { "Confirming the changes failed: " (err) }
+        },
+        _ => waiting("Waiting for the backend to reconnect"),
+    }
+}
+
+pub async fn status(req: ServerRequest) -> Result<ServerResponse, ServerResponse> {
+    req.check_login()?;
+    req.check_admin()?;
+
+    let query: NetworkStatusQuery = req.extract_query()?;
+
+    template(&req, network_status(&query, &req).await)
+}
+
+pub async fn confirm(req: ServerRequest) -> Result<ServerResponse, ServerResponse> {
+    req.check_login()?;
+    req.check_admin()?;
+
+    let result = send_req!(req, ConfirmNetwork)?;
+
+    let content = html! {
+        div #network-result {
+            @match result {
+                Ok(()) => p .command-banner.-success { "The changes have been kept" },
+                Err(err) => p .command-banner.-failure { "Confirming the changes failed: " (err) },
+            }
+        }
+    };
+
+    template(&req, content)
+}
+
+pub async fn wifi_scan(req: ServerRequest) -> Result<ServerResponse, ServerResponse> {
+    req.check_login()?;
+
+    let networks = send_req!(req, WifiScan)?;
+
+    template(&req, wifi_list(&networks))
+}
+
+pub async fn wifi_connect(mut req: ServerRequest) -> Result<ServerResponse, ServerResponse> {
+    req.check_login()?;
+    req.check_admin()?;
+
+    let form: WifiConnectForm = req.extract_form().await?;
+
+    let action = WifiConnectAction {
+        ssid: form.ssid.clone(),
+        password: form.password,
+    };
+    let result = send_req!(req, WifiConnect(action))?;
+
+    let content = html! {
+        div #wifi-result {
+            @match result {
+                Ok(()) => p .command-banner.-success { "Connecting to " (form.ssid) },
+                Err(err) => p .command-banner.-failure { "Connecting to " (form.ssid) " failed: " (err) },
+            }
+        }
+    };
+
+    template(&req, content)
+}
@@ -0,0 +1,327 @@
+use std::{net::IpAddr, time::Duration};
+
+use futures_util::future::join_all;
+use hyper::{StatusCode, header};
+use maud::{Markup, html};
+use pretty_bytes_typed::pretty_bytes_binary;
+use proto::{
+    backend::{ResponseBackendMessage, ServiceStatus, UsageData},
+    frontend::RequestFrontendMessage,
+};
+use serde::Deserialize;
+
+use crate::{
+    backend::{BackendHandle, BackendInfo},
+    http::{
+        request::ServerRequest,
+        response::{RedirectType, ServerResponse},
+    },
+};
+
+use super::template::template;
+
+// One slow backend shouldn't hold up the whole page
+const BACKEND_TIMEOUT: Duration = Duration::from_secs(5);
+
+// Pages that can be opened for a specific backend, by path and label
+const DETAIL_PAGES: &[(&str, &str)] = &[
+    ("system", "System"),
+    ("process", "Processes"),
+    ("service", "Services"),
+    ("logs", "Logs"),
+];
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortKey {
+    #[default]
+    Name,
+    Cpu,
+    Ram,
+    Disk,
+    Temp,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+pub struct OverviewQuery {
+    filter: String,
+    sort: SortKey,
+}
+
+#[derive(Deserialize)]
+pub struct SelectQuery {
+    addr: IpAddr,
+    page: String,
+}
+
+struct BackendStats {
+    hostname: String,
+    cpu: f32,
+    ram: UsageData,
+    /// The root filesystem, or the first disk if it isn't shown
+    disk: Option<UsageData>,
+    temp: Option<f32>,
+    uptime: u64,
+    failed_services: usize,
+}
+
+struct BackendSummary {
+    addr: IpAddr,
+    info: BackendInfo,
+    stats: Result<BackendStats, String>,
+}
+
+fn percent(usage: &UsageData) -> f32 {
+    if usage.total == 0 {
+        return 0.;
+    }
+
+    usage.used as f32 / usage.total as f32 * 100.
+}
+
+async fn fetch_stats(handle: &BackendHandle) -> Result<BackendStats, String> {
+    let send = |req| async {
+        handle
+            .send_req(req)
+            .await
+            .map_err(|err| format!("request failed: {err}"))
+    };
+
+    let (cpu, mem, disk, temp, host, services) = tokio::try_join!(
+        send(RequestFrontendMessage::Cpu),
+        send(RequestFrontendMessage::Mem),
+        send(RequestFrontendMessage::Disk),
+        send(RequestFrontendMessage::Temp),
+        send(RequestFrontendMessage::Host),
+        send(RequestFrontendMessage::Services),
+    )?;
+
+    let (
+        ResponseBackendMessage::Cpu(cpu),
+        ResponseBackendMessage::Mem(mem),
+        ResponseBackendMessage::Disk(disk),
+        ResponseBackendMessage::Temp(temp),
+        ResponseBackendMessage::Host(host),
+        ResponseBackendMessage::Services(services),
+    ) = (cpu, mem, disk, temp, host, services)
+    else {
+        unreachable!()
+    };
+
+    let disk = disk
+        .disks
+        .iter()
+        .find(|disk| disk.mnt_point == "/")
+        .or(disk.disks.first())
+        .map(|disk| disk.usage.clone());
+    let failed_services = services
+        .services
+        .iter()
+        .filter(|service| matches!(service.status, ServiceStatus::Failed))
+        .count();
+
+    Ok(BackendStats {
+        hostname: host.hostname,
+        cpu: cpu.global_cpu,
+        ram: mem.ram,
+        disk,
+        temp: temp.temp,
+        uptime: host.uptime,
+        failed_services,
+    })
+}
+
+async fn summarize(addr: IpAddr, info: BackendInfo) -> BackendSummary {
+    let stats = tokio::time::timeout(BACKEND_TIMEOUT, fetch_stats(&info.handle))
+        .await
+        .unwrap_or_else(|_| Err("not responding".into()));
+
+    BackendSummary { addr, info, stats }
+}
+
+fn matches_filter(summary: &BackendSummary, filter: &str) -> bool {
+    let filter = filter.trim().to_lowercase();
+
+    filter.is_empty()
+        || summary.info.nickname.to_lowercase().contains(&filter)
+        || summary.addr.to_string().contains(&filter)
+        || summary
+            .info
+            .tags
+            .iter()
+            .any(|tag| tag.to_lowercase().contains(&filter))
+}
+
+fn sort_summaries(summaries: &mut [BackendSummary], key: SortKey) {
+    // Highest usage first, since that's what needs attention, with unresponsive backends last
+    let metric = |summary: &BackendSummary| {
+        let stats = summary.stats.as_ref().ok()?;
+
+        match key {
+            SortKey::Name => None,
+            SortKey::Cpu => Some(stats.cpu),
+            SortKey::Ram => Some(percent(&stats.ram)),
+            SortKey::Disk => stats.disk.as_ref().map(percent),
+            SortKey::Temp => stats.temp,
+        }
+    };
+
+    summaries.sort_by(|a, b| {
+        let name = || {
+            a.info
+                .nickname
+                .to_lowercase()
+                .cmp(&b.info.nickname.to_lowercase())
+        };
+
+        match key {
+            SortKey::Name => name(),
+            _ => metric(b)
+                .partial_cmp(&metric(a))
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(name),
+        }
+    });
+}
+
+fn usage_meter(label: &str, class: &str, usage: &UsageData) -> Markup {
+    let percent = percent(usage);
+
+    html! {
+        p {
+            (label) ": " (pretty_bytes_binary(usage.used, Some(0))) " / "
+            (pretty_bytes_binary(usage.total, Some(0))) " (" (format!("{percent:.0}")) "%)"
+        }
+        .meter-container {
+            .bar.(class) style={"--scale:" (percent / 100.)} {}
+        }
+    }
+}
+
+fn backend_card(summary: &BackendSummary, current: IpAddr) -> Markup {
+    let select_url = |page: &str| format!("/overview/select?addr={}&page={page}", summary.addr);
+
+    html! {
+        section .overview-card.-current[summary.addr == current] {
+            h2 {
+                (summary.info.nickname)
+                @match &summary.stats {
+                    Ok(_) => span .overview-status.-online { "Online" },
+                    Err(err) => span .overview-status title=(err) { "Unresponsive" },
+                }
+            }
+            p .overview-address { (summary.addr) }
+            @if !summary.info.tags.is_empty() {
+                p .overview-tags {
+                    @for tag in &summary.info.tags {
+                        a href={"/overview?" (serde_urlencoded::to_string([("filter", tag)]).unwrap())} { (tag) }
+                    }
+                }
+            }
+
+            @match &summary.stats {
+                Ok(stats) => {
+                    p { "Hostname: " (stats.hostname) }
+                    p { "Uptime: " (humantime::format_duration(Duration::from_secs(stats.uptime))) }
+                    p {
+                        "CPU: " (format!("{:.1}", stats.cpu)) "%"
+                        @if let Some(temp) = stats.temp {
+                            " at " (temp) "ºC"
+                        }
+                    }
+                    .meter-container {
+                        .bar.-cpu style={"--scale:" (stats.cpu / 100.)} {}
+                    }
+                    (usage_meter("RAM", "-ram", &stats.ram))
+                    @if let Some(disk) = &stats.disk {
+                        (usage_meter("Disk", "-disk", disk))
+                    }
+                    p .overview-failed[stats.failed_services > 0] {
+                        "Failed services: " (stats.failed_services)
+                    }
+                }
+                Err(err) => p .command-banner.-failure { "Failed to get statistics: " (err) },
+            }
+
+            p .overview-links {
+                @for (page, label) in DETAIL_PAGES {
+                    a href=(select_url(page)) { (label) }
+                }
+            }
+        }
+    }
+}
+
+pub async fn page(req: ServerRequest) -> Result<ServerResponse, ServerResponse> {
+    req.check_login()?;
+
+    let query: OverviewQuery = req.extract_query()?;
+    let (current, _) = req.extract_backends()?.current_backend;
+
+    let mut summaries: Vec<_> = join_all(
+        req.all_backends()
+            .into_iter()
+            .map(|(addr, info)| summarize(addr, info)),
+    )
+    .await;
+    let total = summaries.len();
+
+    summaries.retain(|summary| matches_filter(summary, &query.filter));
+    sort_summaries(&mut summaries, query.sort);
+
+    let sort_option = |key: SortKey, value: &str, label: &str| {
+        html! {
+            option value=(value) selected[query.sort == key] { (label) }
+        }
+    };
+
+    let content = html! {
+        section {
+            h2 { "Overview" }
+            form .management-form action="/overview" {
+                input type="search" name="filter" value=(query.filter) placeholder="Filter by nickname or tag";
+                label {
+                    "Sort by "
+                    select name="sort" {
+                        (sort_option(SortKey::Name, "name", "Name"))
+                        (sort_option(SortKey::Cpu, "cpu", "CPU"))
+                        (sort_option(SortKey::Ram, "ram", "RAM"))
+                        (sort_option(SortKey::Disk, "disk", "Disk"))
+                        (sort_option(SortKey::Temp, "temp", "Temperature"))
+                    }
+                }
+                input type="submit" value="Apply";
+            }
+            p { "Showing " (summaries.len()) " of " (total) " connected backends" }
+        }
+
+        .overview-grid {
+            @for summary in &summaries {
+                (backend_card(summary, current))
+            }
+        }
+    };
+
+    template(&req, content)
+}
+
+pub async fn select(req: ServerRequest) -> Result<ServerResponse, ServerResponse> {
+    req.check_login()?;
+
+    let query: SelectQuery = req.extract_query()?;
+
+    // Only redirect to known pages, so this can't be used to send someone elsewhere
+    if !DETAIL_PAGES.iter().any(|(page, _)| *page == query.page) {
+        return Err(ServerResponse::new()
+            .status(StatusCode::BAD_REQUEST)
+            .body("unknown page"));
+    }
+
+    Ok(ServerResponse::new()
+        .redirect(RedirectType::SeeOther, &format!("/{}", query.page))
+        .header(
+            header::SET_COOKIE,
+            format!("backend={}; Max-Age=999999999; Path=/", query.addr),
+        ))
+}
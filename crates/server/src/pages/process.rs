@@ -1,20 +1,46 @@
+use std::{
+    collections::{HashMap, HashSet},
+    time::{Duration, UNIX_EPOCH},
+};
+
 use maud::{Markup, html};
 use pretty_bytes_typed::pretty_bytes_binary;
 use proto::{
-    backend::ProcessStatus,
-    frontend::{ActionFrontendMessage, SignalAction},
+    backend::{
+        CommandResponse, CommandStatus, ProcessInfo, ProcessStatus, SignalResult, SocketProtocol,
+    },
+    frontend::{
+        AffinityAction, IoNiceAction, ProcessListQuery, ProcessSort, ReniceAction, SignalAction,
+    },
 };
 use serde::{Deserialize, Serialize};
 
 use crate::http::{request::ServerRequest, response::ServerResponse};
 
-use super::template::{Icon, send_req, template};
+use super::template::{Icon, send_req, template, toast};
+
+const PAGE_SIZE: usize = 100;
 
-#[derive(Default, Deserialize, Serialize)]
+#[derive(Default, Deserialize, Serialize, Clone)]
 #[serde(default)]
 pub struct ProcessQuery {
     sort: ColumnSort,
     reverse: bool,
+    filter: String,
+    tree: bool,
+    kernel: bool,
+    page: usize,
+}
+
+#[derive(Deserialize)]
+pub struct DetailQuery {
+    pid: u32,
+}
+
+#[derive(Deserialize)]
+pub struct AffinityForm {
+    pid: u32,
+    cpus: String,
 }
 
 #[derive(Deserialize, Serialize, PartialEq, Eq, Clone, Copy, Default)]
@@ -28,6 +54,28 @@ pub enum ColumnSort {
     Ram,
 }
 
+impl ColumnSort {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Pid => "pid",
+            Self::Name => "name",
+            Self::Status => "status",
+            Self::Cpu => "cpu",
+            Self::Ram => "ram",
+        }
+    }
+
+    fn to_proto(self) -> ProcessSort {
+        match self {
+            Self::Pid => ProcessSort::Pid,
+            Self::Name => ProcessSort::Name,
+            Self::Status => ProcessSort::Status,
+            Self::Cpu => ProcessSort::Cpu,
+            Self::Ram => ProcessSort::Ram,
+        }
+    }
+}
+
 fn table_header(name: &str, sort: ColumnSort, query: &ProcessQuery) -> Markup {
     let reverse = if query.sort == sort {
         !query.reverse
@@ -35,7 +83,12 @@ fn table_header(name: &str, sort: ColumnSort, query: &ProcessQuery) -> Markup {
         false
     };
 
-    let new_query = ProcessQuery { sort, reverse };
+    let new_query = ProcessQuery {
+        sort,
+        reverse,
+        page: 0,
+        ..query.clone()
+    };
     let new_query = serde_urlencoded::to_string(&new_query).unwrap();
 
     html! {
@@ -56,62 +109,134 @@ fn table_header(name: &str, sort: ColumnSort, query: &ProcessQuery) -> Markup {
     }
 }
 
+fn page_button(label: &str, page: usize, query: &ProcessQuery) -> Markup {
+    let new_query = ProcessQuery {
+        page,
+        ..query.clone()
+    };
+    let new_query = serde_urlencoded::to_string(&new_query).unwrap();
+
+    html! {
+        server-swap action={"/process?" (new_query)} target="#process-swap" {
+            button { (label) }
+        }
+    }
+}
+
+// Orders processes depth-first, so children come right after their parent.
+// Processes whose parent was filtered out are treated as roots.
+fn tree_order(processes: Vec<ProcessInfo>) -> Vec<(ProcessInfo, usize)> {
+    let pids: HashSet<u32> = processes.iter().map(|x| x.pid).collect();
+
+    let mut roots = Vec::new();
+    let mut children: HashMap<u32, Vec<ProcessInfo>> = HashMap::new();
+    for proc in processes {
+        match proc.parent.filter(|x| pids.contains(x)) {
+            Some(parent) => children.entry(parent).or_default().push(proc),
+            None => roots.push(proc),
+        }
+    }
+
+    let mut ordered = Vec::new();
+    let mut stack: Vec<(ProcessInfo, usize)> = roots.into_iter().rev().map(|x| (x, 0)).collect();
+    while let Some((proc, depth)) = stack.pop() {
+        if let Some(procs) = children.remove(&proc.pid) {
+            stack.extend(procs.into_iter().rev().map(|x| (x, depth + 1)));
+        }
+        ordered.push((proc, depth));
+    }
+
+    ordered
+}
+
 pub async fn page(req: ServerRequest) -> Result<ServerResponse, ServerResponse> {
     req.check_login()?;
 
     let query: ProcessQuery = req.extract_query()?;
 
-    let mut processes = send_req!(req, Processes)?.processes;
-    match query.sort {
-        ColumnSort::Pid => processes.sort_by_key(|a| a.pid),
-        ColumnSort::Name => processes.sort_by(|a, b| a.name.cmp(&b.name)),
-        ColumnSort::Status => processes.sort_by_key(|a| a.status),
-        ColumnSort::Cpu => processes.sort_by(|a, b| a.cpu.total_cmp(&b.cpu)),
-        ColumnSort::Ram => processes.sort_by_key(|a| a.mem),
-    }
-    if query.reverse {
-        processes.reverse();
-    }
+    let list_query = ProcessListQuery {
+        filter: query.filter.clone(),
+        kernel: query.kernel,
+        sort: query.sort.to_proto(),
+        reverse: query.reverse,
+    };
+    let data = send_req!(req, Processes(list_query))?;
+    let processes = data.processes;
+
+    // Sorting still applies between siblings in tree mode
+    let processes = if query.tree {
+        tree_order(processes)
+    } else {
+        processes.into_iter().map(|x| (x, 0)).collect()
+    };
+
+    let received = processes.len();
+    let page_count = received.div_ceil(PAGE_SIZE).max(1);
+    let page = query.page.min(page_count - 1);
 
     let query_str = serde_urlencoded::to_string(&query).unwrap();
 
     let content = html! {
-        server-swap #process-swap action={"/process?" (query_str)} trigger="delay" {
+        // Refreshes only replace the table, so leave out everything above it
+        @if !req.is_fixi() {
             section {
                 h2 { "Processes" }
 
+                form .process-filter action="/process" {
+                    input type="hidden" name="sort" value=(query.sort.as_str());
+                    input type="hidden" name="reverse" value=(query.reverse);
+                    input type="search" name="filter" placeholder="Filter by name, command or user" value=(query.filter);
+                    label {
+                        input type="checkbox" name="tree" value="true" checked[query.tree];
+                        " Tree view"
+                    }
+                    label {
+                        input type="checkbox" name="kernel" value="true" checked[query.kernel];
+                        " Show kernel threads"
+                    }
+                    input type="submit" value="Apply";
+                }
+            }
+        }
+
+        server-swap #process-swap action={"/process?" (query_str)} trigger="delay" {
+            section {
                 table .process-table {
                     tr {
                         (table_header("PID", ColumnSort::Pid, &query))
                         (table_header("Name", ColumnSort::Name, &query))
+                        th { "User" }
                         (table_header("Status", ColumnSort::Status, &query))
                         (table_header("CPU Usage", ColumnSort::Cpu, &query))
                         (table_header("RAM Usage", ColumnSort::Ram, &query))
                         th { "Actions" }
                     }
-                    @for proc in processes {
+                    @for (proc, depth) in processes.into_iter().skip(page * PAGE_SIZE).take(PAGE_SIZE) {
                         @let pretty_mem = pretty_bytes_binary(proc.mem, Some(0));
 
                         tr {
-                            td { (proc.pid) }
-                            td { (proc.name) }
+                            td { a href={"/process/detail?pid=" (proc.pid)} { (proc.pid) } }
+                            td .process-name style={"--depth: " (depth)} title=(proc.cmdline) {
+                                a href={"/process/detail?pid=" (proc.pid)} { (proc.name) }
+                            }
+                            td { (proc.user) }
                             td { (format!("{:?}", proc.status)) }
                             td { (proc.cpu) "%" }
                             td { (pretty_mem) }
                             td {
                                 .actions-cell {
-                                    server-swap action={"/process/signal?signal=kill&pid=" (proc.pid) } target="none" {
+                                    server-swap action={"/process/signal?signal=kill&pid=" (proc.pid) } target="#toast" {
                                         button { (Icon::new("fa6-solid-skull")) }
                                     }
-                                    server-swap action={"/process/signal?signal=term&pid=" (proc.pid) } target="none" {
+                                    server-swap action={"/process/signal?signal=term&pid=" (proc.pid) } target="#toast" {
                                         button { (Icon::new("fa6-solid-ban")) }
                                     }
                                     @if proc.status == ProcessStatus::Paused {
-                                        server-swap action={"/process/signal?signal=resume&pid=" (proc.pid) } target="none" {
+                                        server-swap action={"/process/signal?signal=resume&pid=" (proc.pid) } target="#toast" {
                                             button { (Icon::new("fa6-solid-play")) }
                                         }
                                     } @else {
-                                        server-swap action={"/process/signal?signal=pause&pid=" (proc.pid) } target="none" {
+                                        server-swap action={"/process/signal?signal=pause&pid=" (proc.pid) } target="#toast" {
                                             button { (Icon::new("fa6-solid-pause")) }
                                         }
                                     }
@@ -120,6 +245,192 @@ pub async fn page(req: ServerRequest) -> Result<ServerResponse, ServerResponse>
                         }
                     }
                 }
+
+                .pagination {
+                    @if page > 0 {
+                        (page_button("Previous", page - 1, &query))
+                    }
+                    span { "Page " (page + 1) " of " (page_count) " (" (data.total) " processes)" }
+                    @if page + 1 < page_count {
+                        (page_button("Next", page + 1, &query))
+                    }
+                }
+                @if (received as u32) < data.total {
+                    p { "Only the first " (received) " are listed, filter or sort them to see the rest" }
+                }
+            }
+        }
+    };
+
+    template(&req, content)
+}
+
+pub async fn detail(req: ServerRequest) -> Result<ServerResponse, ServerResponse> {
+    req.check_login()?;
+
+    let query: DetailQuery = req.extract_query()?;
+
+    let Some(proc) = send_req!(req, ProcessDetail(query.pid))? else {
+        let content = html! {
+            section {
+                h2 { "Process " (query.pid) }
+                p { "This process no longer exists. " a href="/process" { "Back to processes" } }
+            }
+        };
+
+        return template(&req, content);
+    };
+
+    let start_time =
+        humantime::format_rfc3339_seconds(UNIX_EPOCH + Duration::from_secs(proc.start_time));
+    let unknown = || "unknown".to_string();
+
+    let content = html! {
+        section {
+            h2 { (proc.name) " (" (proc.pid) ")" }
+
+            table .process-detail {
+                tr { th { "Command line" } td { code { (proc.cmdline.join(" ")) } } }
+                tr { th { "Executable" } td { (proc.exe.unwrap_or_else(unknown)) } }
+                tr { th { "User" } td { (proc.user.unwrap_or_else(unknown)) } }
+                tr { th { "Group" } td { (proc.group.unwrap_or_else(unknown)) } }
+                tr {
+                    th { "Parent PID" }
+                    td {
+                        @if let Some(parent) = proc.parent {
+                            a href={"/process/detail?pid=" (parent)} { (parent) }
+                        } @else {
+                            "none"
+                        }
+                    }
+                }
+                tr { th { "Started" } td { (start_time) } }
+                tr { th { "Status" } td { (format!("{:?}", proc.status)) } }
+                tr { th { "Threads" } td { (proc.threads) } }
+                tr { th { "Nice" } td { (proc.nice) } }
+                tr { th { "CPU Affinity" } td { (proc.cpu_affinity) } }
+                tr { th { "CPU Usage" } td { (proc.cpu) "%" } }
+                tr { th { "RAM Usage" } td { (pretty_bytes_binary(proc.mem, Some(0))) } }
+                tr { th { "Disk Read" } td { (pretty_bytes_binary(proc.disk_read, Some(0))) } }
+                tr { th { "Disk Written" } td { (pretty_bytes_binary(proc.disk_written, Some(0))) } }
+            }
+        }
+
+        section {
+            h2 { "Priority" }
+
+            .priority-forms {
+                server-swap action="/process/renice" method="POST" trigger="submit" target="#priority-result" {
+                    form {
+                        input type="hidden" name="pid" value=(proc.pid);
+                        label {
+                            "Nice value "
+                            input type="number" name="nice" min="-20" max="19" value=(proc.nice);
+                        }
+                        input type="submit" value="Renice";
+                    }
+                }
+
+                server-swap action="/process/ionice" method="POST" trigger="submit" target="#priority-result" {
+                    form {
+                        input type="hidden" name="pid" value=(proc.pid);
+                        label {
+                            "I/O class "
+                            select name="class" {
+                                option value="realtime" { "Realtime" }
+                                option value="besteffort" selected { "Best effort" }
+                                option value="idle" { "Idle" }
+                            }
+                        }
+                        label {
+                            "Level "
+                            input type="number" name="level" min="0" max="7" value="4";
+                        }
+                        input type="submit" value="Set I/O priority";
+                    }
+                }
+
+                server-swap action="/process/affinity" method="POST" trigger="submit" target="#priority-result" {
+                    form {
+                        input type="hidden" name="pid" value=(proc.pid);
+                        label {
+                            "CPUs "
+                            input name="cpus" placeholder="e.g. 0-1,3" value=(proc.cpu_affinity);
+                        }
+                        input type="submit" value="Set affinity";
+                    }
+                }
+            }
+
+            div #priority-result {}
+        }
+
+        section {
+            h2 { "Send Signal" }
+
+            server-swap action="/process/signal" trigger="submit" target="#toast" {
+                form .signal-form {
+                    input type="hidden" name="pid" value=(proc.pid);
+                    select name="signal" {
+                        option value="term" { "SIGTERM (terminate)" }
+                        option value="kill" { "SIGKILL (kill)" }
+                        option value="hup" { "SIGHUP (hang up / reload)" }
+                        option value="int" { "SIGINT (interrupt)" }
+                        option value="usr1" { "SIGUSR1" }
+                        option value="usr2" { "SIGUSR2" }
+                        option value="pause" { "SIGSTOP (pause)" }
+                        option value="resume" { "SIGCONT (resume)" }
+                    }
+                    input type="submit" value="Send";
+                }
+            }
+        }
+
+        section {
+            h2 { "Listening Sockets" }
+            @if proc.listening.is_empty() {
+                p { "This process isn't listening on any sockets" }
+            } @else {
+                ul {
+                    @for socket in &proc.listening {
+                        li {
+                            @match socket.protocol {
+                                SocketProtocol::Tcp => "TCP ",
+                                SocketProtocol::Udp => "UDP ",
+                            }
+                            code { (socket.address) }
+                        }
+                    }
+                }
+            }
+        }
+
+        section {
+            h2 { "Open Files" }
+            details {
+                summary { (proc.open_files.len()) " file descriptors" }
+                table .process-detail {
+                    @for file in &proc.open_files {
+                        tr { th { (file.fd) } td { (file.target) } }
+                    }
+                }
+                @if proc.open_files_truncated {
+                    p { "Some file descriptors were left out" }
+                }
+            }
+        }
+
+        @if req.is_admin() {
+            section {
+                h2 { "Environment" }
+                details {
+                    summary { (proc.environment.len()) " variables" }
+                    pre {
+                        @for var in &proc.environment {
+                            (var) "\n"
+                        }
+                    }
+                }
             }
         }
     };
@@ -127,13 +438,148 @@ pub async fn page(req: ServerRequest) -> Result<ServerResponse, ServerResponse>
     template(&req, content)
 }
 
+// Parses a CPU list like "0-2,4" into individual CPUs, which all have to exist
+fn parse_cpu_list(list: &str, cpu_count: u32) -> Option<Vec<u32>> {
+    let mut cpus = Vec::new();
+
+    for part in list.split(',').map(str::trim).filter(|x| !x.is_empty()) {
+        match part.split_once('-') {
+            Some((start, end)) => {
+                let (start, end): (u32, u32) =
+                    (start.trim().parse().ok()?, end.trim().parse().ok()?);
+                if start > end || end >= cpu_count {
+                    return None;
+                }
+                cpus.extend(start..=end);
+            }
+            None => {
+                let cpu = part.parse().ok()?;
+                if cpu >= cpu_count {
+                    return None;
+                }
+                cpus.push(cpu);
+            }
+        }
+    }
+
+    Some(cpus)
+}
+
+fn priority_result(label: &str, resp: &CommandResponse) -> Markup {
+    html! {
+        div #priority-result {
+            @match resp {
+                CommandResponse::Finished(output) if output.status == CommandStatus::Exited(0) => {
+                    p .command-banner.-success { (label) " succeeded" }
+                }
+                CommandResponse::Finished(output) => {
+                    p .command-banner.-failure {
+                        (label) " failed: " (String::from_utf8_lossy(&output.stderr).trim())
+                    }
+                }
+                CommandResponse::SpawnFailed(err) => {
+                    p .command-banner.-failure { (label) " failed: " (err) }
+                }
+                CommandResponse::Denied => {
+                    p .command-banner.-failure { (label) " failed: value out of range" }
+                }
+            }
+        }
+    }
+}
+
+pub async fn renice(mut req: ServerRequest) -> Result<ServerResponse, ServerResponse> {
+    req.check_login()?;
+    req.check_admin()?;
+
+    let action: ReniceAction = req.extract_form().await?;
+    let resp = send_req!(req, Renice(action))?;
+
+    template(&req, priority_result("Renice", &resp))
+}
+
+pub async fn ionice(mut req: ServerRequest) -> Result<ServerResponse, ServerResponse> {
+    req.check_login()?;
+    req.check_admin()?;
+
+    let action: IoNiceAction = req.extract_form().await?;
+    let resp = send_req!(req, IoNice(action))?;
+
+    template(&req, priority_result("Setting I/O priority", &resp))
+}
+
+pub async fn affinity(mut req: ServerRequest) -> Result<ServerResponse, ServerResponse> {
+    req.check_login()?;
+    req.check_admin()?;
+
+    let form: AffinityForm = req.extract_form().await?;
+    let cpu_count = send_req!(req, Cpu)?.cpus.len() as u32;
+
+    let Some(cpus) = parse_cpu_list(&form.cpus, cpu_count) else {
+        let content = html! {
+            div #priority-result {
+                p .command-banner.-failure { "Invalid CPU list: " (form.cpus) }
+            }
+        };
+        return template(&req, content);
+    };
+
+    let action = AffinityAction {
+        pid: form.pid,
+        cpus,
+    };
+    let resp = send_req!(req, Affinity(action))?;
+
+    template(&req, priority_result("Setting CPU affinity", &resp))
+}
+
 pub async fn signal(req: ServerRequest) -> Result<ServerResponse, ServerResponse> {
     req.check_login()?;
+    req.check_admin()?;
 
     let signal: SignalAction = req.extract_query()?;
+    let pid = signal.pid;
 
-    req.send_backend_action(ActionFrontendMessage::Signal(signal))
-        .await?;
+    let content = match send_req!(req, Signal(signal))? {
+        SignalResult::Sent => toast(true, html! { "Sent signal to process " (pid) }),
+        SignalResult::NoSuchProcess => toast(false, html! { "Process " (pid) " no longer exists" }),
+        SignalResult::PermissionDenied => toast(
+            false,
+            html! { "Permission denied sending signal to process " (pid) },
+        ),
+        SignalResult::Unsupported => {
+            toast(false, html! { "Signal is not supported on this system" })
+        }
+        SignalResult::Failed(err) => toast(false, html! { "Failed to send signal: " (err) }),
+    };
+
+    template(&req, content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    Ok(ServerResponse::new())
+    #[test]
+    fn parses_cpu_lists() {
+        assert_eq!(parse_cpu_list("0", 4), Some(vec![0]));
+        assert_eq!(parse_cpu_list("0-2,3", 4), Some(vec![0, 1, 2, 3]));
+        assert_eq!(parse_cpu_list(" 1 - 2 , ,3", 4), Some(vec![1, 2, 3]));
+        assert_eq!(parse_cpu_list("", 4), Some(vec![]));
+    }
+
+    #[test]
+    fn rejects_invalid_cpu_lists() {
+        assert_eq!(parse_cpu_list("2-1", 4), None);
+        assert_eq!(parse_cpu_list("a", 4), None);
+        assert_eq!(parse_cpu_list("-1", 4), None);
+        assert_eq!(parse_cpu_list("1-", 4), None);
+    }
+
+    #[test]
+    fn rejects_missing_cpus() {
+        assert_eq!(parse_cpu_list("4", 4), None);
+        assert_eq!(parse_cpu_list("0-4", 4), None);
+        assert_eq!(parse_cpu_list("0-4294967295", 4), None);
+    }
 }
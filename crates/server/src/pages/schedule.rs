@@ -0,0 +1,329 @@
+use maud::{Markup, html};
+use proto::{
+    backend::{CommandResponse, CommandStatus, CronEntry, CronResponse, CronSource},
+    frontend::{CronAction, CronOperation, ServiceAction, ServiceOperation},
+};
+use serde::Deserialize;
+
+use crate::http::{request::ServerRequest, response::ServerResponse};
+
+use super::template::{send_req, template};
+
+#[derive(Deserialize)]
+pub struct CronAddForm {
+    user: String,
+    schedule: String,
+    command: String,
+}
+
+#[derive(Deserialize)]
+pub struct CronEditForm {
+    user: String,
+    line: u32,
+    original: String,
+    schedule: String,
+    command: String,
+}
+
+#[derive(Deserialize)]
+pub struct CronToggleForm {
+    user: String,
+    line: u32,
+    original: String,
+    enabled: bool,
+}
+
+#[derive(Deserialize)]
+pub struct CronDeleteForm {
+    user: String,
+    line: u32,
+    original: String,
+}
+
+#[derive(Deserialize)]
+pub struct RunForm {
+    unit: String,
+}
+
+fn line_fields(entry: &CronEntry) -> Markup {
+    html! {
+        input type="hidden" name="user" value=(entry.user);
+        input type="hidden" name="line" value=(entry.line);
+        input type="hidden" name="original" value=(entry.raw);
+    }
+}
+
+fn cron_actions(entry: &CronEntry) -> Markup {
+    html! {
+        .cron-actions {
+            server-swap action="/schedule/cron/toggle" method="POST" trigger="submit" target="#cron-section" {
+                form {
+                    (line_fields(entry))
+                    input type="hidden" name="enabled" value=(!entry.enabled);
+                    input type="submit" value=(if entry.enabled { "Disable" } else { "Enable" });
+                }
+            }
+            server-swap action="/schedule/cron/delete" method="POST" trigger="submit" target="#cron-section"
+                confirm="Delete this entry?" {
+                form {
+                    (line_fields(entry))
+                    input type="submit" value="Delete";
+                }
+            }
+        }
+        details {
+            summary { "Edit" }
+            server-swap action="/schedule/cron/edit" method="POST" trigger="submit" target="#cron-section" {
+                form .cron-form {
+                    (line_fields(entry))
+                    input type="text" name="schedule" value=(entry.schedule) required;
+                    input type="text" name="command" value=(entry.command) required;
+                    input type="submit" value="Save";
+                }
+            }
+        }
+    }
+}
+
+fn cron_section(data: &CronResponse, admin: bool, banner: Markup) -> Markup {
+    html! {
+        section #cron-section {
+            h2 { "Cron Jobs" }
+            (banner)
+            @if data.entries.is_empty() {
+                p { "No cron jobs found" }
+            } @else {
+                table .cron-table {
+                    tr {
+                        th { "User" }
+                        th { "Schedule" }
+                        th { "Command" }
+                        th { "Source" }
+                        th { "Status" }
+                        @if admin {
+                            th { "Actions" }
+                        }
+                    }
+                    @for entry in &data.entries {
+                        tr .-disabled[!entry.enabled] {
+                            td { (entry.user) }
+                            td .cron-schedule { (entry.schedule) }
+                            td .cron-command { (entry.command) }
+                            td {
+                                @match &entry.source {
+                                    CronSource::User => "crontab",
+                                    CronSource::System(path) => (path),
+                                }
+                            }
+                            td { (if entry.enabled { "Enabled" } else { "Disabled" }) }
+                            @if admin {
+                                td {
+                                    // System crontabs are managed by packages, so they aren't editable here
+                                    @if entry.source == CronSource::User {
+                                        (cron_actions(entry))
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+
+            @if admin {
+                h3 { "Add Cron Job" }
+                server-swap action="/schedule/cron/add" method="POST" trigger="submit" target="#cron-section" {
+                    form .cron-form {
+                        input type="text" name="user" value="root" required size="10";
+                        input type="text" name="schedule" placeholder="*/5 * * * * or @daily" required;
+                        input type="text" name="command" placeholder="Command" required;
+                        input type="submit" value="Add";
+                    }
+                }
+            }
+        }
+    }
+}
+
+pub async fn page(req: ServerRequest) -> Result<ServerResponse, ServerResponse> {
+    req.check_login()?;
+
+    let crontabs = send_req!(req, Crontabs)?;
+    let timers = send_req!(req, Timers)?;
+    let admin = req.is_admin();
+
+    let content = html! {
+        (cron_section(&crontabs, admin, html! {}))
+
+        section {
+            h2 { "Systemd Timers" }
+            #timer-result {}
+            @match timers {
+                Ok(timers) if timers.is_empty() => p { "No timers found" },
+                Ok(timers) => {
+                    table .cron-table {
+                        tr {
+                            th { "Name" }
+                            th { "Description" }
+                            th { "Unit" }
+                            th { "Next Run" }
+                            th { "Last Run" }
+                            th { "Actions" }
+                        }
+                        @for timer in timers {
+                            tr .-disabled[!timer.active] {
+                                td { (timer.name) }
+                                td { (timer.description) }
+                                td { (timer.unit) }
+                                td { @if timer.next.is_empty() { "-" } @else { (timer.next) } }
+                                td { @if timer.last.is_empty() { "Never" } @else { (timer.last) } }
+                                td {
+                                    @if admin {
+                                        server-swap action="/schedule/run" method="POST" trigger="submit" target="#timer-result" {
+                                            form {
+                                                input type="hidden" name="unit" value=(timer.unit);
+                                                input type="submit" value="Run Now";
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+                Err(err) => p .command-banner.-failure { "Failed to list timers: " (err) },
+            }
+        }
+    };
+
+    template(&req, content)
+}
+
+async fn cron_action(
+    req: &ServerRequest,
+    label: &str,
+    action: CronAction,
+) -> Result<ServerResponse, ServerResponse> {
+    let result = send_req!(req, CronAction(action))?;
+    let crontabs = send_req!(req, Crontabs)?;
+
+    let banner = html! {
+        @match result {
+            Ok(()) => p .command-banner.-success { (label) " succeeded" },
+            Err(err) => p .command-banner.-failure { (label) " failed: " (err) },
+        }
+    };
+
+    template(req, cron_section(&crontabs, true, banner))
+}
+
+pub async fn add(mut req: ServerRequest) -> Result<ServerResponse, ServerResponse> {
+    req.check_login()?;
+    req.check_admin()?;
+
+    let form: CronAddForm = req.extract_form().await?;
+
+    let action = CronAction {
+        user: form.user.trim().to_string(),
+        operation: CronOperation::Add {
+            schedule: form.schedule,
+            command: form.command,
+        },
+    };
+
+    cron_action(&req, "Adding the cron job", action).await
+}
+
+pub async fn edit(mut req: ServerRequest) -> Result<ServerResponse, ServerResponse> {
+    req.check_login()?;
+    req.check_admin()?;
+
+    let form: CronEditForm = req.extract_form().await?;
+
+    let action = CronAction {
+        user: form.user,
+        operation: CronOperation::Edit {
+            line: form.line,
+            original: form.original,
+            schedule: form.schedule,
+            command: form.command,
+        },
+    };
+
+    cron_action(&req, "Editing the cron job", action).await
+}
+
+pub async fn toggle(mut req: ServerRequest) -> Result<ServerResponse, ServerResponse> {
+    req.check_login()?;
+    req.check_admin()?;
+
+    let form: CronToggleForm = req.extract_form().await?;
+
+    let label = if form.enabled {
+        "Enabling the cron job"
+    } else {
+        "Disabling the cron job"
+    };
+    let action = CronAction {
+        user: form.user,
+        operation: CronOperation::SetEnabled {
+            line: form.line,
+            original: form.original,
+            enabled: form.enabled,
+        },
+    };
+
+    cron_action(&req, label, action).await
+}
+
+pub async fn delete(mut req: ServerRequest) -> Result<ServerResponse, ServerResponse> {
+    req.check_login()?;
+    req.check_admin()?;
+
+    let form: CronDeleteForm = req.extract_form().await?;
+
+    let action = CronAction {
+        user: form.user,
+        operation: CronOperation::Delete {
+            line: form.line,
+            original: form.original,
+        },
+    };
+
+    cron_action(&req, "Deleting the cron job", action).await
+}
+
+pub async fn run(mut req: ServerRequest) -> Result<ServerResponse, ServerResponse> {
+    req.check_login()?;
+    req.check_admin()?;
+
+    let form: RunForm = req.extract_form().await?;
+
+    let action = ServiceAction {
+        name: form.unit.clone(),
+        operation: ServiceOperation::Start,
+    };
+    let resp = send_req!(req, ServiceAction(action))?;
+
+    let content = html! {
+        div #timer-result {
+            @match resp {
+                CommandResponse::Finished(output) if output.status == CommandStatus::Exited(0) => {
+                    p .command-banner.-success { "Started " (form.unit) }
+                }
+                CommandResponse::Finished(output) => {
+                    p .command-banner.-failure {
+                        "Failed to start " (form.unit) ": " (String::from_utf8_lossy(&output.stderr).trim())
+                    }
+                }
+                CommandResponse::SpawnFailed(err) => {
+                    p .command-banner.-failure { "Failed to start " (form.unit) ": " (err) }
+                }
+                CommandResponse::Denied => {
+                    p .command-banner.-failure { "Failed to start " (form.unit) ": invalid unit name" }
+                }
+            }
+        }
+    };
+
+    template(&req, content)
+}
@@ -1,48 +1,170 @@
-use maud::html;
-use proto::backend::ServiceStatus;
+use std::time::Duration;
+
+use maud::{Markup, html};
+use pretty_bytes_typed::pretty_bytes_binary;
+use proto::{
+    backend::{CommandResponse, CommandStatus, ServiceInfo, ServiceStatus},
+    frontend::{ServiceAction, ServiceOperation},
+};
 
 use crate::http::{request::ServerRequest, response::ServerResponse};
 
 use super::template::{send_req, template};
 
+// Stopping or disabling these could lock users out of the system
+const CRITICAL_SERVICES: &[&str] = &["ssh", "sshd", "dropbear", "dietpi-dashboard", "networking"];
+
+fn action_button(service: &ServiceInfo, operation: ServiceOperation) -> Markup {
+    let (op_name, label) = match operation {
+        ServiceOperation::Start => ("start", "Start"),
+        ServiceOperation::Stop => ("stop", "Stop"),
+        ServiceOperation::Restart => ("restart", "Restart"),
+        ServiceOperation::Enable => ("enable", "Enable"),
+        ServiceOperation::Disable => ("disable", "Disable"),
+    };
+
+    let is_critical = CRITICAL_SERVICES.contains(&service.name.as_str());
+    let needs_confirm = is_critical
+        && matches!(
+            operation,
+            ServiceOperation::Stop | ServiceOperation::Disable
+        );
+    let confirm_msg = format!(
+        "{} is a critical service, are you sure you want to {op_name} it?",
+        service.name
+    );
+
+    html! {
+        server-swap
+            action={"/service/action?operation=" (op_name) "&name=" (service.name)}
+            method="POST"
+            target={"tr[data-service='" (service.name) "']"}
+            confirm=[needs_confirm.then_some(confirm_msg)]
+        {
+            button { (label) }
+        }
+    }
+}
+
+fn action_result(operation: ServiceOperation, resp: &CommandResponse) -> Markup {
+    let op_name = match operation {
+        ServiceOperation::Start => "Start",
+        ServiceOperation::Stop => "Stop",
+        ServiceOperation::Restart => "Restart",
+        ServiceOperation::Enable => "Enable",
+        ServiceOperation::Disable => "Disable",
+    };
+
+    html! {
+        @match resp {
+            CommandResponse::Finished(output) if output.status == CommandStatus::Exited(0) => {
+                p .command-banner.-success { (op_name) " succeeded" }
+            }
+            CommandResponse::Finished(output) => {
+                details .command-banner.-failure {
+                    summary { (op_name) " failed" }
+                    pre { (String::from_utf8_lossy(&output.stderr)) }
+                }
+            }
+            CommandResponse::SpawnFailed(err) => {
+                p .command-banner.-failure { (op_name) " failed: " (err) }
+            }
+            CommandResponse::Denied => {
+                p .command-banner.-failure { (op_name) " denied: invalid service name" }
+            }
+        }
+    }
+}
+
+fn service_row(service: &ServiceInfo, admin: bool, result: Option<Markup>) -> Markup {
+    html! {
+        tr data-service=(service.name) {
+            td title=(service.description) { (service.name) }
+            td {
+                @match service.status {
+                    ServiceStatus::Active => "active",
+                    ServiceStatus::Inactive => "inactive",
+                    ServiceStatus::Failed => "failed",
+                    ServiceStatus::Unknown => "unknown"
+                }
+                @if !service.sub_state.is_empty() {
+                    " (" (service.sub_state) ")"
+                }
+            }
+            td {
+                @if let Some(pid) = service.main_pid {
+                    (pid)
+                }
+            }
+            td {
+                @if let Some(memory) = service.memory {
+                    (pretty_bytes_binary(memory, Some(0)))
+                }
+            }
+            td {
+                @if let Some(cpu_time) = service.cpu_time {
+                    // Round to milliseconds, anything more precise is just noise
+                    (humantime::format_duration(Duration::from_millis(cpu_time / 1_000_000)))
+                }
+            }
+            td {
+                @if !service.err_log.is_empty() {
+                    details {
+                        summary { "View log" }
+                        pre {
+                            (service.err_log)
+                        }
+                    }
+                }
+            }
+            td { (service.start) }
+            td {
+                @if admin {
+                    .actions-cell {
+                        @match service.status {
+                            ServiceStatus::Active => {
+                                (action_button(service, ServiceOperation::Stop))
+                                (action_button(service, ServiceOperation::Restart))
+                            }
+                            _ => {
+                                (action_button(service, ServiceOperation::Start))
+                            }
+                        }
+                        (action_button(service, ServiceOperation::Enable))
+                        (action_button(service, ServiceOperation::Disable))
+                    }
+                }
+                a href={"/logs?unit=" (service.name)} { "View logs" }
+                @if let Some(result) = result {
+                    (result)
+                }
+            }
+        }
+    }
+}
+
 pub async fn page(req: ServerRequest) -> Result<ServerResponse, ServerResponse> {
     req.check_login()?;
 
     let data = send_req!(req, Services)?;
+    let admin = req.is_admin();
 
     let content = html! {
         section {
             h2 { "Services" }
-            table {
+            table .service-table {
                 tr {
                     th { "Name" }
                     th { "Status" }
+                    th { "Main PID" }
+                    th { "Memory" }
+                    th { "CPU Time" }
                     th { "Error Log" }
                     th { "Start Time" }
+                    th { "Actions" }
                 }
-                @for service in data.services {
-                    tr {
-                        td { (service.name) }
-                        td {
-                            @match service.status {
-                                ServiceStatus::Active => "active",
-                                ServiceStatus::Inactive => "inactive",
-                                ServiceStatus::Failed => "failed",
-                                ServiceStatus::Unknown => "unknown"
-                            }
-                        }
-                        td {
-                            @if !service.err_log.is_empty() {
-                                details {
-                                    summary { "View log" }
-                                    pre {
-                                        (service.err_log)
-                                    }
-                                }
-                            }
-                        }
-                        td { (service.start) }
-                    }
+                @for service in &data.services {
+                    (service_row(service, admin, None))
                 }
             }
         }
@@ -50,3 +172,36 @@ pub async fn page(req: ServerRequest) -> Result<ServerResponse, ServerResponse>
 
     template(&req, content)
 }
+
+pub async fn action(req: ServerRequest) -> Result<ServerResponse, ServerResponse> {
+    req.check_login()?;
+    req.check_admin()?;
+
+    let action: ServiceAction = req.extract_query()?;
+    let name = action.name.clone();
+    let operation = action.operation;
+
+    let resp = send_req!(req, ServiceAction(action))?;
+
+    // Fetch the service list again so the row reflects the new status
+    let service = send_req!(req, Services)?
+        .services
+        .into_iter()
+        .find(|service| service.name == name)
+        .unwrap_or(ServiceInfo {
+            name,
+            description: String::new(),
+            status: ServiceStatus::Unknown,
+            load_state: String::new(),
+            sub_state: String::new(),
+            start: String::new(),
+            main_pid: None,
+            memory: None,
+            cpu_time: None,
+            err_log: String::new(),
+        });
+
+    let content = service_row(&service, true, Some(action_result(operation, &resp)));
+
+    template(&req, content)
+}
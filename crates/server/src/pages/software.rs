@@ -1,44 +1,126 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
 use maud::{Markup, html};
-use proto::{backend::SoftwareInfo, frontend::CommandAction};
+use proto::{
+    backend::{CommandResponse, CommandStatus, SoftwareInfo, SoftwareResponse},
+    frontend::CommandAction,
+};
 use serde::Deserialize;
 
 use crate::http::{query_array::QueryArray, request::ServerRequest, response::ServerResponse};
 
 use super::template::{send_req, template};
 
-fn software_table(list: &[SoftwareInfo], action: &str) -> Markup {
+#[derive(Default, Deserialize)]
+#[serde(default)]
+pub struct SoftwareQuery {
+    search: String,
+    category: String,
+}
+
+fn category_name(item: &SoftwareInfo) -> &str {
+    if item.category.is_empty() {
+        "Other"
+    } else {
+        &item.category
+    }
+}
+
+fn matches_query(item: &SoftwareInfo, query: &SoftwareQuery) -> bool {
+    let search = query.search.trim().to_lowercase();
+    let category = query.category.is_empty() || category_name(item) == query.category;
+
+    category
+        && [&item.name, &item.desc]
+            .iter()
+            .any(|x| x.to_lowercase().contains(&search))
+}
+
+// Maps each title to the installed titles which depend on it
+fn installed_dependents(installed: &[SoftwareInfo]) -> HashMap<u16, Vec<&str>> {
+    let mut map: HashMap<u16, Vec<&str>> = HashMap::new();
+
+    for item in installed {
+        for &dep in &item.dep_ids {
+            map.entry(dep).or_default().push(&item.name);
+        }
+    }
+
+    map
+}
+
+fn software_table(
+    list: &[SoftwareInfo],
+    action: &str,
+    query: &SoftwareQuery,
+    required_by: Option<&HashMap<u16, Vec<&str>>>,
+    admin: bool,
+) -> Markup {
+    let mut categories: BTreeMap<&str, Vec<&SoftwareInfo>> = BTreeMap::new();
+    for item in list.iter().filter(|x| matches_query(x, query)) {
+        categories
+            .entry(category_name(item))
+            .or_default()
+            .push(item);
+    }
+
     html! {
-        server-swap trigger="submit" target="#output" method="POST" disable={"input[value='" (action) "']"} {
+        server-swap trigger="submit" target="#output" method="POST" disable={"button[value='" (action) "']"} {
             array-form array-name="software" {
                 form {
-                    table {
-                        tr {
-                            th { "Name" }
-                            th { "Description" }
-                            th { "Dependencies" }
-                            th { "Docs" }
-                            th { (action) }
-                        }
-                        @for item in list {
+                    @if categories.is_empty() {
+                        p { "No matching software" }
+                    }
+                    @for (category, items) in &categories {
+                        h3 { (category) }
+                        table .software-table {
                             tr {
-                                td { (item.name) }
-                                td { (item.desc) }
-                                td { (item.deps) }
-                                td {
-                                    @if item.docs.starts_with("http") {
-                                        a href=(item.docs) { (item.docs) }
-                                    } @else {
-                                        (item.docs)
-                                    }
+                                th { "Name" }
+                                th { "Description" }
+                                th { "Dependencies" }
+                                @if required_by.is_some() {
+                                    th { "Required By" }
+                                }
+                                th { "Docs" }
+                                @if admin {
+                                    th { (action) }
                                 }
-                                td {
-                                    input type="checkbox" name="software" value=(item.id);
+                            }
+                            @for item in items {
+                                tr {
+                                    td { (item.name) }
+                                    td { (item.desc) }
+                                    td { (item.deps) }
+                                    @if let Some(required_by) = required_by {
+                                        td {
+                                            @if let Some(names) = required_by.get(&item.id) {
+                                                (names.join(", "))
+                                            }
+                                        }
+                                    }
+                                    td {
+                                        @if item.docs.starts_with("http") {
+                                            a href=(item.docs) { (item.docs) }
+                                        } @else {
+                                            (item.docs)
+                                        }
+                                    }
+                                    @if admin {
+                                        td {
+                                            input type="checkbox" name="software" value=(item.id);
+                                        }
+                                    }
                                 }
                             }
                         }
                     }
-                    br;
-                    input .software-input type="submit" name="action" value=(action);
+                    @if admin {
+                        br;
+                        .software-buttons {
+                            button .software-input type="submit" name="action" value={"Preview" (action)} { "Preview" }
+                            button .software-input type="submit" name="action" value=(action) { (action) }
+                        }
+                    }
                 }
             }
         }
@@ -48,17 +130,42 @@ fn software_table(list: &[SoftwareInfo], action: &str) -> Markup {
 pub async fn page(req: ServerRequest) -> Result<ServerResponse, ServerResponse> {
     req.check_login()?;
 
+    let query: SoftwareQuery = req.extract_query()?;
+
     let data = send_req!(req, Software)?;
 
+    let categories: BTreeSet<_> = data
+        .installed
+        .iter()
+        .chain(&data.uninstalled)
+        .map(category_name)
+        .collect();
+    let required_by = installed_dependents(&data.installed);
+    let admin = req.is_admin();
+
     let content = html! {
+        section {
+            h2 { "Software" }
+            form .software-filter action="/software" {
+                input type="search" name="search" placeholder="Search by name or description" value=(query.search);
+                select name="category" {
+                    option value="" { "All categories" }
+                    @for category in categories {
+                        option value=(category) selected[query.category == category] { (category) }
+                    }
+                }
+                input type="submit" value="Apply";
+            }
+        }
+        br;
         section {
             h2 { "Installed Software" }
-            (software_table(&data.installed, "Uninstall"))
+            (software_table(&data.installed, "Uninstall", &query, Some(&required_by), admin))
         }
         br;
         section {
             h2 { "Not Installed Software" }
-            (software_table(&data.uninstalled, "Install"))
+            (software_table(&data.uninstalled, "Install", &query, None, admin))
         }
         br;
         #output {}
@@ -67,10 +174,121 @@ pub async fn page(req: ServerRequest) -> Result<ServerResponse, ServerResponse>
     template(&req, content)
 }
 
+fn install_preview(data: &SoftwareResponse, selected: &[u16]) -> Markup {
+    let lookup: HashMap<u16, (&SoftwareInfo, bool)> = data
+        .installed
+        .iter()
+        .map(|x| (x.id, (x, true)))
+        .chain(data.uninstalled.iter().map(|x| (x.id, (x, false))))
+        .collect();
+
+    // Walk the dependency graph so that dependencies of dependencies are included too
+    let mut seen: HashSet<u16> = selected.iter().copied().collect();
+    let mut queue: Vec<u16> = selected.to_vec();
+    let mut pulled_in = Vec::new();
+    let mut already_installed = Vec::new();
+    while let Some(id) = queue.pop() {
+        let Some((info, _)) = lookup.get(&id) else {
+            continue;
+        };
+        for &dep in &info.dep_ids {
+            if !seen.insert(dep) {
+                continue;
+            }
+            let Some(&(dep_info, installed)) = lookup.get(&dep) else {
+                continue;
+            };
+            if installed {
+                already_installed.push(dep_info.name.as_str());
+            } else {
+                pulled_in.push(dep_info.name.as_str());
+                queue.push(dep);
+            }
+        }
+    }
+
+    html! {
+        section #output {
+            h2 { "Install Preview" }
+            p { "Nothing has been installed yet." }
+            h3 { "Selected" }
+            ul {
+                @for id in selected {
+                    @if let Some((info, installed)) = lookup.get(id) {
+                        li {
+                            (info.name)
+                            @if *installed {
+                                " (already installed)"
+                            }
+                        }
+                    }
+                }
+            }
+            h3 { "Additionally Installed Dependencies" }
+            @if pulled_in.is_empty() {
+                p { "None" }
+            } @else {
+                ul {
+                    @for name in pulled_in {
+                        li { (name) }
+                    }
+                }
+            }
+            @if !already_installed.is_empty() {
+                h3 { "Dependencies Already Installed" }
+                ul {
+                    @for name in already_installed {
+                        li { (name) }
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn uninstall_preview(data: &SoftwareResponse, selected: &[u16]) -> Markup {
+    let required_by = installed_dependents(&data.installed);
+
+    html! {
+        section #output {
+            h2 { "Uninstall Preview" }
+            p { "Nothing has been uninstalled yet." }
+            ul {
+                @for info in data.installed.iter().filter(|x| selected.contains(&x.id)) {
+                    @let dependents: Vec<_> = required_by
+                        .get(&info.id)
+                        .into_iter()
+                        .flatten()
+                        .filter(|name| !data.installed.iter().any(|x| &x.name == *name && selected.contains(&x.id)))
+                        .collect();
+                    li {
+                        (info.name)
+                        @if dependents.is_empty() {
+                            " - no installed software depends on it"
+                        } @else {
+                            p .command-banner.-failure {
+                                "Required by: "
+                                @for (i, name) in dependents.iter().enumerate() {
+                                    @if i > 0 {
+                                        ", "
+                                    }
+                                    (name)
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
 #[derive(Deserialize)]
 enum SoftwareAction {
     Install,
     Uninstall,
+    PreviewInstall,
+    PreviewUninstall,
 }
 
 #[derive(Deserialize)]
@@ -81,12 +299,25 @@ struct SoftwareForm {
 
 pub async fn form(mut req: ServerRequest) -> Result<ServerResponse, ServerResponse> {
     req.check_login()?;
+    req.check_admin()?;
 
     let form: SoftwareForm = req.extract_form().await?;
 
     let action = match form.action {
         SoftwareAction::Install => "install",
         SoftwareAction::Uninstall => "uninstall",
+        SoftwareAction::PreviewInstall | SoftwareAction::PreviewUninstall => {
+            let data = send_req!(req, Software)?;
+            let selected: Vec<u16> = form.software.iter().collect();
+
+            let content = if matches!(form.action, SoftwareAction::PreviewInstall) {
+                install_preview(&data, &selected)
+            } else {
+                uninstall_preview(&data, &selected)
+            };
+
+            return template(&req, content);
+        }
     }
     .into();
 
@@ -101,13 +332,39 @@ pub async fn form(mut req: ServerRequest) -> Result<ServerResponse, ServerRespon
     };
 
     let resp = send_req!(req, Command(msg))?;
-    let output = String::from_utf8_lossy(&resp.output);
 
     let content = html! {
         section #output {
             h2 { "Install Summary" }
-            pre {
-                (output)
+            @match resp {
+                CommandResponse::Finished(output) => {
+                    @let success = output.status == CommandStatus::Exited(0);
+                    @let stdout = String::from_utf8_lossy(&output.stdout);
+                    @let stderr = String::from_utf8_lossy(&output.stderr);
+
+                    @if success {
+                        p .command-banner.-success { "dietpi-software finished successfully" }
+                    } @else {
+                        p .command-banner.-failure {
+                            @match output.status {
+                                CommandStatus::Exited(code) => { "dietpi-software failed with exit code " (code) }
+                                CommandStatus::Signaled(signal) => { "dietpi-software was killed by signal " (signal) }
+                            }
+                        }
+                    }
+                    @if !stderr.trim().is_empty() {
+                        h3 { "Error Output" }
+                        pre { (stderr) }
+                    }
+                    h3 { "Output" }
+                    pre { (stdout) }
+                }
+                CommandResponse::SpawnFailed(err) => {
+                    p .command-banner.-failure { "Failed to run dietpi-software: " (err) }
+                }
+                CommandResponse::Denied => {
+                    p .command-banner.-failure { "dietpi-software is not allowed by the backend's command allow-list" }
+                }
             }
         }
     };
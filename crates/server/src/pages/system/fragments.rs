@@ -4,7 +4,10 @@ use proto::backend::{CpuResponse, DiskResponse, MemResponse, NetworkResponse, Te
 
 use crate::http::query_array::QueryArray;
 
-use super::graph::{Axis, SvgGraph};
+use super::{
+    SystemQuery,
+    graph::{Axis, SvgGraph},
+};
 
 fn calc_percent(used: u64, total: u64) -> f32 {
     if total == 0 {
@@ -21,24 +24,47 @@ fn calc_grid_span(num_elts: usize) -> usize {
     num_elts.div_ceil(2) + 1
 }
 
+// Colors for individual cores, repeated if there are more cores than colors
+const CORE_COLORS: &[&str] = &[
+    "var(--green-6)",
+    "var(--blue-6)",
+    "var(--red-6)",
+    "var(--yellow-6)",
+    "var(--purple-6)",
+    "var(--pink-6)",
+    "var(--orange-6)",
+    "var(--cyan-6)",
+];
+
 pub fn cpu_meters(cpu_data: &CpuResponse, temp_data: &TempResponse) -> Markup {
-    let cpu_iter = cpu_data.cpus.iter().zip(1_u8..);
+    let cpu_iter = cpu_data.cpus.iter().zip(&cpu_data.frequencies).zip(1_u8..);
+    let [load_1, load_5, load_15] = cpu_data.load_avg;
 
-    // Add 1 row to account for CPU temperature and global CPU
-    let span = calc_grid_span(cpu_data.cpus.len()) + 1;
+    // Add 2 rows to account for CPU temperature, global CPU and the extra statistics
+    let span = (calc_grid_span(cpu_data.cpus.len()) + 2).min(6);
 
     html! {
         section .{"span-" (span)} {
             h2 { "CPU Statistics" }
+            p { (cpu_data.brand) }
             @if let Some(temp) = temp_data.temp {
                 p { "CPU Temperature: " (temp) "ºC" }
             }
+            p { "Load Average: " (load_1) ", " (load_5) ", " (load_15) }
+            p {
+                "Context Switches: " (cpu_data.context_switches) "/s, Interrupts: " (cpu_data.interrupts) "/s"
+            }
             p { "Global CPU: " (cpu_data.global_cpu) "%" }
             .meter-container {
                 .bar.-cpu style={"--scale:"(cpu_data.global_cpu / 100.)} {}
             }
-            @for (usage, num) in cpu_iter {
-                p { "CPU "(num)": "(usage)"%" }
+            @for ((usage, freq), num) in cpu_iter {
+                p {
+                    "CPU "(num)": "(usage)"% at "(freq.current)" MHz"
+                    @if let (Some(min), Some(max)) = (freq.min, freq.max) {
+                        " (" (min) "-" (max) " MHz)"
+                    }
+                }
                 .meter-container {
                     .bar.-cpu style={"--scale:"(usage / 100.)} {}
                 }
@@ -67,8 +93,42 @@ pub fn cpu_graph(data: &CpuResponse, points: &mut QueryArray) -> Markup {
     }
 }
 
-pub fn temp_graph(data: &TempResponse, points: &mut QueryArray) -> Option<Markup> {
-    data.temp.map(|temp| {
+// Points are stored as every core's usage for one sample, followed by the previous sample
+pub fn core_graph(data: &CpuResponse, points: &mut QueryArray) -> Markup {
+    let mut graph = SvgGraph::new(Axis::Percent);
+
+    let num_cores = data.cpus.len().max(1);
+    let prev_points: Vec<f32> = points.iter().collect();
+
+    let samples: Vec<Vec<f32>> = std::iter::once(data.cpus.clone())
+        .chain(prev_points.chunks_exact(num_cores).map(<[f32]>::to_vec))
+        .take(20)
+        .collect();
+
+    // Scale each core down so the top of the stack is global usage.
+    // The top is drawn first, so lower series are painted over it.
+    for core in (0..num_cores).rev() {
+        let stacked = samples
+            .iter()
+            .map(|sample| sample.iter().take(core + 1).sum::<f32>() / num_cores as f32);
+
+        graph.add_filled_series(stacked, CORE_COLORS[core % CORE_COLORS.len()]);
+    }
+
+    *points = samples.iter().flatten().collect();
+
+    html! {
+        section .span-3 {
+            h2 { "Per-Core CPU Graph" }
+            (graph)
+        }
+    }
+}
+
+pub fn temp_graph(data: &TempResponse, sensor: &str, points: &mut QueryArray) -> Option<Markup> {
+    let selected = data.sensors.iter().find(|x| x.label == sensor);
+
+    selected.map(|x| x.temp).or(data.temp).map(|temp| {
         let mut graph = SvgGraph::new(Axis::Temp);
 
         let points_iter = std::iter::once(temp).chain(points.iter()).take(20);
@@ -80,13 +140,91 @@ pub fn temp_graph(data: &TempResponse, points: &mut QueryArray) -> Option<Markup
         html! {
                 section .span-3
                 {
-                    h2 { "Temperature Graph" }
+                    h2 {
+                        "Temperature Graph"
+                        @if let Some(selected) = selected {
+                            " (" (selected.label) ")"
+                        }
+                    }
                     (graph)
                 }
         }
     })
 }
 
+pub fn sensors(data: &TempResponse, query: &SystemQuery) -> Option<Markup> {
+    if data.sensors.is_empty() && data.fans.is_empty() && data.throttle.is_none() {
+        return None;
+    }
+
+    // Throttle state takes up about two rows
+    let lines = data.sensors.len() + data.fans.len() + data.throttle.as_ref().map_or(0, |_| 4);
+    let span = calc_grid_span(lines).min(6);
+
+    let throttle_flags = data.throttle.as_ref().map(|throttle| {
+        [
+            (
+                "Under-voltage",
+                throttle.under_voltage,
+                throttle.under_voltage_occurred,
+            ),
+            (
+                "Frequency capped",
+                throttle.freq_capped,
+                throttle.freq_capped_occurred,
+            ),
+            ("Throttled", throttle.throttled, throttle.throttled_occurred),
+            (
+                "Soft temperature limit",
+                throttle.soft_temp_limit,
+                throttle.soft_temp_limit_occurred,
+            ),
+        ]
+    });
+
+    Some(html! {
+        section .{"span-" (span)} {
+            h2 { "Sensors" }
+
+            @for sensor in &data.sensors {
+                // The old points belong to a different sensor, so start the graph over
+                @let graph_query = SystemQuery {
+                    temp_sensor: sensor.label.clone(),
+                    temp_points: QueryArray::default(),
+                    ..query.clone()
+                };
+                @let graph_query = serde_urlencoded::to_string(&graph_query).unwrap();
+
+                p {
+                    (sensor.label) ": " (sensor.temp) "ºC (max " (sensor.max) "ºC"
+                    @if let Some(critical) = sensor.critical {
+                        ", critical " (critical) "ºC"
+                    }
+                    ") · "
+                    a href={"/system?" (graph_query)} { "Graph" }
+                }
+            }
+
+            @for fan in &data.fans {
+                p { (fan.label) ": " (fan.rpm) " RPM" }
+            }
+
+            @if let Some(flags) = throttle_flags {
+                @for (name, now, occurred) in flags {
+                    @if now {
+                        p .command-banner.-failure { (name) " right now" }
+                    } @else if occurred {
+                        p { (name) " since boot" }
+                    }
+                }
+                @if flags.iter().all(|(_, now, occurred)| !now && !occurred) {
+                    p { "No throttling or under-voltage since boot" }
+                }
+            }
+        }
+    })
+}
+
 pub fn mem_meters(data: &MemResponse) -> Markup {
     let pretty_ram_used = pretty_bytes_binary(data.ram.used, Some(2));
     let pretty_ram_total = pretty_bytes_binary(data.ram.total, Some(2));
@@ -96,8 +234,11 @@ pub fn mem_meters(data: &MemResponse) -> Markup {
     let pretty_swap_total = pretty_bytes_binary(data.swap.total, Some(2));
     let swap_percent = calc_percent(data.swap.used, data.swap.total);
 
+    let lines = data.breakdown.as_ref().map_or(0, |_| 4) + data.zram.len() + data.pressure.len();
+    let span = (calc_grid_span(lines) + 1).min(6);
+
     html! {
-        section .span-2 {
+        section .{"span-" (span)} {
             h2 { "Memory Usage" }
 
             p { "RAM Usage: " (pretty_ram_used) " / " (pretty_ram_total) }
@@ -109,6 +250,43 @@ pub fn mem_meters(data: &MemResponse) -> Markup {
             div .meter-container {
                 div .bar.-swap style={"--scale:"(swap_percent / 100.)} {}
             }
+
+            @if let Some(breakdown) = &data.breakdown {
+                p { "Available: " (pretty_bytes_binary(breakdown.available, Some(2))) }
+                p {
+                    "Buffers: " (pretty_bytes_binary(breakdown.buffers, Some(2)))
+                    ", Cached: " (pretty_bytes_binary(breakdown.cached, Some(2)))
+                }
+                p {
+                    "Shared: " (pretty_bytes_binary(breakdown.shared, Some(2)))
+                    ", Dirty: " (pretty_bytes_binary(breakdown.dirty, Some(2)))
+                }
+                @if let (Some(zswap), Some(zswapped)) = (breakdown.zswap, breakdown.zswapped) {
+                    p {
+                        "Zswap: " (pretty_bytes_binary(zswapped, Some(2)))
+                        " stored in " (pretty_bytes_binary(zswap, Some(2)))
+                    }
+                }
+            }
+
+            @for zram in &data.zram {
+                p {
+                    (zram.name) ": " (pretty_bytes_binary(zram.orig_data, Some(2)))
+                    " compressed to " (pretty_bytes_binary(zram.compr_data, Some(2)))
+                    " (" (pretty_bytes_binary(zram.mem_used, Some(2))) " used)"
+                }
+            }
+
+            @for pressure in &data.pressure {
+                @let [some_10, some_60, some_300] = pressure.some;
+
+                p title="Percentage of time tasks were stalled waiting, averaged over 10s, 60s and 300s" {
+                    "Pressure (" (pressure.resource) "): " (some_10) "%, " (some_60) "%, " (some_300) "%"
+                    @if let Some([full_10, full_60, full_300]) = pressure.full {
+                        " (all tasks: " (full_10) "%, " (full_60) "%, " (full_300) "%)"
+                    }
+                }
+            }
         }
     }
 }
@@ -116,6 +294,7 @@ pub fn mem_meters(data: &MemResponse) -> Markup {
 pub fn mem_graph(
     data: &MemResponse,
     ram_points: &mut QueryArray,
+    cache_points: &mut QueryArray,
     swap_points: &mut QueryArray,
 ) -> Markup {
     let mut graph = SvgGraph::new(Axis::Percent);
@@ -123,17 +302,26 @@ pub fn mem_graph(
     let ram_percent = calc_percent(data.ram.used, data.ram.total);
     let swap_percent = calc_percent(data.swap.used, data.swap.total);
 
+    // Cache is stacked on top of used memory, so it shows how much is actually in use
+    let cache = data.breakdown.as_ref().map_or(0, |x| x.buffers + x.cached);
+    let cache_percent = calc_percent((data.ram.used + cache).min(data.ram.total), data.ram.total);
+
     let ram_points_iter = std::iter::once(ram_percent)
         .chain(ram_points.iter())
         .take(20);
+    let cache_points_iter = std::iter::once(cache_percent)
+        .chain(cache_points.iter())
+        .take(20);
     let swap_points_iter = std::iter::once(swap_percent)
         .chain(swap_points.iter())
         .take(20);
 
-    graph.add_series(ram_points_iter.clone(), "var(--red-6)");
+    graph.add_filled_series(cache_points_iter.clone(), "var(--orange-6)");
+    graph.add_filled_series(ram_points_iter.clone(), "var(--red-6)");
     graph.add_series(swap_points_iter.clone(), "var(--blue-6)");
 
     *ram_points = ram_points_iter.collect();
+    *cache_points = cache_points_iter.collect();
     *swap_points = swap_points_iter.collect();
 
     html! {
@@ -145,7 +333,8 @@ pub fn mem_graph(
 }
 
 pub fn disk_meters(data: &DiskResponse) -> Markup {
-    let span = calc_grid_span(data.disks.len());
+    // Each disk has an extra line for its details
+    let span = calc_grid_span(data.disks.len() * 2);
 
     html! {
         section .{"span-" (span)} {
@@ -160,22 +349,75 @@ pub fn disk_meters(data: &DiskResponse) -> Markup {
                 .meter-container {
                     .bar.-disk style={"--scale:"(disk_percent / 100.)} {}
                 }
+                p .disk-details {
+                    (disk.fs_type)
+                    @if disk.read_only {
+                        ", read-only"
+                    }
+                    @if let Some(inodes) = &disk.inodes {
+                        ", inodes: " (calc_percent(inodes.used, inodes.total)) "% used"
+                    }
+                    @if let Some(io) = &disk.io {
+                        @let pretty_read = pretty_bytes(io.read_rate, Some(1));
+                        @let pretty_write = pretty_bytes(io.write_rate, Some(1));
+
+                        ", read: " (pretty_read) "/s (" (io.read_iops) " IOPS)"
+                        ", write: " (pretty_write) "/s (" (io.write_iops) " IOPS)"
+                    }
+                }
+                @if disk.remounted_ro {
+                    p .command-banner.-failure {
+                        (disk.mnt_point) " was remounted read-only, which usually means the disk has errors"
+                    }
+                }
             }
         }
     }
 }
 
+pub fn disk_graph(
+    data: &DiskResponse,
+    read_points: &mut QueryArray,
+    write_points: &mut QueryArray,
+) -> Markup {
+    let mut graph = SvgGraph::new(Axis::Bytes);
+
+    let read_points_iter = std::iter::once(data.read as f32)
+        .chain(read_points.iter())
+        .take(20);
+    let write_points_iter = std::iter::once(data.write as f32)
+        .chain(write_points.iter())
+        .take(20);
+
+    graph.add_series(read_points_iter.clone(), "var(--yellow-6)");
+    graph.add_series(write_points_iter.clone(), "var(--orange-6)");
+
+    *read_points = read_points_iter.collect();
+    *write_points = write_points_iter.collect();
+
+    html! {
+        section .span-3 {
+            h2 { "Disk I/O Graph" }
+            (graph)
+        }
+    }
+}
+
 pub fn net_graph(
     data: &NetworkResponse,
+    iface: &str,
     sent_points: &mut QueryArray,
     recv_points: &mut QueryArray,
 ) -> Markup {
     let mut graph = SvgGraph::new(Axis::Bytes);
 
-    let sent_points_iter = std::iter::once(data.sent as f32)
+    let selected = data.interfaces.iter().find(|x| x.name == iface);
+    let (sent, recv) = selected.map_or((data.sent, data.recv), |x| (x.sent, x.recv));
+
+    let sent_points_iter = std::iter::once(sent as f32)
         .chain(sent_points.iter())
         .take(20);
-    let recv_points_iter = std::iter::once(data.recv as f32)
+    let recv_points_iter = std::iter::once(recv as f32)
         .chain(recv_points.iter())
         .take(20);
 
@@ -187,8 +429,49 @@ pub fn net_graph(
 
     html! {
         section .span-3 {
-            h2 { "Network Graph" }
+            h2 {
+                "Network Graph"
+                @if let Some(selected) = selected {
+                    " (" (selected.name) ")"
+                }
+            }
             (graph)
         }
     }
 }
+
+pub fn net_interfaces(data: &NetworkResponse) -> Markup {
+    html! {
+        @for iface in &data.interfaces {
+            @let pretty_sent = pretty_bytes(iface.sent, Some(1));
+            @let pretty_recv = pretty_bytes(iface.recv, Some(1));
+            @let pretty_total_sent = pretty_bytes(iface.total_sent, Some(2));
+            @let pretty_total_recv = pretty_bytes(iface.total_recv, Some(2));
+            @let span = (calc_grid_span(iface.addresses.len()) + 2).min(6);
+
+            section .{"span-" (span)} {
+                h2 { (iface.name) }
+                p {
+                    @if iface.up { "Up" } @else { "Down" }
+                    @if let Some(speed) = iface.speed {
+                        ", " (speed) " Mbit/s"
+                    }
+                    " · " a href={"/system?net_iface=" (iface.name)} { "Graph" }
+                }
+                p { "MAC: " (iface.mac) }
+                @for addr in &iface.addresses {
+                    p { (addr) }
+                }
+                p { "Sending: " (pretty_sent) "/s, receiving: " (pretty_recv) "/s" }
+                p { "Sent: " (pretty_total_sent) " (" (iface.packets_sent) " packets)" }
+                p { "Received: " (pretty_total_recv) " (" (iface.packets_recv) " packets)" }
+                p {
+                    "Errors: " (iface.errors_sent) " sent, " (iface.errors_recv) " received"
+                }
+                p {
+                    "Dropped: " (iface.dropped_sent) " sent, " (iface.dropped_recv) " received"
+                }
+            }
+        }
+    }
+}
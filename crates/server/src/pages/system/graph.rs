@@ -8,6 +8,7 @@ const LINE_SPACING: u32 = 10;
 pub struct GraphSeries {
     points: Vec<(u32, f32)>,
     color: String,
+    filled: bool,
 }
 
 #[derive(Clone, Copy)]
@@ -55,6 +56,15 @@ impl SvgGraph {
     }
 
     pub fn add_series(&mut self, points: impl Iterator<Item = f32>, color: &str) {
+        self.push_series(points, color, false);
+    }
+
+    // Fills the area below the line, so later series should be lower to stay visible
+    pub fn add_filled_series(&mut self, points: impl Iterator<Item = f32>, color: &str) {
+        self.push_series(points, color, true);
+    }
+
+    fn push_series(&mut self, points: impl Iterator<Item = f32>, color: &str, filled: bool) {
         let points = points.map(|x| self.axis.interpolate(x));
 
         // Creates (x, y) pairs starting from the right
@@ -63,6 +73,7 @@ impl SvgGraph {
         let series = GraphSeries {
             points,
             color: color.to_string(),
+            filled,
         };
 
         self.series.push(series);
@@ -119,9 +130,20 @@ impl Render for SvgGraph {
                         }
                         acc
                     };
-                    g fill=(&series.color) {
-                        @for (x, y) in points {
-                            circle cx=(x) cy=(y) r="1.5" {}
+                    @if series.filled {
+                        @let (first_x, last_x) = (
+                            series.points.first().map_or(0, |(x, _)| left_margin + LINE_SPACING * x),
+                            series.points.last().map_or(0, |(x, _)| left_margin + LINE_SPACING * x),
+                        );
+                        polygon
+                            points={(polyline_points) (last_x) "," (y_end) " " (first_x) "," (y_end)}
+                            fill=(&series.color)
+                            fill-opacity="0.6" {}
+                    } @else {
+                        g fill=(&series.color) {
+                            @for (x, y) in points {
+                                circle cx=(x) cy=(y) r="1.5" {}
+                            }
                         }
                     }
                     polyline points=(polyline_points) stroke=(&series.color) fill="none" {}
@@ -8,15 +8,21 @@ use super::template::{send_req, template};
 mod fragments;
 mod graph;
 
-#[derive(Default, Serialize, Deserialize)]
+#[derive(Default, Serialize, Deserialize, Clone)]
 #[serde(default)]
 pub struct SystemQuery {
     cpu_points: QueryArray,
+    core_points: QueryArray,
     temp_points: QueryArray,
     ram_points: QueryArray,
+    cache_points: QueryArray,
     swap_points: QueryArray,
+    disk_read_points: QueryArray,
+    disk_write_points: QueryArray,
     sent_points: QueryArray,
     recv_points: QueryArray,
+    net_iface: String,
+    temp_sensor: String,
 }
 
 pub async fn page(req: ServerRequest) -> Result<ServerResponse, ServerResponse> {
@@ -33,11 +39,30 @@ pub async fn page(req: ServerRequest) -> Result<ServerResponse, ServerResponse>
     let cpu_meters = fragments::cpu_meters(&cpu_data, &temp_data);
     let mem_meters = fragments::mem_meters(&mem_data);
     let disk_meters = fragments::disk_meters(&disk_data);
+    let disk_graph = fragments::disk_graph(
+        &disk_data,
+        &mut query.disk_read_points,
+        &mut query.disk_write_points,
+    );
 
     let cpu_graph = fragments::cpu_graph(&cpu_data, &mut query.cpu_points);
-    let temp_graph = fragments::temp_graph(&temp_data, &mut query.temp_points);
-    let mem_graph = fragments::mem_graph(&mem_data, &mut query.ram_points, &mut query.swap_points);
-    let net_graph = fragments::net_graph(&net_data, &mut query.sent_points, &mut query.recv_points);
+    let core_graph = fragments::core_graph(&cpu_data, &mut query.core_points);
+    let temp_graph = fragments::temp_graph(&temp_data, &query.temp_sensor, &mut query.temp_points);
+    let mem_graph = fragments::mem_graph(
+        &mem_data,
+        &mut query.ram_points,
+        &mut query.cache_points,
+        &mut query.swap_points,
+    );
+    let net_graph = fragments::net_graph(
+        &net_data,
+        &query.net_iface,
+        &mut query.sent_points,
+        &mut query.recv_points,
+    );
+    let net_interfaces = fragments::net_interfaces(&net_data);
+    // Links to the other sensors keep the rest of the points, so this has to come after every graph
+    let sensors = fragments::sensors(&temp_data, &query);
 
     let new_query = serde_urlencoded::to_string(&query).unwrap();
 
@@ -45,13 +70,19 @@ pub async fn page(req: ServerRequest) -> Result<ServerResponse, ServerResponse>
         server-swap .card-grid action={"/system?" (new_query)} trigger="delay" {
             (cpu_meters)
             (cpu_graph)
+            (core_graph)
             @if let Some(temp_graph) = temp_graph {
                 (temp_graph)
             }
+            @if let Some(sensors) = sensors {
+                (sensors)
+            }
             (mem_meters)
             (mem_graph)
             (disk_meters)
+            (disk_graph)
             (net_graph)
+            (net_interfaces)
         }
     };
 
@@ -61,6 +61,10 @@ fn header(req: &ServerRequest) -> Result<Markup, ServerResponse> {
 fn nav() -> Markup {
     html! {
         nav {
+            a href="/overview" {
+                (Icon::new("fa6-solid-server"))
+                "Overview"
+            }
             a href="/system" {
                 (Icon::new("fa6-solid-gauge"))
                 "System"
@@ -77,6 +81,38 @@ fn nav() -> Markup {
                 (Icon::new("fa6-solid-list"))
                 "Services"
             }
+            a href="/logs" {
+                (Icon::new("fa6-solid-file-lines"))
+                "Logs"
+            }
+            a href="/files" {
+                (Icon::new("fa6-solid-folder"))
+                "Files"
+            }
+            a href="/updates" {
+                (Icon::new("fa6-solid-download"))
+                span {
+                    "Updates"
+                    // Checking for updates is slow, so don't hold up the page for it
+                    server-swap action="/updates/badge" trigger="delay" {}
+                }
+            }
+            a href="/schedule" {
+                (Icon::new("fa6-solid-clock"))
+                "Schedule"
+            }
+            a href="/network" {
+                (Icon::new("fa6-solid-network-wired"))
+                "Network"
+            }
+            a href="/firewall" {
+                (Icon::new("fa6-solid-shield-halved"))
+                "Firewall"
+            }
+            a href="/containers" {
+                (Icon::new("fa6-brands-docker"))
+                "Containers"
+            }
             a href="/management" {
                 (Icon::new("fa6-solid-user"))
                 "Management"
@@ -128,6 +164,8 @@ pub fn template(req: &ServerRequest, content: Markup) -> Result<ServerResponse,
 
                     (footer())
 
+                    div #toast {}
+
                     script src="/static/main.js" {}
                 }
             }
@@ -139,6 +177,15 @@ pub fn template(req: &ServerRequest, content: Markup) -> Result<ServerResponse,
         .body(page.into_string()))
 }
 
+// Replaces the page-wide toast, which fades out by itself
+pub fn toast(success: bool, message: Markup) -> Markup {
+    html! {
+        div #toast .command-banner.-success[success].-failure[!success] role="status" {
+            (message)
+        }
+    }
+}
+
 pub struct Icon {
     name: &'static str,
     size: u8,
@@ -4,17 +4,32 @@ use futures_util::{SinkExt, StreamExt};
 use proto::frontend::ActionFrontendMessage;
 use tokio_tungstenite::tungstenite::Message;
 
+use serde::Deserialize;
+
 use crate::http::{request::ServerRequest, response::ServerResponse};
 
 use super::template::template;
 
+#[derive(Deserialize)]
+pub struct TerminalQuery {
+    /// Open a shell in this container instead of the host
+    container: Option<String>,
+}
+
 pub async fn page(req: ServerRequest) -> Result<ServerResponse, ServerResponse> {
     req.check_login()?;
 
+    let query: TerminalQuery = req.extract_query()?;
+
     let content = html! {
         section {
-            h2 { "Terminal" }
-            web-terminal {}
+            @if let Some(container) = &query.container {
+                h2 { "Terminal: " (container) }
+                web-terminal src={"/terminal/ws?container=" (container)} {}
+            } @else {
+                h2 { "Terminal" }
+                web-terminal {}
+            }
         }
     };
 
@@ -24,11 +39,23 @@ pub async fn page(req: ServerRequest) -> Result<ServerResponse, ServerResponse>
 pub async fn socket(req: ServerRequest) -> Result<ServerResponse, ServerResponse> {
     req.check_login()?;
 
+    let query: TerminalQuery = req.extract_query()?;
+    if query.container.is_some() {
+        req.check_admin()?;
+    }
+
     let backend = req.extract_backends()?.current_backend.1;
 
     req.extract_websocket(async move |mut ws| {
         let mut term_rx = backend.get_terminal_handle().await.unwrap();
 
+        if let Some(container) = query.container {
+            let msg = ActionFrontendMessage::ContainerExec(container);
+            if backend.send_action(msg).await.is_err() {
+                return;
+            }
+        }
+
         loop {
             tokio::select! {
                 data = term_rx.recv() => {
@@ -0,0 +1,195 @@
+use futures_util::{SinkExt, StreamExt};
+use maud::{Markup, html};
+use proto::{
+    backend::{CommandStatus, JobEvent},
+    frontend::{ActionFrontendMessage, Job},
+};
+use serde::Deserialize;
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::http::{request::ServerRequest, response::ServerResponse};
+
+use super::template::{send_req, template};
+
+#[derive(Deserialize)]
+pub struct StartQuery {
+    job: Job,
+}
+
+fn job_name(job: Job) -> &'static str {
+    match job {
+        Job::AptUpdate => "apt-get update",
+        Job::AptUpgrade => "apt-get upgrade",
+        Job::DietPiUpdate => "dietpi-update",
+    }
+}
+
+fn job_button(job: Job, label: &str, confirm: Option<&str>) -> Markup {
+    let job_param = match job {
+        Job::AptUpdate => "apt_update",
+        Job::AptUpgrade => "apt_upgrade",
+        Job::DietPiUpdate => "dietpi_update",
+    };
+
+    html! {
+        server-swap action={"/updates/start?job=" (job_param)} method="POST" target="none" confirm=[confirm] {
+            button { (label) }
+        }
+    }
+}
+
+fn job_event(event: &JobEvent) -> Markup {
+    html! {
+        @match event {
+            JobEvent::Started(job) => {
+                strong { "$ " (job_name(*job)) } "\n"
+            }
+            JobEvent::Output(line) => {
+                (line) "\n"
+            }
+            JobEvent::Finished(CommandStatus::Exited(0)) => {
+                strong .job-success { "Finished successfully. " }
+                a href="/updates" { "Reload package list" } "\n"
+            }
+            JobEvent::Finished(CommandStatus::Exited(code)) => {
+                strong .job-failure { "Failed with exit code " (code) } "\n"
+            }
+            JobEvent::Finished(CommandStatus::Signaled(signal)) => {
+                strong .job-failure { "Killed by signal " (signal) } "\n"
+            }
+            JobEvent::Failed(err) => {
+                strong .job-failure { "Failed to run job: " (err) } "\n"
+            }
+            JobEvent::Rejected(err) => {
+                strong .job-failure { "Couldn't start job: " (err) } "\n"
+            }
+        }
+    }
+}
+
+pub async fn page(req: ServerRequest) -> Result<ServerResponse, ServerResponse> {
+    req.check_login()?;
+
+    let data = send_req!(req, Updates)?;
+
+    let security_count = data.packages.iter().filter(|x| x.security).count();
+
+    let content = html! {
+        section {
+            h2 { "Updates" }
+
+            @if let Some(version) = &data.dietpi_update {
+                p .command-banner.-success { "DietPi v" (version) " is available" }
+            }
+            @if let Some(err) = &data.error {
+                p .command-banner.-failure { "Failed to list upgradable packages: " (err) }
+            }
+
+            p {
+                (data.packages.len()) " packages can be upgraded"
+                @if security_count > 0 {
+                    ", " strong { (security_count) " of them security updates" }
+                }
+            }
+
+            @if req.is_admin() {
+                .update-actions {
+                    (job_button(Job::AptUpdate, "Refresh package lists", None))
+                    (job_button(Job::AptUpgrade, "Upgrade all packages", Some("Upgrade all packages now?")))
+                    @if data.dietpi_update.is_some() {
+                        (job_button(Job::DietPiUpdate, "Update DietPi", Some("Update DietPi now?")))
+                    }
+                }
+            }
+
+            job-output src="/updates/ws" {
+                pre {}
+            }
+        }
+
+        @if !data.packages.is_empty() {
+            section {
+                table .update-table {
+                    tr {
+                        th { "Package" }
+                        th { "Installed" }
+                        th { "Candidate" }
+                        th { "Origin" }
+                    }
+                    @for package in &data.packages {
+                        tr .-security[package.security] {
+                            td { (package.name) }
+                            td { (package.current) }
+                            td { (package.candidate) }
+                            td { (package.origin) }
+                        }
+                    }
+                }
+            }
+        }
+    };
+
+    template(&req, content)
+}
+
+pub async fn start(req: ServerRequest) -> Result<ServerResponse, ServerResponse> {
+    req.check_login()?;
+    req.check_admin()?;
+
+    let query: StartQuery = req.extract_query()?;
+
+    req.send_backend_action(ActionFrontendMessage::StartJob(query.job))
+        .await?;
+
+    Ok(ServerResponse::new())
+}
+
+pub async fn badge(req: ServerRequest) -> Result<ServerResponse, ServerResponse> {
+    req.check_login()?;
+
+    let data = send_req!(req, Updates)?;
+
+    let count = data.packages.len() + usize::from(data.dietpi_update.is_some());
+
+    let content = html! {
+        @if count > 0 {
+            span .nav-badge title="Updates available" { (count) }
+        }
+    };
+
+    template(&req, content)
+}
+
+pub async fn socket(req: ServerRequest) -> Result<ServerResponse, ServerResponse> {
+    req.check_login()?;
+
+    let backend = req.extract_backends()?.current_backend.1;
+
+    req.extract_websocket(async move |mut ws| {
+        let Ok(mut job_rx) = backend.follow_job().await else {
+            return;
+        };
+
+        loop {
+            tokio::select! {
+                event = job_rx.recv() => {
+                    let Some(event) = event else {
+                        break;
+                    };
+
+                    let html = job_event(&event).into_string();
+
+                    if ws.send(Message::text(html)).await.is_err() {
+                        break;
+                    }
+                }
+                data = ws.next() => {
+                    // The client never sends anything meaningful, so this only detects disconnects
+                    let Some(Ok(_)) = data else {
+                        break;
+                    };
+                }
+            }
+        }
+    })
+}
@@ -0,0 +1,402 @@
+use maud::{Markup, html};
+use proto::{
+    backend::{AuthorizedKey, UsersResponse},
+    frontend::UserAction,
+};
+use serde::Deserialize;
+
+use crate::http::{request::ServerRequest, response::ServerResponse};
+
+use super::template::{send_req, template};
+
+#[derive(Deserialize)]
+pub struct UserQuery {
+    name: String,
+}
+
+#[derive(Deserialize)]
+pub struct AddUserForm {
+    name: String,
+    password: String,
+    confirm: String,
+    groups: String,
+}
+
+#[derive(Deserialize)]
+pub struct PasswordForm {
+    name: String,
+    password: String,
+    confirm: String,
+}
+
+#[derive(Deserialize)]
+pub struct LockForm {
+    name: String,
+    lock: bool,
+}
+
+#[derive(Deserialize)]
+pub struct RemoveForm {
+    name: String,
+    #[serde(default)]
+    remove_home: bool,
+}
+
+#[derive(Deserialize)]
+pub struct KeyForm {
+    name: String,
+    key: String,
+}
+
+pub fn users_section(data: &UsersResponse, audit_log: &[String]) -> Markup {
+    html! {
+        section {
+            h2 { "Users" }
+
+            table .users-table {
+                tr {
+                    th { "Name" }
+                    th { "UID" }
+                    th { "Groups" }
+                    th { "Shell" }
+                    th { "Status" }
+                    th { "Actions" }
+                }
+                @for user in &data.users {
+                    tr {
+                        td { (user.name) }
+                        td { (user.uid) }
+                        td { (user.groups.join(", ")) }
+                        td { (user.shell) }
+                        td {
+                            @match user.locked {
+                                Some(true) => "Locked",
+                                Some(false) => "Active",
+                                None => "Unknown",
+                            }
+                        }
+                        td {
+                            a href={"/management/users/detail?name=" (user.name)} { "Manage" }
+                        }
+                    }
+                }
+            }
+
+            details {
+                summary { "Groups" }
+                table .users-table {
+                    tr {
+                        th { "Name" }
+                        th { "GID" }
+                        th { "Members" }
+                    }
+                    @for group in &data.groups {
+                        tr {
+                            td { (group.name) }
+                            td { (group.gid) }
+                            td { (group.members.join(", ")) }
+                        }
+                    }
+                }
+            }
+
+            h3 { "Add User" }
+            server-swap action="/management/users/add" method="POST" trigger="submit" target="#user-result" {
+                form .management-form {
+                    input type="text" name="name" placeholder="Username" required
+                        maxlength="32" pattern="[a-z_][a-z0-9_\\-]*";
+                    input type="password" name="password" placeholder="Password" required autocomplete="new-password";
+                    input type="password" name="confirm" placeholder="Confirm password" required autocomplete="new-password";
+                    input type="text" name="groups" placeholder="Extra groups, e.g. sudo, video";
+                    input type="submit" value="Add";
+                }
+            }
+            #user-result {}
+
+            details {
+                summary { "Recent Changes" }
+                @if audit_log.is_empty() {
+                    p { "No user changes have been recorded" }
+                } @else {
+                    pre .audit-log {
+                        @for line in audit_log.iter().rev() {
+                            (line) "\n"
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn result_banner(label: &str, result: &Result<(), String>) -> Markup {
+    html! {
+        @match result {
+            Ok(()) => p .command-banner.-success { (label) " succeeded" },
+            Err(err) => p .command-banner.-failure { (label) " failed: " (err) },
+        }
+    }
+}
+
+fn action_result(label: &str, result: &Result<(), String>) -> Markup {
+    html! {
+        div #user-result {
+            (result_banner(label, result))
+        }
+    }
+}
+
+fn keys_section(name: &str, keys: &Result<Vec<AuthorizedKey>, String>, banner: Markup) -> Markup {
+    html! {
+        section #user-keys {
+            h2 { "SSH Keys" }
+            (banner)
+            @match keys {
+                Ok(keys) if keys.is_empty() => p { "No authorized keys" },
+                Ok(keys) => {
+                    table .users-table {
+                        tr {
+                            th { "Type" }
+                            th { "Key" }
+                            th { "Comment" }
+                            th { "Options" }
+                            th { "Actions" }
+                        }
+                        @for key in keys {
+                            @let start = key.data.len().saturating_sub(16);
+                            tr {
+                                td { (key.key_type) }
+                                td .key-data title=(key.data) { "..." (key.data.get(start..).unwrap_or(&key.data)) }
+                                td { (key.comment) }
+                                td { (key.options) }
+                                td {
+                                    server-swap action="/management/users/keys/remove" method="POST" trigger="submit"
+                                        target="#user-keys" confirm={"Remove this key from " (name) "?"} {
+                                        form {
+                                            input type="hidden" name="name" value=(name);
+                                            input type="hidden" name="key" value=(key.data);
+                                            input type="submit" value="Remove";
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+                Err(err) => p .command-banner.-failure { "Failed to read authorized keys: " (err) },
+            }
+
+            h3 { "Add Key" }
+            server-swap action="/management/users/keys/add" method="POST" trigger="submit" target="#user-keys" {
+                form .key-form {
+                    input type="hidden" name="name" value=(name);
+                    textarea name="key" rows="3" required placeholder="ssh-ed25519 AAAA... user@host" {}
+                    input type="submit" value="Add";
+                }
+            }
+        }
+    }
+}
+
+pub async fn page(req: ServerRequest) -> Result<ServerResponse, ServerResponse> {
+    req.check_login()?;
+    req.check_admin()?;
+
+    let query: UserQuery = req.extract_query()?;
+
+    let data = send_req!(req, Users)?;
+    let Some(user) = data.users.into_iter().find(|x| x.name == query.name) else {
+        return template(&req, html! { p { "No such user" } });
+    };
+    let keys = send_req!(req, AuthorizedKeys(user.name.clone()))?;
+
+    let content = html! {
+        section {
+            h2 { "User " (user.name) }
+            table .management-table {
+                tr { td { "UID" } td { (user.uid) } }
+                tr { td { "GID" } td { (user.gid) } }
+                tr { td { "Home" } td { (user.home) } }
+                tr { td { "Shell" } td { (user.shell) } }
+                tr { td { "Groups" } td { (user.groups.join(", ")) } }
+                tr {
+                    td { "Status" }
+                    td {
+                        @match user.locked {
+                            Some(true) => "Locked",
+                            Some(false) => "Active",
+                            None => "Unknown",
+                        }
+                    }
+                }
+            }
+        }
+
+        section {
+            h2 { "Account" }
+
+            server-swap action="/management/users/password" method="POST" trigger="submit" target="#user-result" {
+                form .management-form {
+                    input type="hidden" name="name" value=(user.name);
+                    input type="password" name="password" placeholder="New password" required autocomplete="new-password";
+                    input type="password" name="confirm" placeholder="Confirm password" required autocomplete="new-password";
+                    input type="submit" value="Change Password";
+                }
+            }
+
+            // Root can't be locked or removed, which would make it easy to lock yourself out
+            @if user.uid != 0 {
+                .management-form {
+                    server-swap action="/management/users/lock" method="POST" trigger="submit" target="#user-result" {
+                        form {
+                            input type="hidden" name="name" value=(user.name);
+                            @if user.locked == Some(true) {
+                                input type="hidden" name="lock" value="false";
+                                input type="submit" value="Unlock";
+                            } @else {
+                                input type="hidden" name="lock" value="true";
+                                input type="submit" value="Lock";
+                            }
+                        }
+                    }
+                    server-swap action="/management/users/remove" method="POST" trigger="submit" target="#user-result"
+                        confirm={"Remove user " (user.name) "?"} {
+                        form {
+                            input type="hidden" name="name" value=(user.name);
+                            label {
+                                input type="checkbox" name="remove_home" value="true";
+                                " Also remove home directory"
+                            }
+                            " "
+                            input type="submit" value="Remove User";
+                        }
+                    }
+                }
+            }
+
+            #user-result {}
+        }
+
+        (keys_section(&user.name, &keys, html! {}))
+    };
+
+    template(&req, content)
+}
+
+pub async fn add(mut req: ServerRequest) -> Result<ServerResponse, ServerResponse> {
+    req.check_login()?;
+    req.check_admin()?;
+
+    let form: AddUserForm = req.extract_form().await?;
+
+    let result = if form.password != form.confirm {
+        Err("passwords don't match".into())
+    } else {
+        let action = UserAction::Add {
+            name: form.name.trim().to_string(),
+            password: form.password,
+            groups: form
+                .groups
+                .split(',')
+                .map(str::trim)
+                .filter(|x| !x.is_empty())
+                .map(String::from)
+                .collect(),
+        };
+        send_req!(req, UserAction(action))?
+    };
+
+    template(&req, action_result("Adding the user", &result))
+}
+
+pub async fn password(mut req: ServerRequest) -> Result<ServerResponse, ServerResponse> {
+    req.check_login()?;
+    req.check_admin()?;
+
+    let form: PasswordForm = req.extract_form().await?;
+
+    let result = if form.password != form.confirm {
+        Err("passwords don't match".into())
+    } else {
+        let action = UserAction::SetPassword {
+            name: form.name,
+            password: form.password,
+        };
+        send_req!(req, UserAction(action))?
+    };
+
+    template(&req, action_result("Changing the password", &result))
+}
+
+pub async fn lock(mut req: ServerRequest) -> Result<ServerResponse, ServerResponse> {
+    req.check_login()?;
+    req.check_admin()?;
+
+    let form: LockForm = req.extract_form().await?;
+
+    let (action, label) = if form.lock {
+        (UserAction::Lock(form.name), "Locking the account")
+    } else {
+        (UserAction::Unlock(form.name), "Unlocking the account")
+    };
+    let result = send_req!(req, UserAction(action))?;
+
+    template(&req, action_result(label, &result))
+}
+
+pub async fn remove(mut req: ServerRequest) -> Result<ServerResponse, ServerResponse> {
+    req.check_login()?;
+    req.check_admin()?;
+
+    let form: RemoveForm = req.extract_form().await?;
+
+    let action = UserAction::Remove {
+        name: form.name,
+        remove_home: form.remove_home,
+    };
+    let result = send_req!(req, UserAction(action))?;
+
+    template(&req, action_result("Removing the user", &result))
+}
+
+pub async fn add_key(mut req: ServerRequest) -> Result<ServerResponse, ServerResponse> {
+    req.check_login()?;
+    req.check_admin()?;
+
+    let form: KeyForm = req.extract_form().await?;
+
+    let action = UserAction::AddKey {
+        name: form.name.clone(),
+        key: form.key.trim().to_string(),
+    };
+    let result = send_req!(req, UserAction(action))?;
+    let keys = send_req!(req, AuthorizedKeys(form.name.clone()))?;
+
+    template(
+        &req,
+        keys_section(&form.name, &keys, result_banner("Adding the key", &result)),
+    )
+}
+
+pub async fn remove_key(mut req: ServerRequest) -> Result<ServerResponse, ServerResponse> {
+    req.check_login()?;
+    req.check_admin()?;
+
+    let form: KeyForm = req.extract_form().await?;
+
+    let action = UserAction::RemoveKey {
+        name: form.name.clone(),
+        key: form.key,
+    };
+    let result = send_req!(req, UserAction(action))?;
+    let keys = send_req!(req, AuthorizedKeys(form.name.clone()))?;
+
+    template(
+        &req,
+        keys_section(
+            &form.name,
+            &keys,
+            result_banner("Removing the key", &result),
+        ),
+    )
+}
@@ -16,6 +16,16 @@ css_assets=(
   "$asset_path/css/process.css"
   "$asset_path/css/management.css"
   "$asset_path/css/software.css"
+  "$asset_path/css/service.css"
+  "$asset_path/css/logs.css"
+  "$asset_path/css/files.css"
+  "$asset_path/css/editor.css"
+  "$asset_path/css/updates.css"
+  "$asset_path/css/schedule.css"
+  "$asset_path/css/network.css"
+  "$asset_path/css/firewall.css"
+  "$asset_path/css/containers.css"
+  "$asset_path/css/overview.css"
   "$asset_path/css/xterm-5.5.0.css"
 )
 